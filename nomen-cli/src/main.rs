@@ -2,8 +2,11 @@
 
 mod nostr;
 
+use bitcoin::Txid;
 use clap::Parser;
-use nomen_core::TransferBuilder;
+use nomen_core::{
+    NameEntity, NsidBuilder, Records, RecordsBuilder, TransferAuthorization, TransferBuilder,
+};
 use nostr::{Npub, Nsec};
 use nostr_sdk::{Keys, ToBech32, UnsignedEvent};
 use secp256k1::{Secp256k1, SecretKey, XOnlyPublicKey};
@@ -19,7 +22,23 @@ pub fn main() -> anyhow::Result<()> {
 fn handle_ops(ops: Ops) -> anyhow::Result<()> {
     match ops.command {
         Commands::Keys { pubkey, nostr } => cmd_keys(pubkey, nostr)?,
-        Commands::Transfer { old, new, name } => cmd_transfer(old, new, name)?,
+        Commands::Transfer {
+            old,
+            new,
+            name,
+            txid,
+        } => cmd_transfer(old, new, name, txid)?,
+        Commands::Records {
+            owner,
+            name,
+            records,
+        } => cmd_records(owner, name, records)?,
+        Commands::Nsid {
+            nsid,
+            name,
+            owner,
+            relays,
+        } => cmd_nsid(nsid, name, owner, relays)?,
     }
 
     Ok(())
@@ -45,18 +64,64 @@ fn cmd_keys(pubkey: bool, nostr: bool) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn cmd_transfer(old: Nsec, new: Npub, name: String) -> anyhow::Result<()> {
+fn cmd_transfer(old: Nsec, new: Npub, name: String, txid: Option<Txid>) -> anyhow::Result<()> {
     let tb = TransferBuilder {
         new_pubkey: new.as_ref(),
         name: &name,
     };
     let keys = nostr_sdk::Keys::new(*old.as_ref());
+    if let Some(txid) = txid {
+        let auth = TransferAuthorization {
+            name: &name,
+            new_owner: new.as_ref(),
+            transfer_txid: &txid,
+        };
+        println!("{}", hex::encode(auth.signature_op_return(&keys)?));
+        return Ok(());
+    }
     let or1 = tb.transfer_op_return();
     let or2 = tb.signature_op_return(keys)?;
     println!("{}\n{}", hex::encode(or1), hex::encode(or2));
     Ok(())
 }
 
+fn cmd_records(owner: Nsec, name: String, records: Vec<String>) -> anyhow::Result<()> {
+    let records: Records = records.join("\n").parse()?;
+    let keys = nostr_sdk::Keys::new(*owner.as_ref());
+    let event = RecordsBuilder::new(&keys.public_key(), &name, &records).signed_event(&keys)?;
+    println!("{}", event.as_json());
+    Ok(())
+}
+
+fn cmd_nsid(
+    nsid: Option<NameEntity>,
+    name: Option<String>,
+    owner: Option<Npub>,
+    relays: Vec<String>,
+) -> anyhow::Result<()> {
+    let mut entity = match (nsid, name, owner) {
+        (Some(entity), _, _) => entity,
+        (None, Some(name), Some(owner)) => {
+            let mut entity = NameEntity::new(NsidBuilder::new(&name, owner.as_ref()).finalize());
+            entity.name = Some(name);
+            entity
+        }
+        _ => anyhow::bail!("Either an nsid or a name and owner are required"),
+    };
+    entity.relays.extend(relays);
+
+    println!("NSID: {}", entity.nsid);
+    println!("Bech32: {}", entity.nsid.to_bech32());
+    if let Some(name) = &entity.name {
+        println!("Name: {name}");
+    }
+    for relay in &entity.relays {
+        println!("Relay: {relay}");
+    }
+    println!("Entity: {}", entity.to_bech32()?);
+    Ok(())
+}
+
 #[derive(clap::Parser)]
 struct Ops {
     #[command(subcommand)]
@@ -84,5 +149,42 @@ enum Commands {
 
         /// Name to transfer
         name: String,
+
+        /// Txid of the broadcast transfer transaction. When given, only the v2 signature OP_RETURN is generated,
+        /// which is bound to this transfer.
+        #[arg(long)]
+        txid: Option<Txid>,
+    },
+
+    /// Generate a signed record event for a name, ready to publish to relays.
+    Records {
+        /// Hex-encoded or bech32 (nsec) secret key for the owner
+        owner: Nsec,
+
+        /// Name to set records for
+        name: String,
+
+        /// Records as KEY=value pairs
+        records: Vec<String>,
+    },
+
+    /// Decode an nsid given as hex, bech32 (nsid1…) or a name entity (nname1…), or calculate one from a name and
+    /// owner, and print it in each encoding.
+    Nsid {
+        /// Hex, bech32 (nsid1…) or name entity (nname1…) nsid
+        #[arg(required_unless_present_all = ["name", "owner"])]
+        nsid: Option<NameEntity>,
+
+        /// Name to calculate the nsid for
+        #[arg(long, conflicts_with = "nsid")]
+        name: Option<String>,
+
+        /// Hex-encoded or bech32 (npub) public key of the owner
+        #[arg(long, conflicts_with = "nsid")]
+        owner: Option<Npub>,
+
+        /// Indexer relay to include in the name entity, may be repeated
+        #[arg(long = "relay")]
+        relays: Vec<String>,
     },
 }
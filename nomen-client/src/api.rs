@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use secp256k1::XOnlyPublicKey;
+use serde::Deserialize;
+
+use crate::{ClientError, Resolution};
+
+/// Contents of an indexer's `.well-known/nomen.json`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct WellKnown {
+    pub indexer: WellKnownIndexer,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WellKnownIndexer {
+    pub pubkey: XOnlyPublicKey,
+    pub relays: Vec<String>,
+}
+
+pub async fn well_known(http: &reqwest::Client, url: &str) -> Result<WellKnown, ClientError> {
+    let url = format!("{}/.well-known/nomen.json", url.trim_end_matches('/'));
+    Ok(http
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+#[derive(Deserialize)]
+struct NameResponse {
+    pubkey: XOnlyPublicKey,
+    records: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// An indexer queried through its REST API. If `pubkey` is set, the indexer must advertise that key in its
+/// `.well-known/nomen.json`. This pins the indexer's identity but does not authenticate its answers, which are not
+/// signed.
+#[derive(Debug, Clone)]
+pub struct ApiSource {
+    pub url: String,
+    pub pubkey: Option<XOnlyPublicKey>,
+}
+
+impl ApiSource {
+    pub(crate) async fn resolve(
+        &self,
+        http: &reqwest::Client,
+        name: &str,
+    ) -> Result<Option<Resolution>, ClientError> {
+        if let Some(expected) = self.pubkey {
+            let found = well_known(http, &self.url).await?.indexer.pubkey;
+            if found != expected {
+                return Err(ClientError::PubkeyMismatch {
+                    expected: expected.to_string(),
+                    found: found.to_string(),
+                });
+            }
+        }
+
+        let url = format!("{}/api/name", self.url.trim_end_matches('/'));
+        let response = http.get(url).query(&[("name", name)]).send().await?;
+        if response.status().is_success() {
+            let nr: NameResponse = response.json().await?;
+            return Ok(Some(Resolution {
+                name: name.to_string(),
+                pubkey: nr.pubkey,
+                records: nr.records,
+            }));
+        }
+
+        let err: ErrorResponse = response.json().await?;
+        if err.error == "Name not found" {
+            Ok(None)
+        } else {
+            Err(ClientError::Indexer(err.error))
+        }
+    }
+}
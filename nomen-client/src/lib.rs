@@ -0,0 +1,280 @@
+mod api;
+mod relay;
+
+pub use api::*;
+pub use relay::*;
+
+use std::{collections::HashMap, time::Duration};
+
+use futures::future::join_all;
+use secp256k1::XOnlyPublicKey;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ClientError {
+    #[error("http")]
+    Http(#[from] reqwest::Error),
+    #[error("nostr client")]
+    Nostr(#[from] nostr_sdk::client::Error),
+    #[error("json")]
+    Json(#[from] serde_json::Error),
+    #[error("indexer pubkey mismatch: expected {expected}, found {found}")]
+    PubkeyMismatch { expected: String, found: String },
+    #[error("invalid relay index event")]
+    InvalidEvent,
+    #[error("indexer error: {0}")]
+    Indexer(String),
+    #[error("timed out")]
+    Timeout,
+    #[error("no quorum: {agreeing} sources agree, {required} required")]
+    NoQuorum { agreeing: usize, required: usize },
+}
+
+/// A resolved name: its owner and current records.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resolution {
+    pub name: String,
+    pub pubkey: XOnlyPublicKey,
+    pub records: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Source {
+    Api(ApiSource),
+    RelayIndex(RelayIndexSource),
+}
+
+impl Source {
+    /// Resolves `name`, giving up after `timeout`.
+    async fn resolve(
+        &self,
+        http: &reqwest::Client,
+        name: &str,
+        timeout: Duration,
+    ) -> Result<Option<Resolution>, ClientError> {
+        let resolved = async {
+            match self {
+                Source::Api(api) => api.resolve(http, name).await,
+                Source::RelayIndex(ri) => ri.resolve(name, timeout).await,
+            }
+        };
+        tokio::time::timeout(timeout, resolved)
+            .await
+            .map_err(|_| ClientError::Timeout)?
+    }
+}
+
+/// Resolves names against one or more indexers, returning an answer only when at least `quorum` of them agree.
+#[derive(Debug, Clone)]
+pub struct Resolver {
+    sources: Vec<Source>,
+    quorum: usize,
+    timeout: Duration,
+    http: reqwest::Client,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Resolver {
+            sources: vec![],
+            quorum: 1,
+            timeout: Duration::from_secs(10),
+            http: reqwest::Client::new(),
+        }
+    }
+}
+
+impl Resolver {
+    pub fn new() -> Resolver {
+        Resolver::default()
+    }
+
+    pub fn quorum(mut self, quorum: usize) -> Resolver {
+        self.quorum = quorum.max(1);
+        self
+    }
+
+    /// How long each source may take to answer before it is counted as failed.
+    pub fn timeout(mut self, timeout: Duration) -> Resolver {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Query an indexer's REST API at `url`, optionally pinning the indexer pubkey it must advertise, see [`ApiSource`].
+    pub fn api(mut self, url: &str, pubkey: Option<XOnlyPublicKey>) -> Resolver {
+        self.sources.push(Source::Api(ApiSource {
+            url: url.to_string(),
+            pubkey,
+        }));
+        self
+    }
+
+    /// Read the relay index signed by the indexer `pubkey` from `relays`.
+    pub fn relay_index(mut self, pubkey: XOnlyPublicKey, relays: Vec<String>) -> Resolver {
+        self.sources
+            .push(Source::RelayIndex(RelayIndexSource { pubkey, relays }));
+        self
+    }
+
+    /// Read the relay index of the indexer at `url`, using the pubkey and relays from its `.well-known/nomen.json`.
+    pub async fn discover(self, url: &str) -> Result<Resolver, ClientError> {
+        let wk = tokio::time::timeout(self.timeout, well_known(&self.http, url))
+            .await
+            .map_err(|_| ClientError::Timeout)??;
+        Ok(self.relay_index(wk.indexer.pubkey, wk.indexer.relays))
+    }
+
+    pub fn sources(&self) -> &[Source] {
+        &self.sources
+    }
+
+    /// Resolves `name`. Returns `Ok(None)` when a quorum agrees the name does not exist.
+    pub async fn resolve(&self, name: &str) -> Result<Option<Resolution>, ClientError> {
+        let answers = join_all(
+            self.sources
+                .iter()
+                .map(|s| s.resolve(&self.http, name, self.timeout)),
+        )
+        .await;
+
+        let mut tally: Vec<(Option<Resolution>, usize)> = vec![];
+        for answer in answers {
+            match answer {
+                Ok(answer) => match tally.iter_mut().find(|(a, _)| *a == answer) {
+                    Some((_, count)) => *count += 1,
+                    None => tally.push((answer, 1)),
+                },
+                Err(err) => tracing::warn!("Source failed to resolve {name}: {err}"),
+            }
+        }
+
+        match tally.into_iter().max_by_key(|(_, count)| *count) {
+            Some((answer, count)) if count >= self.quorum => Ok(answer),
+            best => Err(ClientError::NoQuorum {
+                agreeing: best.map_or(0, |(_, count)| count),
+                required: self.quorum,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use axum::{extract::Query, http::StatusCode, routing::get, Json, Router};
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    const INDEXER_PK: &str = "d57b873363d2233d3cd54453416deff9546df50d963bb1208da37f10a4c23d6f";
+    const OWNER_PK: &str = "60de6fbc4a78209942c62706d904ff9592c2e856f219793f7f73e62fc33bfc18";
+
+    /// Starts a stand-in indexer which knows a single name, `hello-world`, owned by `owner`.
+    fn stand_in(owner: &'static str) -> String {
+        let app = Router::new()
+            .route(
+                "/.well-known/nomen.json",
+                get(|| async { Json(json!({"indexer": {"pubkey": INDEXER_PK, "relays": []}})) }),
+            )
+            .route(
+                "/api/name",
+                get(move |Query(q): Query<HashMap<String, String>>| async move {
+                    if q.get("name").map(String::as_str) == Some("hello-world") {
+                        (
+                            StatusCode::OK,
+                            Json(json!({"pubkey": owner, "records": {"IP4": "127.0.0.1"}})),
+                        )
+                    } else {
+                        (
+                            StatusCode::BAD_REQUEST,
+                            Json::<Value>(json!({"error": "Name not found"})),
+                        )
+                    }
+                }),
+            );
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+        url
+    }
+
+    #[tokio::test]
+    async fn test_single_indexer() {
+        let resolver = Resolver::new().api(&stand_in(OWNER_PK), None);
+        let r = resolver.resolve("hello-world").await.unwrap().unwrap();
+        assert_eq!(r.pubkey.to_string(), OWNER_PK);
+        assert_eq!(r.records["IP4"], "127.0.0.1");
+        assert!(resolver.resolve("missing").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_quorum() {
+        let resolver = Resolver::new()
+            .quorum(2)
+            .api(&stand_in(OWNER_PK), None)
+            .api(&stand_in(OWNER_PK), None)
+            .api(&stand_in(INDEXER_PK), None);
+        let r = resolver.resolve("hello-world").await.unwrap().unwrap();
+        assert_eq!(r.pubkey.to_string(), OWNER_PK);
+
+        let resolver = resolver.quorum(3);
+        assert!(matches!(
+            resolver.resolve("hello-world").await,
+            Err(ClientError::NoQuorum {
+                agreeing: 2,
+                required: 3
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_pinned_pubkey() {
+        let url = stand_in(OWNER_PK);
+        let resolver = Resolver::new().api(&url, Some(INDEXER_PK.parse().unwrap()));
+        assert!(resolver.resolve("hello-world").await.unwrap().is_some());
+
+        let resolver = Resolver::new().api(&url, Some(OWNER_PK.parse().unwrap()));
+        assert!(resolver.resolve("hello-world").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hung_indexer() {
+        let app = Router::new().route("/api/name", get(futures::future::pending::<Json<Value>>));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let hung = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+
+        let resolver = Resolver::new()
+            .api(&hung, None)
+            .api(&stand_in(OWNER_PK), None)
+            .timeout(Duration::from_millis(200));
+        let r = resolver.resolve("hello-world").await.unwrap().unwrap();
+        assert_eq!(r.pubkey.to_string(), OWNER_PK);
+
+        let resolver = resolver.quorum(2);
+        assert!(matches!(
+            resolver.resolve("hello-world").await,
+            Err(ClientError::NoQuorum {
+                agreeing: 1,
+                required: 2
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_discover() {
+        let resolver = Resolver::new().discover(&stand_in(OWNER_PK)).await.unwrap();
+        assert!(matches!(
+            resolver.sources(),
+            [Source::RelayIndex(ri)] if ri.pubkey.to_string() == INDEXER_PK
+        ));
+    }
+}
@@ -0,0 +1,107 @@
+use std::{collections::HashMap, time::Duration};
+
+use nomen_core::NameKind;
+use nostr_sdk::{Event, Filter, Keys};
+use secp256k1::XOnlyPublicKey;
+use serde::Deserialize;
+
+use crate::{ClientError, Resolution};
+
+/// An indexer read through the kind 38301 relay index it publishes to Nostr relays.
+#[derive(Debug, Clone)]
+pub struct RelayIndexSource {
+    pub pubkey: XOnlyPublicKey,
+    pub relays: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Content {
+    name: String,
+    pubkey: XOnlyPublicKey,
+    records: HashMap<String, String>,
+}
+
+impl RelayIndexSource {
+    pub(crate) async fn resolve(
+        &self,
+        name: &str,
+        timeout: Duration,
+    ) -> Result<Option<Resolution>, ClientError> {
+        let client = nostr_sdk::Client::new(&Keys::generate());
+        for relay in &self.relays {
+            client.add_relay(relay.as_str(), None).await?;
+        }
+        client.connect().await;
+        let filter = Filter::new()
+            .kind(NameKind::RelayIndex.into())
+            .author(self.pubkey.to_string())
+            .identifier(name);
+        let events = client.get_events_of(vec![filter], Some(timeout)).await?;
+        client.disconnect().await.ok();
+
+        events
+            .iter()
+            .filter(|e| e.pubkey == self.pubkey && e.verify().is_ok())
+            .max_by_key(|e| e.created_at)
+            .map(|e| parse_relay_index_event(e, &self.pubkey, name))
+            .transpose()
+    }
+}
+
+/// Parses a kind 38301 relay index event for `name`, checking that it was signed by the indexer `pubkey`.
+pub fn parse_relay_index_event(
+    event: &Event,
+    pubkey: &XOnlyPublicKey,
+    name: &str,
+) -> Result<Resolution, ClientError> {
+    if event.pubkey != *pubkey || event.verify().is_err() {
+        return Err(ClientError::InvalidEvent);
+    }
+    if !matches!(NameKind::try_from(event.kind), Ok(NameKind::RelayIndex)) {
+        return Err(ClientError::InvalidEvent);
+    }
+    let content: Content = serde_json::from_str(&event.content)?;
+    if content.name != name {
+        return Err(ClientError::InvalidEvent);
+    }
+    Ok(Resolution {
+        name: content.name,
+        pubkey: content.pubkey,
+        records: content.records,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::{EventBuilder, Tag};
+
+    use super::*;
+
+    fn event(keys: &Keys, kind: NameKind, name: &str) -> Event {
+        let content = serde_json::json!({
+            "name": name,
+            "pubkey": "60de6fbc4a78209942c62706d904ff9592c2e856f219793f7f73e62fc33bfc18",
+            "records": {"IP4": "127.0.0.1"},
+        });
+        EventBuilder::new(
+            kind.into(),
+            content.to_string(),
+            &[Tag::Identifier(name.to_string())],
+        )
+        .to_event(keys)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_relay_index_event() {
+        let keys = Keys::generate();
+        let e = event(&keys, NameKind::RelayIndex, "hello-world");
+        let r = parse_relay_index_event(&e, &keys.public_key(), "hello-world").unwrap();
+        assert_eq!(r.records["IP4"], "127.0.0.1");
+
+        assert!(parse_relay_index_event(&e, &keys.public_key(), "other").is_err());
+        assert!(
+            parse_relay_index_event(&e, &Keys::generate().public_key(), "hello-world").is_err()
+        );
+    }
+}
@@ -2,10 +2,12 @@ use std::path::PathBuf;
 
 use anyhow::bail;
 use bitcoin::Network;
+use nomen_core::TRANSFER_EXPIRY_BLOCKS;
 use nostr_sdk::{
     prelude::{FromSkStr, ToBech32},
     Options,
 };
+use secp256k1::XOnlyPublicKey;
 use sqlx::{sqlite, SqlitePool};
 
 use crate::util::Nsec;
@@ -93,6 +95,9 @@ impl Config {
         if self.missing_secret_key() {
             bail!("Config: Secret key required for relay publising");
         }
+        if self.network() == Network::Bitcoin && self.file.server.transfer_expiry_blocks.is_some() {
+            bail!("Config: transfer_expiry_blocks can only be overridden on test networks");
+        }
         Ok(())
     }
 
@@ -151,6 +156,14 @@ impl Config {
         })
     }
 
+    pub fn send_interval_ms(&self) -> u64 {
+        self.file.nostr.send_interval_ms.unwrap_or(250)
+    }
+
+    pub fn max_send_attempts(&self) -> i64 {
+        self.file.nostr.max_send_attempts.unwrap_or(8)
+    }
+
     pub fn network(&self) -> Network {
         self.file.rpc.network.unwrap_or(Network::Bitcoin)
     }
@@ -178,4 +191,52 @@ impl Config {
     pub fn api(&self) -> bool {
         self.file.server.api.unwrap_or(true)
     }
+
+    pub fn audit(&self) -> bool {
+        self.file.server.audit.unwrap_or_default()
+    }
+
+    pub fn audit_delay(&self) -> u64 {
+        self.file.server.audit_delay.unwrap_or(300)
+    }
+
+    pub fn relay(&self) -> bool {
+        self.file.server.relay.unwrap_or_default()
+    }
+
+    pub fn relay_accept(&self) -> bool {
+        self.file.server.relay_accept.unwrap_or_default()
+    }
+
+    /// `(activation_height, blocks)`: names lapse `blocks` after they were created or last renewed, counting from
+    /// `activation_height` at the earliest. Names never expire unless both are configured.
+    pub fn name_expiry(&self) -> Option<(usize, usize)> {
+        self.file
+            .server
+            .expiry_activation_height
+            .zip(self.file.server.expiry_blocks)
+    }
+
+    /// Blocks a transfer may wait for its signature, see [`TRANSFER_EXPIRY_BLOCKS`]. Test networks may shorten it
+    /// with `transfer_expiry_blocks`.
+    pub fn transfer_expiry_blocks(&self) -> usize {
+        match self.network() {
+            Network::Bitcoin => TRANSFER_EXPIRY_BLOCKS,
+            _ => self
+                .file
+                .server
+                .transfer_expiry_blocks
+                .unwrap_or(TRANSFER_EXPIRY_BLOCKS),
+        }
+    }
+
+    pub fn peers(&self) -> Vec<XOnlyPublicKey> {
+        self.file
+            .nostr
+            .peers
+            .iter()
+            .flatten()
+            .map(|npub| *npub.as_ref())
+            .collect()
+    }
 }
@@ -35,6 +35,28 @@ pub enum Subcommand {
     /// Publish full name index to relay servers
     Publish,
 
+    /// Cross-check the relay indexes published by peer indexers against the local index
+    Audit,
+
+    /// Export conformance vectors from the indexed blocks, see docs/vectors
+    Vectors {
+        /// First block height to export. Default: the first indexed block
+        #[arg(long)]
+        from: Option<i64>,
+
+        /// Last block height to export. Default: the last indexed block
+        #[arg(long)]
+        to: Option<i64>,
+
+        /// Description of the exported scenario
+        #[arg(long, default_value = "Exported scenario")]
+        description: String,
+
+        /// Export the decoded OP_RETURNs instead of an indexing scenario
+        #[arg(long)]
+        op_returns: bool,
+    },
+
     /// Prints the current version of application
     Version,
 }
@@ -4,7 +4,7 @@ use bitcoin::Network;
 use nostr_sdk::Keys;
 use serde::{Deserialize, Serialize};
 
-use crate::util::Nsec;
+use crate::util::{Npub, Nsec};
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct ServerConfig {
@@ -14,6 +14,13 @@ pub struct ServerConfig {
     pub indexer: Option<bool>,
     pub indexer_delay: Option<u64>,
     pub confirmations: Option<usize>,
+    pub audit: Option<bool>,
+    pub audit_delay: Option<u64>,
+    pub relay: Option<bool>,
+    pub relay_accept: Option<bool>,
+    pub expiry_activation_height: Option<usize>,
+    pub expiry_blocks: Option<usize>,
+    pub transfer_expiry_blocks: Option<usize>,
 }
 impl ServerConfig {
     fn example() -> ServerConfig {
@@ -24,6 +31,13 @@ impl ServerConfig {
             indexer: Some(true),
             indexer_delay: Some(30),
             confirmations: Some(3),
+            audit: Some(false),
+            audit_delay: Some(300),
+            relay: Some(false),
+            relay_accept: Some(false),
+            expiry_activation_height: None,
+            expiry_blocks: None,
+            transfer_expiry_blocks: None,
         }
     }
 }
@@ -56,6 +70,9 @@ pub struct NostrConfig {
     pub secret: Option<Nsec>,
     pub publish: Option<bool>,
     pub well_known: Option<bool>,
+    pub peers: Option<Vec<Npub>>,
+    pub send_interval_ms: Option<u64>,
+    pub max_send_attempts: Option<i64>,
 }
 impl NostrConfig {
     fn example() -> NostrConfig {
@@ -67,6 +84,9 @@ impl NostrConfig {
                 .map(std::convert::Into::into),
             publish: Some(true),
             well_known: Some(true),
+            peers: Some(vec![]),
+            send_interval_ms: Some(250),
+            max_send_attempts: Some(8),
         }
     }
 }
@@ -0,0 +1,105 @@
+use sqlx::{FromRow, SqlitePool};
+
+#[derive(FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub peer: String,
+    pub name: String,
+    pub kind: String,
+    pub local: Option<String>,
+    pub remote: Option<String>,
+}
+
+#[derive(FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct PeerStatus {
+    pub peer: String,
+    pub status: String,
+    pub checked_at: i64,
+}
+
+/// Replace the mismatches previously recorded for `peer` with the results of a new audit of its complete index.
+pub async fn replace(conn: &SqlitePool, peer: &str, mismatches: &[Mismatch]) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+    sqlx::query("DELETE FROM audit_mismatches WHERE peer = ?;")
+        .bind(peer)
+        .execute(&mut tx)
+        .await?;
+    set_status(&mut tx, peer, "complete").await?;
+    for m in mismatches {
+        sqlx::query(
+            "INSERT INTO audit_mismatches (peer, name, kind, local, remote, checked_at) VALUES (?, ?, ?, ?, ?, unixepoch());",
+        )
+        .bind(&m.peer)
+        .bind(&m.name)
+        .bind(&m.kind)
+        .bind(&m.local)
+        .bind(&m.remote)
+        .execute(&mut tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Records that the index of `peer` couldn't be fetched. Its earlier mismatches are dropped, as they can no longer be
+/// confirmed.
+pub async fn unknown(conn: &SqlitePool, peer: &str) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+    sqlx::query("DELETE FROM audit_mismatches WHERE peer = ?;")
+        .bind(peer)
+        .execute(&mut tx)
+        .await?;
+    set_status(&mut tx, peer, "unknown").await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn set_status(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    peer: &str,
+    status: &str,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO audit_peers (peer, status, checked_at) VALUES (?, ?, unixepoch())
+        ON CONFLICT (peer) DO UPDATE SET status = excluded.status, checked_at = excluded.checked_at;",
+    )
+    .bind(peer)
+    .bind(status)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// The outcome of the last audit of each peer: `complete`, or `unknown` when its index couldn't be fetched.
+pub async fn peers(conn: &SqlitePool) -> anyhow::Result<Vec<PeerStatus>> {
+    let peers = sqlx::query_as::<_, PeerStatus>(
+        "SELECT peer, status, checked_at FROM audit_peers ORDER BY peer;",
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(peers)
+}
+
+pub async fn fetch_all(conn: &SqlitePool) -> anyhow::Result<Vec<Mismatch>> {
+    let mismatches = sqlx::query_as::<_, Mismatch>(
+        "SELECT peer, name, kind, local, remote FROM audit_mismatches ORDER BY name, peer;",
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(mismatches)
+}
+
+pub async fn count(conn: &SqlitePool) -> anyhow::Result<i64> {
+    let (count,) = sqlx::query_as::<_, (i64,)>("SELECT count(*) FROM audit_mismatches;")
+        .fetch_one(conn)
+        .await?;
+    Ok(count)
+}
+
+pub async fn last_audit_time(conn: &SqlitePool) -> anyhow::Result<Option<i64>> {
+    let row = sqlx::query_as::<_, (i64,)>(
+        "SELECT created_at FROM event_log WHERE type = 'audit' ORDER BY created_at DESC LIMIT 1;",
+    )
+    .fetch_optional(conn)
+    .await?;
+    Ok(row.map(|(t,)| t))
+}
@@ -0,0 +1,111 @@
+use sqlx::{FromRow, SqlitePool};
+
+#[derive(Debug)]
+pub struct Child {
+    pub name: String,
+    pub pubkey: String,
+    pub nsid: String,
+}
+
+#[derive(Debug)]
+pub struct Delegation {
+    pub parent: String,
+    pub parent_pubkey: String,
+    pub event_id: String,
+    pub created_at: i64,
+    pub raw_event: String,
+    pub children: Vec<Child>,
+}
+
+/// Saves a child delegation event, replacing the children previously delegated by the same parent owner. Older
+/// delegations are ignored. Returns whether the delegation was saved.
+pub async fn save(conn: &SqlitePool, delegation: &Delegation) -> anyhow::Result<bool> {
+    let mut tx = conn.begin().await?;
+    let updated = sqlx::query(
+        "INSERT INTO child_delegations (parent, parent_pubkey, event_id, created_at, raw_event, indexed_at)
+        VALUES (?, ?, ?, ?, ?, unixepoch())
+        ON CONFLICT (parent, parent_pubkey) DO UPDATE SET
+        event_id = excluded.event_id,
+        created_at = excluded.created_at,
+        raw_event = excluded.raw_event,
+        indexed_at = excluded.indexed_at
+        WHERE excluded.created_at > created_at;",
+    )
+    .bind(&delegation.parent)
+    .bind(&delegation.parent_pubkey)
+    .bind(&delegation.event_id)
+    .bind(delegation.created_at)
+    .bind(&delegation.raw_event)
+    .execute(&mut tx)
+    .await?
+    .rows_affected();
+    if updated == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query("DELETE FROM child_names WHERE parent = ? AND parent_pubkey = ?;")
+        .bind(&delegation.parent)
+        .bind(&delegation.parent_pubkey)
+        .execute(&mut tx)
+        .await?;
+    for child in &delegation.children {
+        sqlx::query(
+            "INSERT OR REPLACE INTO child_names (name, pubkey, nsid, parent, parent_pubkey) VALUES (?, ?, ?, ?, ?);",
+        )
+        .bind(&child.name)
+        .bind(&child.pubkey)
+        .bind(&child.nsid)
+        .bind(&delegation.parent)
+        .bind(&delegation.parent_pubkey)
+        .execute(&mut tx)
+        .await?;
+        sqlx::query("INSERT OR IGNORE INTO relay_index_queue (name) VALUES (?)")
+            .bind(&child.name)
+            .execute(&mut tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(true)
+}
+
+pub async fn last_delegation_time(conn: &SqlitePool) -> anyhow::Result<u64> {
+    let (t,) =
+        sqlx::query_as::<_, (i64,)>("SELECT COALESCE(MAX(created_at), 0) FROM child_delegations;")
+            .fetch_one(conn)
+            .await?;
+    Ok(t as u64)
+}
+
+#[derive(FromRow, Debug)]
+pub struct ChildRecords {
+    pub name: String,
+    pub pubkey: String,
+    pub nsid: String,
+    pub parent: String,
+    pub root: String,
+    pub records: String,
+}
+
+/// Resolves a child name by walking the delegations down from its top-level name.
+pub async fn records(conn: &SqlitePool, query: &str) -> anyhow::Result<Option<ChildRecords>> {
+    let records = sqlx::query_as::<_, ChildRecords>(
+        "SELECT name, pubkey, nsid, parent, root, records FROM valid_children_records_vw
+        WHERE name = ? OR nsid = ? LIMIT 1;",
+    )
+    .bind(query)
+    .bind(query)
+    .fetch_optional(conn)
+    .await?;
+    Ok(records)
+}
+
+/// Valid children directly below `parent`, as `(name, pubkey)`.
+pub async fn of_parent(conn: &SqlitePool, parent: &str) -> anyhow::Result<Vec<(String, String)>> {
+    let children = sqlx::query_as::<_, (String, String)>(
+        "SELECT name, pubkey FROM valid_children_vw WHERE parent = ? ORDER BY name;",
+    )
+    .bind(parent)
+    .fetch_all(conn)
+    .await?;
+    Ok(children)
+}
@@ -0,0 +1,23 @@
+use sqlx::{FromRow, SqlitePool};
+
+#[derive(FromRow, Debug)]
+pub struct NameEvent {
+    pub event: String,
+    pub txid: Option<String>,
+    pub blockheight: i64,
+    pub created_at: i64,
+}
+
+/// Events in the history of `name`, newest first.
+pub async fn events(conn: &SqlitePool, name: &str) -> anyhow::Result<Vec<NameEvent>> {
+    let events = sqlx::query_as::<_, NameEvent>(
+        "SELECT event, txid, blockheight, created_at
+        FROM name_history
+        WHERE name = ?
+        ORDER BY blockheight DESC, id DESC;",
+    )
+    .bind(name)
+    .fetch_all(conn)
+    .await?;
+    Ok(events)
+}
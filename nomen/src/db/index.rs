@@ -1,9 +1,11 @@
 #![allow(clippy::module_name_repetitions)]
 
 use bitcoin::{BlockHash, Txid};
-use nomen_core::{Hash160, Nsid, NsidBuilder};
+use nomen_core::{Hash160, Name, Nsid, NsidBuilder, REVEAL_MAX_DELAY, REVEAL_MIN_DELAY};
 use secp256k1::XOnlyPublicKey;
-use sqlx::{Executor, Sqlite, SqlitePool};
+use sqlx::{Executor, FromRow, Sqlite, SqlitePool};
+
+use super::raw::RawBlockchain;
 
 pub struct BlockchainIndex {
     pub protocol: i64,
@@ -35,11 +37,38 @@ pub async fn insert_blockchain_index(
         .bind(index.blockheight as i64)
         .bind(index.txheight as i64)
         .bind(index.vout as i64)
+        .bind(index.name.as_deref().and_then(skeleton))
         .execute(conn)
         .await?;
     Ok(())
 }
 
+/// The skeleton stored with an indexed name, see [`nomen_core::skeleton`].
+fn skeleton(name: &str) -> Option<String> {
+    Name::canonical(name)
+        .ok()
+        .map(|name| nomen_core::skeleton(&name))
+}
+
+/// Stores the skeletons of the names indexed before skeletons were.
+pub async fn fill_skeletons(conn: &SqlitePool) -> anyhow::Result<()> {
+    let names = sqlx::query_as::<_, (String,)>(
+        "SELECT DISTINCT name FROM blockchain_index WHERE name IS NOT NULL AND skeleton IS NULL;",
+    )
+    .fetch_all(conn)
+    .await?;
+    let mut tx = conn.begin().await?;
+    for (name,) in names {
+        sqlx::query("UPDATE blockchain_index SET skeleton = ? WHERE name = ?;")
+            .bind(skeleton(&name))
+            .bind(&name)
+            .execute(&mut tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
 pub async fn insert_transfer_cache(
     conn: impl Executor<'_, Database = Sqlite>,
     index: &BlockchainIndex,
@@ -123,10 +152,11 @@ pub async fn upgrade_v0_to_v1(
 
     let updated = sqlx::query(
         "UPDATE blockchain_index
-        SET name = ?, pubkey = ?, protocol = 1, v1_upgrade_blockheight = ?, v1_upgrade_txid = ?
+        SET name = ?, skeleton = ?, pubkey = ?, protocol = 1, v1_upgrade_blockheight = ?, v1_upgrade_txid = ?
         WHERE fingerprint = ? AND nsid = ? AND protocol = 0;",
     )
     .bind(name)
+    .bind(skeleton(name))
     .bind(hex::encode(pubkey.serialize()))
     .bind(blockheight as i64)
     .bind(hex::encode(txid))
@@ -149,9 +179,10 @@ pub async fn update_v0_index(
     nsid: Nsid,
 ) -> anyhow::Result<()> {
     sqlx::query(
-        "UPDATE blockchain_index SET name = ?, pubkey = ? WHERE protocol = 0 AND nsid = ?;",
+        "UPDATE blockchain_index SET name = ?, skeleton = ?, pubkey = ? WHERE protocol = 0 AND nsid = ?;",
     )
     .bind(name)
+    .bind(skeleton(name))
     .bind(pubkey.to_string())
     .bind(hex::encode(nsid.as_slice()))
     .execute(conn)
@@ -160,22 +191,466 @@ pub async fn update_v0_index(
     Ok(())
 }
 
-pub async fn delete_from_transfer_cache(
+#[derive(FromRow, Debug)]
+pub struct TransferCandidate {
+    pub id: i64,
+    pub name: String,
+    pub fingerprint: String,
+    pub new_owner: String,
+    pub txid: String,
+}
+
+/// The pending transfers which the signature in `signature` may complete, in the order they should be tried:
+/// transfers earlier in the signature's own transaction, then transfers in the transactions it spends in block order.
+/// A signature is never matched to a transfer in an unrelated transaction.
+pub async fn transfer_candidates(
+    conn: impl Executor<'_, Database = Sqlite>,
+    signature: &RawBlockchain,
+) -> anyhow::Result<Vec<TransferCandidate>> {
+    let candidates = sqlx::query_as::<_, TransferCandidate>(
+        "SELECT id, name, fingerprint, pubkey AS new_owner, txid
+        FROM transfer_cache
+        WHERE (txid = ?1 OR txid IN (SELECT prev_txid FROM raw_blockchain_inputs WHERE txid = ?1))
+        AND (blockheight, txheight, vout) < (?2, ?3, ?4)
+        ORDER BY txid = ?1 DESC, blockheight, txheight, vout;",
+    )
+    .bind(signature.txid.to_string())
+    .bind(signature.blockheight as i64)
+    .bind(signature.txheight as i64)
+    .bind(signature.vout as i64)
+    .fetch_all(conn)
+    .await?;
+    Ok(candidates)
+}
+
+/// The owner of `name` at `height`, by the same ranking as `valid_names_vw` but looked up by fingerprint.
+pub async fn current_owner(
+    conn: impl Executor<'_, Database = Sqlite>,
+    fingerprint: &str,
+    name: &str,
+    height: usize,
+) -> anyhow::Result<Option<String>> {
+    let owner = sqlx::query_as::<_, (Option<String>, Option<String>)>(
+        "SELECT name, pubkey FROM claims_vw
+        WHERE fingerprint = ? AND (expires_at IS NULL OR expires_at > ?)
+        ORDER BY claim_blockheight, claim_txheight, claim_vout
+        LIMIT 1;",
+    )
+    .bind(fingerprint)
+    .bind(height as i64)
+    .fetch_optional(conn)
+    .await?;
+    Ok(owner.and_then(|(owner_name, pubkey)| {
+        if owner_name.as_deref() == Some(name) {
+            pubkey
+        } else {
+            None
+        }
+    }))
+}
+
+pub async fn complete_transfer_cache(
     conn: &sqlx::Pool<sqlx::Sqlite>,
     id: i64,
+    signature_txid: Txid,
+    signature_blockheight: usize,
 ) -> Result<(), anyhow::Error> {
-    tracing::debug!("DELETING transfer_cache with id {id}");
+    tracing::debug!("COMPLETING transfer_cache with id {id}");
+    let mut tx = conn.begin().await?;
+    sqlx::query(
+        "INSERT INTO old_transfer_cache
+        (id, protocol, fingerprint, nsid, name, pubkey, blockhash, txid, blocktime, blockheight, txheight, vout, indexed_at, outcome, signature_txid, signature_blockheight)
+        SELECT id, protocol, fingerprint, nsid, name, pubkey, blockhash, txid, blocktime, blockheight, txheight, vout, indexed_at, 'completed', ?, ?
+        FROM transfer_cache WHERE id = ?;",
+    )
+    .bind(signature_txid.to_string())
+    .bind(signature_blockheight as i64)
+    .bind(id)
+    .execute(&mut tx)
+    .await?;
     sqlx::query("DELETE FROM transfer_cache WHERE id = ?;")
         .bind(id)
-        .execute(conn)
+        .execute(&mut tx)
         .await?;
+    tx.commit().await?;
     Ok(())
 }
 
-pub async fn reindex(
-    conn: impl sqlx::Executor<'_, Database = Sqlite> + Copy,
+/// The first index height at which a pending transfer expires, if any are pending.
+pub async fn next_transfer_expiry(
+    conn: &SqlitePool,
+    expiry_blocks: usize,
+) -> anyhow::Result<Option<i64>> {
+    let (height,) =
+        sqlx::query_as::<_, (Option<i64>,)>("SELECT MIN(blockheight) + ? + 1 FROM transfer_cache;")
+            .bind(expiry_blocks as i64)
+            .fetch_one(conn)
+            .await?;
+    Ok(height)
+}
+
+/// Expires the transfers which have waited more than `expiry_blocks` for a signature at `index_height`, and records
+/// each expiration in the name history. Returns the number of expired transfers.
+pub async fn expire_transfer_cache(
+    conn: &sqlx::Pool<sqlx::Sqlite>,
+    index_height: i64,
+    expiry_blocks: usize,
+) -> anyhow::Result<u64> {
+    let mut tx = conn.begin().await?;
+    sqlx::query(
+        "INSERT INTO name_history (name, fingerprint, event, txid, blockheight, created_at)
+        SELECT name, fingerprint, 'transfer_expired', txid, ?1, unixepoch()
+        FROM transfer_cache WHERE blockheight < (?1 - ?2)
+        ORDER BY blockheight, txheight, vout;",
+    )
+    .bind(index_height)
+    .bind(expiry_blocks as i64)
+    .execute(&mut tx)
+    .await?;
+    sqlx::query(
+        "INSERT INTO old_transfer_cache
+        (id, protocol, fingerprint, nsid, name, pubkey, blockhash, txid, blocktime, blockheight, txheight, vout, indexed_at, outcome, expired_at)
+        SELECT id, protocol, fingerprint, nsid, name, pubkey, blockhash, txid, blocktime, blockheight, txheight, vout, indexed_at, 'expired', ?1
+        FROM transfer_cache WHERE blockheight < (?1 - ?2);",
+    )
+    .bind(index_height)
+    .bind(expiry_blocks as i64)
+    .execute(&mut tx)
+    .await?;
+    let expired = sqlx::query("DELETE FROM transfer_cache WHERE blockheight < (? - ?);")
+        .bind(index_height)
+        .bind(expiry_blocks as i64)
+        .execute(&mut tx)
+        .await?;
+    tx.commit().await?;
+    Ok(expired.rows_affected())
+}
+
+/// Undoes the transfer expirations at or above `blockheight`, returning the transfers to `transfer_cache`, so that
+/// they are expired again against the blocks which replace them.
+pub async fn unexpire_transfers(
+    conn: &sqlx::Pool<sqlx::Sqlite>,
     blockheight: i64,
 ) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+    sqlx::query(
+        "INSERT INTO transfer_cache
+        (id, protocol, fingerprint, nsid, name, pubkey, blockhash, txid, blocktime, blockheight, txheight, vout, indexed_at)
+        SELECT id, protocol, fingerprint, nsid, name, pubkey, blockhash, txid, blocktime, blockheight, txheight, vout, indexed_at
+        FROM old_transfer_cache WHERE outcome = 'expired' AND expired_at >= ?;",
+    )
+    .bind(blockheight)
+    .execute(&mut tx)
+    .await?;
+    sqlx::query("DELETE FROM old_transfer_cache WHERE outcome = 'expired' AND expired_at >= ?;")
+        .bind(blockheight)
+        .execute(&mut tx)
+        .await?;
+    sqlx::query("DELETE FROM name_history WHERE event = 'transfer_expired' AND blockheight >= ?;")
+        .bind(blockheight)
+        .execute(&mut tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn insert_commit(
+    conn: impl Executor<'_, Database = Sqlite>,
+    commitment: &[u8; 32],
+    row: &RawBlockchain,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO name_commits (commitment, blockhash, txid, blocktime, blockheight, txheight, vout, indexed_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, unixepoch());",
+    )
+    .bind(hex::encode(commitment))
+    .bind(row.blockhash.to_string())
+    .bind(row.txid.to_string())
+    .bind(row.blocktime as i64)
+    .bind(row.blockheight as i64)
+    .bind(row.txheight as i64)
+    .bind(row.vout as i64)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+#[derive(FromRow, Debug)]
+pub struct NameCommit {
+    pub txid: String,
+    pub blockheight: i64,
+    pub txheight: i64,
+    pub vout: i64,
+}
+
+/// The earliest commit to `commitment` which a reveal at `reveal_height` may open.
+pub async fn revealable_commit(
+    conn: impl Executor<'_, Database = Sqlite>,
+    commitment: &[u8; 32],
+    reveal_height: usize,
+) -> anyhow::Result<Option<NameCommit>> {
+    let commit = sqlx::query_as::<_, NameCommit>(
+        "SELECT txid, blockheight, txheight, vout FROM name_commits
+        WHERE commitment = ? AND blockheight BETWEEN ? AND ?
+        ORDER BY blockheight, txheight, vout LIMIT 1;",
+    )
+    .bind(hex::encode(commitment))
+    .bind(reveal_height as i64 - REVEAL_MAX_DELAY as i64)
+    .bind(reveal_height as i64 - REVEAL_MIN_DELAY as i64)
+    .fetch_optional(conn)
+    .await?;
+    Ok(commit)
+}
+
+/// Ranks the name created by the output `txid:vout` by the position of its commit.
+pub async fn set_commit(
+    conn: impl Executor<'_, Database = Sqlite>,
+    txid: &Txid,
+    vout: usize,
+    commit: &NameCommit,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "UPDATE blockchain_index SET commit_txid = ?, commit_blockheight = ?, commit_txheight = ?, commit_vout = ?
+        WHERE txid = ? AND vout = ?;",
+    )
+    .bind(&commit.txid)
+    .bind(commit.blockheight)
+    .bind(commit.txheight)
+    .bind(commit.vout)
+    .bind(txid.to_string())
+    .bind(vout as i64)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Marks the claims on `fingerprint` to be recomputed by [`update_claims`].
+pub async fn mark_claims(
+    conn: impl Executor<'_, Database = Sqlite>,
+    fingerprint: &[u8; 5],
+) -> anyhow::Result<()> {
+    sqlx::query("INSERT OR IGNORE INTO stale_claims (fingerprint) VALUES (?);")
+        .bind(hex::encode(fingerprint))
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Recomputes the claims marked by [`mark_claims`]: when each expires from the expiry configuration, see
+/// [`Config::name_expiry`](crate::config::Config::name_expiry), and which are eligible after expiries and releases.
+/// Every claim is recomputed once when the expiry configuration changes.
+pub async fn update_claims(
+    conn: &SqlitePool,
+    expiry: Option<(usize, usize)>,
+) -> anyhow::Result<()> {
+    let setting = expiry.map_or(String::new(), |(activation_height, blocks)| {
+        format!("{activation_height}:{blocks}")
+    });
+    let last = sqlx::query_as::<_, (String,)>(
+        "SELECT data FROM event_log WHERE type = 'name_expiry' ORDER BY id DESC LIMIT 1;",
+    )
+    .fetch_optional(conn)
+    .await?;
+    if last
+        .as_ref()
+        .map_or(!setting.is_empty(), |(data,)| *data != setting)
+    {
+        sqlx::query("INSERT OR IGNORE INTO stale_claims (fingerprint) SELECT DISTINCT fingerprint FROM blockchain_index;")
+            .execute(conn)
+            .await?;
+        super::event_log::save(conn, "name_expiry", &setting).await?;
+    }
+
+    let stale = sqlx::query_as::<_, (String,)>("SELECT fingerprint FROM stale_claims;")
+        .fetch_all(conn)
+        .await?;
+    let mut tx = conn.begin().await?;
+    for (fingerprint,) in &stale {
+        update_fingerprint_claims(&mut tx, expiry, fingerprint).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Recomputes the claims on `fingerprint` if they are marked, so that a renewal or release sees the claims indexed
+/// before it in the same run.
+pub async fn update_name_claims(
+    conn: &SqlitePool,
+    expiry: Option<(usize, usize)>,
+    fingerprint: &[u8; 5],
+) -> anyhow::Result<()> {
+    let fingerprint = hex::encode(fingerprint);
+    let mut tx = conn.begin().await?;
+    let stale = sqlx::query("SELECT 1 FROM stale_claims WHERE fingerprint = ?;")
+        .bind(&fingerprint)
+        .fetch_optional(&mut tx)
+        .await?;
+    if stale.is_some() {
+        update_fingerprint_claims(&mut tx, expiry, &fingerprint).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn update_fingerprint_claims(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    expiry: Option<(usize, usize)>,
+    fingerprint: &str,
+) -> anyhow::Result<()> {
+    match expiry {
+        Some((activation_height, blocks)) => {
+            sqlx::query(
+                "UPDATE blockchain_index SET expires_at = MAX(
+                    COALESCE((SELECT MAX(r.blockheight) FROM name_renewals r WHERE r.index_id = blockchain_index.id), blockheight),
+                    ?
+                ) + ?
+                WHERE fingerprint = ?;",
+            )
+            .bind(activation_height as i64)
+            .bind(blocks as i64)
+            .bind(fingerprint)
+            .execute(&mut *tx)
+            .await?;
+        }
+        None => {
+            sqlx::query("UPDATE blockchain_index SET expires_at = NULL WHERE fingerprint = ?;")
+                .bind(fingerprint)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    // Walk the claims on the name in order. A claim is eligible when the last eligible claim had lapsed or been
+    // released by the time it was made.
+    let claims = sqlx::query_as::<_, (i64, i64, Option<i64>, Option<bool>)>(
+        "SELECT id, COALESCE(commit_blockheight, blockheight),
+            MIN(COALESCE(expires_at, released_at), COALESCE(released_at, expires_at)), eligible
+        FROM (
+            SELECT *, (SELECT MIN(r.blockheight) FROM name_releases r WHERE r.index_id = b.id) AS released_at
+            FROM blockchain_index b
+            WHERE fingerprint = ?
+        )
+        ORDER BY COALESCE(commit_blockheight, blockheight), COALESCE(commit_txheight, txheight), COALESCE(commit_vout, vout);",
+    )
+    .bind(fingerprint)
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut holder: Option<Option<i64>> = None;
+    for (id, height, live_until, was_eligible) in claims {
+        let eligible = holder.map_or(true, |live_until| live_until.map_or(false, |h| height >= h));
+        if eligible {
+            holder = Some(live_until);
+        }
+        if was_eligible == Some(eligible) {
+            continue;
+        }
+        sqlx::query("UPDATE blockchain_index SET eligible = ? WHERE id = ?;")
+            .bind(eligible)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query("DELETE FROM stale_claims WHERE fingerprint = ?;")
+        .bind(fingerprint)
+        .execute(&mut *tx)
+        .await?;
+    Ok(())
+}
+
+#[derive(FromRow, Debug)]
+pub struct LiveClaim {
+    pub id: i64,
+    pub name: String,
+    pub pubkey: String,
+    pub txid: String,
+    pub renewed_from: i64,
+}
+
+/// The claim on the name with `fingerprint` which is live at `height`, and so can be renewed or released there.
+pub async fn live_claim(
+    conn: impl Executor<'_, Database = Sqlite>,
+    fingerprint: &[u8; 5],
+    height: usize,
+) -> anyhow::Result<Option<LiveClaim>> {
+    let name = sqlx::query_as::<_, LiveClaim>(
+        "SELECT b.id, b.name, b.pubkey, b.txid,
+            COALESCE((SELECT MAX(r.blockheight) FROM name_renewals r WHERE r.index_id = b.id), b.blockheight) AS renewed_from
+        FROM claims_vw b
+        WHERE b.fingerprint = ? AND b.name IS NOT NULL
+        AND b.blockheight < ? AND (b.expires_at IS NULL OR b.expires_at > ?)
+        ORDER BY b.claim_blockheight, b.claim_txheight, b.claim_vout
+        LIMIT 1;",
+    )
+    .bind(hex::encode(fingerprint))
+    .bind(height as i64)
+    .bind(height as i64)
+    .fetch_optional(conn)
+    .await?;
+    Ok(name)
+}
+
+pub async fn insert_renewal(
+    conn: impl Executor<'_, Database = Sqlite>,
+    claim: &LiveClaim,
+    fingerprint: &[u8; 5],
+    row: &RawBlockchain,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO name_renewals (index_id, fingerprint, name, pubkey, blockhash, txid, blocktime, blockheight, txheight, vout, indexed_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, unixepoch());",
+    )
+    .bind(claim.id)
+    .bind(hex::encode(fingerprint))
+    .bind(&claim.name)
+    .bind(&claim.pubkey)
+    .bind(row.blockhash.to_string())
+    .bind(row.txid.to_string())
+    .bind(row.blocktime as i64)
+    .bind(row.blockheight as i64)
+    .bind(row.txheight as i64)
+    .bind(row.vout as i64)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn insert_release(
+    conn: impl Executor<'_, Database = Sqlite>,
+    claim: &LiveClaim,
+    fingerprint: &[u8; 5],
+    row: &RawBlockchain,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO name_releases (index_id, fingerprint, name, pubkey, blockhash, txid, blocktime, blockheight, txheight, vout, indexed_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, unixepoch());",
+    )
+    .bind(claim.id)
+    .bind(hex::encode(fingerprint))
+    .bind(&claim.name)
+    .bind(&claim.pubkey)
+    .bind(row.blockhash.to_string())
+    .bind(row.txid.to_string())
+    .bind(row.blocktime as i64)
+    .bind(row.blockheight as i64)
+    .bind(row.txheight as i64)
+    .bind(row.vout as i64)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn reindex(conn: &SqlitePool, blockheight: i64) -> anyhow::Result<()> {
+    unexpire_transfers(conn, blockheight).await?;
+    // Claims below the height may have been renewed or released above it.
+    sqlx::query(
+        "INSERT OR IGNORE INTO stale_claims (fingerprint)
+        SELECT fingerprint FROM name_renewals WHERE blockheight >= ?1
+        UNION SELECT fingerprint FROM name_releases WHERE blockheight >= ?1;",
+    )
+    .bind(blockheight)
+    .execute(conn)
+    .await?;
     sqlx::query("DELETE FROM blockchain_index WHERE blockheight >= ?;")
         .bind(blockheight)
         .execute(conn)
@@ -188,6 +663,18 @@ pub async fn reindex(
         .bind(blockheight)
         .execute(conn)
         .await?;
+    sqlx::query("DELETE FROM name_commits WHERE blockheight >= ?;")
+        .bind(blockheight)
+        .execute(conn)
+        .await?;
+    sqlx::query("DELETE FROM name_renewals WHERE blockheight >= ?;")
+        .bind(blockheight)
+        .execute(conn)
+        .await?;
+    sqlx::query("DELETE FROM name_releases WHERE blockheight >= ?;")
+        .bind(blockheight)
+        .execute(conn)
+        .await?;
     sqlx::query("DELETE FROM name_events;")
         .execute(conn)
         .await?;
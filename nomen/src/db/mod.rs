@@ -2,14 +2,22 @@ use crate::config::Config;
 
 use sqlx::SqlitePool;
 
+pub mod audit;
+pub mod children;
 pub mod event_log;
+pub mod history;
 pub mod index;
 pub mod name;
+pub mod outbox;
 pub mod raw;
+pub mod record_delegate;
+pub mod relay;
 pub mod relay_index;
+pub mod release;
 pub mod stats;
+pub mod transfer;
 
-static MIGRATIONS: [&str; 18] = [
+static MIGRATIONS: [&str; 72] = [
     "CREATE TABLE event_log (id INTEGER PRIMARY KEY, created_at, type, data);",
     "CREATE TABLE index_height (blockheight INTEGER PRIMARY KEY, blockhash);",
     "CREATE TABLE raw_blockchain (id INTEGER PRIMARY KEY, blockhash, txid, blocktime, blockheight, txheight, vout, data, indexed_at);",
@@ -47,6 +55,163 @@ static MIGRATIONS: [&str; 18] = [
     "ALTER TABLE blockchain_index ADD COLUMN v1_upgrade_blockheight;",
     "ALTER TABLE blockchain_index ADD COLUMN v1_upgrade_txid",
     "CREATE UNIQUE INDEX riq_name_idx ON relay_index_queue (name)",
+
+    // Transfers leave transfer_cache either because a matching signature was found or because they expired. Keep the outcome so it can be reported.
+    "ALTER TABLE old_transfer_cache ADD COLUMN outcome;",
+    "ALTER TABLE old_transfer_cache ADD COLUMN signature_txid;",
+    "ALTER TABLE old_transfer_cache ADD COLUMN signature_blockheight;",
+    "UPDATE old_transfer_cache SET outcome = 'expired' WHERE outcome IS NULL;",
+    "CREATE VIEW transfer_status_vw AS
+        SELECT name, fingerprint, pubkey, txid, blockheight, txheight, vout, 'pending' AS outcome, NULL AS signature_txid, NULL AS signature_blockheight
+        FROM transfer_cache
+        UNION ALL
+        SELECT name, fingerprint, pubkey, txid, blockheight, txheight, vout, outcome, signature_txid, signature_blockheight
+        FROM old_transfer_cache;",
+    "CREATE TABLE audit_mismatches (id INTEGER PRIMARY KEY, peer, name, kind, local, remote, checked_at);",
+    "CREATE TABLE relay_index_published (name PRIMARY KEY, pubkey, event_id, published_at);",
+    "CREATE TABLE relay_outbox (id INTEGER PRIMARY KEY, kind, name, event_id, raw_event, created_at);",
+    "CREATE TABLE relay_outbox_delivery (outbox_id, relay, status, attempts, next_attempt_at, last_error, updated_at, PRIMARY KEY (outbox_id, relay));",
+    "ALTER TABLE relay_index_published ADD COLUMN raw_event;",
+
+    // Child names are delegated off-chain by the owner of the parent name. A delegation only counts while its signer owns the parent, so
+    // delegations made by a previous owner stop resolving after a transfer.
+    "CREATE TABLE child_delegations (parent, parent_pubkey, event_id, created_at, raw_event, indexed_at, PRIMARY KEY (parent, parent_pubkey));",
+    "CREATE TABLE child_names (name, pubkey, nsid, parent, parent_pubkey, PRIMARY KEY (name, parent_pubkey));",
+    "CREATE VIEW valid_children_vw AS
+        WITH RECURSIVE tree (name, pubkey, nsid, parent, root, depth) AS (
+            SELECT name, pubkey, nsid, NULL, name, 0 FROM valid_names_vw WHERE name IS NOT NULL
+            UNION ALL
+            SELECT cn.name, cn.pubkey, cn.nsid, cn.parent, tree.root, tree.depth + 1
+            FROM child_names cn
+            JOIN tree ON cn.parent = tree.name AND cn.parent_pubkey = tree.pubkey
+        )
+        SELECT name, pubkey, nsid, parent, root, depth FROM tree WHERE depth > 0;",
+    "CREATE VIEW valid_children_records_vw AS
+        SELECT vc.*, COALESCE(ne.records, '{}') as records, ne.raw_event
+        FROM valid_children_vw vc
+        LEFT JOIN name_events ne ON vc.nsid = ne.nsid;",
+
+    // Commit–reveal registrations rank by the position of their commit rather than their reveal, so the name can't be
+    // front-run once the reveal is in the mempool.
+    "CREATE TABLE name_commits (id INTEGER PRIMARY KEY, commitment, blockhash, txid, blocktime, blockheight, txheight, vout, indexed_at);",
+    "CREATE INDEX name_commits_commitment_idx ON name_commits (commitment);",
+    "ALTER TABLE blockchain_index ADD COLUMN commit_txid;",
+    "ALTER TABLE blockchain_index ADD COLUMN commit_blockheight;",
+    "ALTER TABLE blockchain_index ADD COLUMN commit_txheight;",
+    "ALTER TABLE blockchain_index ADD COLUMN commit_vout;",
+    "DROP VIEW ordered_blockchain_vw;",
+    "CREATE VIEW ordered_blockchain_vw AS
+        SELECT * from blockchain_index
+        ORDER BY COALESCE(commit_blockheight, blockheight) ASC, COALESCE(commit_txheight, txheight) ASC, COALESCE(commit_vout, vout) ASC;",
+    "DROP VIEW index_blockheights_vw;",
+    "CREATE VIEW index_blockheights_vw AS
+        SELECT blockheight FROM blockchain_index
+        UNION
+        SELECT blockheight FROM transfer_cache
+        UNION
+        SELECT blockheight FROM old_transfer_cache
+        UNION
+        SELECT blockheight FROM name_commits;",
+
+    // Names may expire when the indexer is configured to. expires_at and eligible are derived from the configuration and the renewals on
+    // every index run. A claim is eligible if no earlier eligible claim was live when it was made. Lapsed and ineligible claims stop ranking,
+    // which keeps a name from passing to a claim made while it was still live.
+    "CREATE TABLE name_renewals (id INTEGER PRIMARY KEY, index_id, fingerprint, name, pubkey, blockhash, txid, blocktime, blockheight, txheight, vout, indexed_at);",
+    "ALTER TABLE blockchain_index ADD COLUMN expires_at;",
+    "ALTER TABLE blockchain_index ADD COLUMN eligible;",
+    "DROP VIEW ranked_blockchain_vw;",
+    "CREATE VIEW ranked_blockchain_vw AS
+        SELECT *, row_number() OVER (PARTITION BY fingerprint) as rank
+        FROM ordered_blockchain_vw
+        WHERE COALESCE(eligible, 1) = 1
+        AND (expires_at IS NULL OR expires_at > (SELECT COALESCE(MAX(blockheight), 0) FROM index_height));",
+    "DROP VIEW index_blockheights_vw;",
+    "CREATE VIEW index_blockheights_vw AS
+        SELECT blockheight FROM blockchain_index
+        UNION
+        SELECT blockheight FROM transfer_cache
+        UNION
+        SELECT blockheight FROM old_transfer_cache
+        UNION
+        SELECT blockheight FROM name_commits
+        UNION
+        SELECT blockheight FROM name_renewals;",
+
+    // An owner can release a name, after which it can be claimed again.
+    "CREATE TABLE name_releases (id INTEGER PRIMARY KEY, index_id, fingerprint, name, pubkey, blockhash, txid, blocktime, blockheight, txheight, vout, indexed_at);",
+    "DROP VIEW ranked_blockchain_vw;",
+    "CREATE VIEW ranked_blockchain_vw AS
+        SELECT *, row_number() OVER (PARTITION BY fingerprint) as rank
+        FROM ordered_blockchain_vw
+        WHERE COALESCE(eligible, 1) = 1
+        AND (expires_at IS NULL OR expires_at > (SELECT COALESCE(MAX(blockheight), 0) FROM index_height))
+        AND id NOT IN (SELECT index_id FROM name_releases);",
+    "DROP VIEW index_blockheights_vw;",
+    "CREATE VIEW index_blockheights_vw AS
+        SELECT blockheight FROM blockchain_index
+        UNION
+        SELECT blockheight FROM transfer_cache
+        UNION
+        SELECT blockheight FROM old_transfer_cache
+        UNION
+        SELECT blockheight FROM name_commits
+        UNION
+        SELECT blockheight FROM name_renewals
+        UNION
+        SELECT blockheight FROM name_releases;",
+
+    // Owners can authorise a delegate key to sign their record events. Only the latest delegation by each owner is kept, and one without a
+    // delegate is a revocation.
+    "CREATE TABLE record_delegates (name, owner, delegate, event_id, created_at, raw_event, indexed_at, PRIMARY KEY (name, owner));",
+
+    // The transactions spent by transactions with Nomen outputs. A transfer signature which spends the transfer transaction is bound to that transfer.
+    "CREATE TABLE raw_blockchain_inputs (blockheight, txid, prev_txid, PRIMARY KEY (txid, prev_txid));",
+
+    // The index height an expired transfer expired at, so that a reorg below it can return the transfer to transfer_cache.
+    "ALTER TABLE old_transfer_cache ADD COLUMN expired_at;",
+    "DROP VIEW transfer_status_vw;",
+    "CREATE VIEW transfer_status_vw AS
+        SELECT name, fingerprint, pubkey, txid, blockheight, txheight, vout, 'pending' AS outcome, NULL AS signature_txid, NULL AS signature_blockheight, NULL AS expired_at
+        FROM transfer_cache
+        UNION ALL
+        SELECT name, fingerprint, pubkey, txid, blockheight, txheight, vout, outcome, signature_txid, signature_blockheight, expired_at
+        FROM old_transfer_cache;",
+
+    // Events in the history of a name which the index tables don't keep, such as transfer expirations.
+    "CREATE TABLE name_history (id INTEGER PRIMARY KEY, name, fingerprint, event, txid, blockheight, created_at);",
+
+    // Transfer signatures look up the transfers in their own transaction and the ones it spends, and then the owner of the name.
+    "CREATE INDEX transfer_cache_txid_idx ON transfer_cache (txid);",
+    "CREATE INDEX blockchain_index_fingerprint_idx ON blockchain_index (fingerprint);",
+
+    // Names whose claims changed since their expiry and eligibility were last computed.
+    "CREATE TABLE stale_claims (fingerprint PRIMARY KEY);",
+    "CREATE INDEX name_renewals_index_id_idx ON name_renewals (index_id);",
+    "CREATE INDEX name_releases_index_id_idx ON name_releases (index_id);",
+
+    // Whether the last audit of each peer fetched its complete relay index.
+    "CREATE TABLE audit_peers (peer PRIMARY KEY, status, checked_at);",
+
+    // The skeleton of each name, see nomen_core::skeleton, so that the names confusable with another are found with the index.
+    "ALTER TABLE blockchain_index ADD COLUMN skeleton;",
+    "CREATE INDEX blockchain_index_skeleton_idx ON blockchain_index (skeleton);",
+
+    // The claims which can own a name, with the order they rank in. The owner of a name at some height is its first claim here
+    // which hasn't expired by then. live_claims_vw holds the claims live at the indexed tip. Both are plain views, so lookups by
+    // fingerprint or skeleton still use the indexes, unlike ranked_blockchain_vw.
+    "CREATE VIEW claims_vw AS
+        SELECT *, COALESCE(commit_blockheight, blockheight) AS claim_blockheight, COALESCE(commit_txheight, txheight) AS claim_txheight,
+            COALESCE(commit_vout, vout) AS claim_vout
+        FROM blockchain_index
+        WHERE COALESCE(eligible, 1) = 1
+        AND id NOT IN (SELECT index_id FROM name_releases);",
+    "CREATE VIEW live_claims_vw AS
+        SELECT * FROM claims_vw
+        WHERE expires_at IS NULL OR expires_at > (SELECT COALESCE(MAX(blockheight), 0) FROM index_height);",
+    "DROP VIEW ranked_blockchain_vw;",
+    "CREATE VIEW ranked_blockchain_vw AS
+        SELECT *, row_number() OVER (PARTITION BY fingerprint ORDER BY claim_blockheight, claim_txheight, claim_vout) as rank
+        FROM live_claims_vw;",
 ];
 
 pub async fn initialize(config: &Config) -> anyhow::Result<SqlitePool> {
@@ -72,6 +237,7 @@ pub async fn initialize(config: &Config) -> anyhow::Result<SqlitePool> {
             .await?;
         tx.commit().await?;
     }
+    index::fill_skeletons(&conn).await?;
 
     Ok(conn)
 }
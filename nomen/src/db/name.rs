@@ -1,6 +1,6 @@
 #![allow(clippy::module_name_repetitions)]
 
-use nomen_core::{Hash160, Name, Nsid};
+use nomen_core::{skeleton, Hash160, Name, Nsid};
 use nostr_sdk::EventId;
 use secp256k1::XOnlyPublicKey;
 use sqlx::{FromRow, Sqlite, SqlitePool};
@@ -13,11 +13,13 @@ pub struct NameDetails {
     pub vout: i64,
     pub blockheight: i64,
     pub name: String,
+    pub nsid: String,
     pub records: String,
     pub pubkey: String,
     pub protocol: i64,
     pub v1_upgrade_blockheight: Option<i64>,
     pub v1_upgrade_txid: Option<String>,
+    pub expires_at: Option<i64>,
 }
 
 pub async fn details(conn: &SqlitePool, query: &str) -> anyhow::Result<NameDetails> {
@@ -33,12 +35,20 @@ pub async fn details(conn: &SqlitePool, query: &str) -> anyhow::Result<NameDetai
 
 #[derive(FromRow)]
 pub struct NameRecords {
+    pub name: String,
+    pub pubkey: String,
     pub blockhash: String,
     pub txid: String,
+    pub blockheight: i64,
     pub fingerprint: String,
     pub nsid: String,
     pub protocol: i64,
     pub records: String,
+    pub raw_event: String,
+    pub v1_upgrade_blockheight: Option<i64>,
+    pub v1_upgrade_txid: Option<String>,
+    pub expires_at: Option<i64>,
+    pub renewed_from: i64,
 }
 
 pub async fn records(conn: &SqlitePool, name: String) -> anyhow::Result<Option<NameRecords>> {
@@ -46,7 +56,9 @@ pub async fn records(conn: &SqlitePool, name: String) -> anyhow::Result<Option<N
         .chain_update(name.as_bytes())
         .fingerprint();
     let records = sqlx::query_as::<_, NameRecords>(
-        "SELECT vn.blockhash, vn.txid, vn.fingerprint, vn.nsid, vn.protocol, coalesce(ne.records, '{}') as records
+        "SELECT vn.name, vn.pubkey, vn.blockhash, vn.txid, vn.blockheight, vn.fingerprint, vn.nsid, vn.protocol, coalesce(ne.records, '{}') as records,
+            ne.raw_event, vn.v1_upgrade_blockheight, vn.v1_upgrade_txid, vn.expires_at,
+            COALESCE((SELECT MAX(r.blockheight) FROM name_renewals r WHERE r.index_id = vn.id), vn.blockheight) AS renewed_from
         FROM valid_names_vw vn
         JOIN name_events ne ON vn.nsid = ne.nsid
         WHERE vn.fingerprint = ? LIMIT 1;",
@@ -133,3 +145,52 @@ pub async fn last_records_time(conn: &SqlitePool) -> anyhow::Result<u64> {
         .await?;
     Ok(t as u64)
 }
+
+/// The valid top-level name with `nsid`.
+pub async fn by_nsid(conn: &SqlitePool, nsid: &Nsid) -> anyhow::Result<Option<String>> {
+    let name = sqlx::query_as::<_, (String,)>(
+        "SELECT name FROM valid_names_vw WHERE nsid = ? AND name IS NOT NULL;",
+    )
+    .bind(nsid.to_string())
+    .fetch_optional(conn)
+    .await?;
+    Ok(name.map(|(n,)| n))
+}
+
+/// Valid names which are confusable with `name`, see [`nomen_core::skeleton`]. The names sharing its skeleton are found
+/// with the index, and then the current owner of each is the first of its claims in `live_claims_vw`.
+pub async fn confusable_names(conn: &SqlitePool, name: &Name) -> anyhow::Result<Vec<String>> {
+    let names = sqlx::query_as::<_, (String,)>(
+        "WITH owners AS MATERIALIZED (
+            SELECT (
+                SELECT c.name FROM live_claims_vw c
+                WHERE c.fingerprint = s.fingerprint
+                ORDER BY c.claim_blockheight, c.claim_txheight, c.claim_vout
+                LIMIT 1
+            ) AS name
+            FROM (SELECT DISTINCT fingerprint FROM blockchain_index WHERE skeleton = ?) s
+        )
+        SELECT name FROM owners WHERE name IS NOT NULL AND name != ? ORDER BY name;",
+    )
+    .bind(skeleton(name))
+    .bind(name.as_ref())
+    .fetch_all(conn)
+    .await?;
+    Ok(names.into_iter().map(|(n,)| n).collect())
+}
+
+/// Whether `pubkey` is the current owner of `name`.
+pub async fn is_owner(conn: &SqlitePool, name: &str, pubkey: &str) -> anyhow::Result<bool> {
+    let (owner,) = sqlx::query_as::<_, (bool,)>(
+        "SELECT COUNT(*) > 0 FROM (
+            SELECT name, pubkey FROM valid_names_vw
+            UNION ALL
+            SELECT name, pubkey FROM valid_children_vw
+        ) WHERE name = ? AND pubkey = ?;",
+    )
+    .bind(name)
+    .bind(pubkey)
+    .fetch_one(conn)
+    .await?;
+    Ok(owner)
+}
@@ -0,0 +1,183 @@
+use nostr_sdk::Event;
+use sqlx::{FromRow, SqlitePool};
+
+/// Kinds of outbox entries which replace earlier entries for the same name.
+fn superseded_kinds(kind: &str) -> (&str, &str) {
+    match kind {
+        "index" | "deletion" => ("index", "deletion"),
+        _ => (kind, kind),
+    }
+}
+
+/// Adds an event to the outbox, with a pending delivery for each relay. Pending deliveries of earlier events for
+/// the same name are superseded, as the new event replaces them.
+pub async fn enqueue(
+    conn: &SqlitePool,
+    kind: &str,
+    name: Option<&str>,
+    event: &Event,
+    relays: &[String],
+) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+    if let Some(name) = name {
+        let (k1, k2) = superseded_kinds(kind);
+        sqlx::query(
+            "UPDATE relay_outbox_delivery SET status = 'superseded', updated_at = unixepoch()
+            WHERE status = 'pending' AND outbox_id IN (SELECT id FROM relay_outbox WHERE name = ? AND kind IN (?, ?));",
+        )
+        .bind(name)
+        .bind(k1)
+        .bind(k2)
+        .execute(&mut tx)
+        .await?;
+    }
+
+    let outbox_id = sqlx::query(
+        "INSERT INTO relay_outbox (kind, name, event_id, raw_event, created_at) VALUES (?, ?, ?, ?, unixepoch());",
+    )
+    .bind(kind)
+    .bind(name)
+    .bind(event.id.to_string())
+    .bind(event.as_json())
+    .execute(&mut tx)
+    .await?
+    .last_insert_rowid();
+
+    for relay in relays {
+        sqlx::query(
+            "INSERT INTO relay_outbox_delivery (outbox_id, relay, status, attempts, next_attempt_at, updated_at)
+            VALUES (?, ?, 'pending', 0, unixepoch(), unixepoch());",
+        )
+        .bind(outbox_id)
+        .bind(relay)
+        .execute(&mut tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+#[derive(FromRow, Debug)]
+pub struct Delivery {
+    pub outbox_id: i64,
+    pub relay: String,
+    pub attempts: i64,
+    pub raw_event: String,
+}
+
+/// Pending deliveries whose next attempt is due, oldest first.
+pub async fn due(conn: &SqlitePool) -> anyhow::Result<Vec<Delivery>> {
+    let deliveries = sqlx::query_as::<_, Delivery>(
+        "SELECT d.outbox_id, d.relay, d.attempts, o.raw_event
+        FROM relay_outbox_delivery d
+        JOIN relay_outbox o ON d.outbox_id = o.id
+        WHERE d.status = 'pending' AND d.next_attempt_at <= unixepoch()
+        ORDER BY d.relay, d.outbox_id;",
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(deliveries)
+}
+
+pub async fn mark_sent(conn: &SqlitePool, outbox_id: i64, relay: &str) -> anyhow::Result<()> {
+    sqlx::query(
+        "UPDATE relay_outbox_delivery SET status = 'sent', attempts = attempts + 1, last_error = NULL, updated_at = unixepoch()
+        WHERE outbox_id = ? AND relay = ?;",
+    )
+    .bind(outbox_id)
+    .bind(relay)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Records a failed delivery attempt. The delivery is retried after `retry_in` seconds, or moved to the dead
+/// letters if `dead` is set.
+pub async fn mark_failed(
+    conn: &SqlitePool,
+    outbox_id: i64,
+    relay: &str,
+    error: &str,
+    retry_in: i64,
+    dead: bool,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "UPDATE relay_outbox_delivery
+        SET status = ?, attempts = attempts + 1, last_error = ?, next_attempt_at = unixepoch() + ?, updated_at = unixepoch()
+        WHERE outbox_id = ? AND relay = ?;",
+    )
+    .bind(if dead { "dead" } else { "pending" })
+    .bind(error)
+    .bind(retry_in)
+    .bind(outbox_id)
+    .bind(relay)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Records an error which stopped the deliveries to `relay` on its pending deliveries. They are retried on the next
+/// run.
+pub async fn relay_failed(conn: &SqlitePool, relay: &str, error: &str) -> anyhow::Result<()> {
+    sqlx::query(
+        "UPDATE relay_outbox_delivery SET last_error = ?, updated_at = unixepoch() WHERE relay = ? AND status = 'pending';",
+    )
+    .bind(error)
+    .bind(relay)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Removes outbox entries which have nothing left to deliver and no dead letters to report.
+pub async fn prune(conn: &SqlitePool) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+    sqlx::query(
+        "DELETE FROM relay_outbox WHERE id NOT IN (
+            SELECT outbox_id FROM relay_outbox_delivery WHERE status IN ('pending', 'dead')
+        );",
+    )
+    .execute(&mut tx)
+    .await?;
+    sqlx::query(
+        "DELETE FROM relay_outbox_delivery WHERE outbox_id NOT IN (SELECT id FROM relay_outbox);",
+    )
+    .execute(&mut tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[derive(FromRow, Debug)]
+pub struct DeadLetter {
+    pub kind: String,
+    pub name: Option<String>,
+    pub event_id: String,
+    pub relay: String,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    pub updated_at: i64,
+}
+
+pub async fn dead_letters(conn: &SqlitePool) -> anyhow::Result<Vec<DeadLetter>> {
+    let dead = sqlx::query_as::<_, DeadLetter>(
+        "SELECT o.kind, o.name, o.event_id, d.relay, d.attempts, d.last_error, d.updated_at
+        FROM relay_outbox_delivery d
+        JOIN relay_outbox o ON d.outbox_id = o.id
+        WHERE d.status = 'dead'
+        ORDER BY d.updated_at DESC;",
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(dead)
+}
+
+/// Number of pending and dead deliveries.
+pub async fn counts(conn: &SqlitePool) -> anyhow::Result<(i64, i64)> {
+    let counts = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT COALESCE(SUM(status = 'pending'), 0), COALESCE(SUM(status = 'dead'), 0) FROM relay_outbox_delivery;",
+    )
+    .fetch_one(conn)
+    .await?;
+    Ok(counts)
+}
@@ -11,7 +11,8 @@ INSERT INTO
     blockheight,
     txheight,
     vout,
+    skeleton,
     indexed_at
   )
 VALUES
-  (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, unixepoch());
\ No newline at end of file
+  (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, unixepoch());
\ No newline at end of file
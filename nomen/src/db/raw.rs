@@ -47,3 +47,23 @@ pub async fn insert_raw_blockchain(
         .await?;
     Ok(())
 }
+
+/// Saves the transactions spent by `txid`.
+pub async fn insert_inputs(
+    conn: impl Executor<'_, Database = Sqlite> + Copy,
+    blockheight: usize,
+    txid: &Txid,
+    prev_txids: &[Txid],
+) -> anyhow::Result<()> {
+    for prev_txid in prev_txids {
+        sqlx::query(
+            "INSERT OR IGNORE INTO raw_blockchain_inputs (blockheight, txid, prev_txid) VALUES (?, ?, ?);",
+        )
+        .bind(blockheight as i64)
+        .bind(txid.to_string())
+        .bind(prev_txid.to_string())
+        .execute(conn)
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use sqlx::{FromRow, SqlitePool};
+
+#[derive(FromRow, Debug)]
+pub struct RecordDelegate {
+    pub name: String,
+    pub owner: String,
+    pub delegate: Option<String>,
+    pub event_id: String,
+    pub created_at: i64,
+    pub raw_event: String,
+}
+
+/// Saves a record delegation event, replacing the previous delegation by the same owner. Older delegations are
+/// ignored. Returns whether the delegation was saved.
+pub async fn save(conn: &SqlitePool, delegate: &RecordDelegate) -> anyhow::Result<bool> {
+    let updated = sqlx::query(
+        "INSERT INTO record_delegates (name, owner, delegate, event_id, created_at, raw_event, indexed_at)
+        VALUES (?, ?, ?, ?, ?, ?, unixepoch())
+        ON CONFLICT (name, owner) DO UPDATE SET
+        delegate = excluded.delegate,
+        event_id = excluded.event_id,
+        created_at = excluded.created_at,
+        raw_event = excluded.raw_event,
+        indexed_at = excluded.indexed_at
+        WHERE excluded.created_at > created_at;",
+    )
+    .bind(&delegate.name)
+    .bind(&delegate.owner)
+    .bind(&delegate.delegate)
+    .bind(&delegate.event_id)
+    .bind(delegate.created_at)
+    .bind(&delegate.raw_event)
+    .execute(conn)
+    .await?
+    .rows_affected();
+    Ok(updated > 0)
+}
+
+pub async fn last_delegation_time(conn: &SqlitePool) -> anyhow::Result<u64> {
+    let (t,) =
+        sqlx::query_as::<_, (i64,)>("SELECT COALESCE(MAX(created_at), 0) FROM record_delegates;")
+            .fetch_one(conn)
+            .await?;
+    Ok(t as u64)
+}
+
+/// The latest record delegation by `owner` for `name`, which may be a revocation.
+pub async fn find(
+    conn: &SqlitePool,
+    name: &str,
+    owner: &str,
+) -> anyhow::Result<Option<RecordDelegate>> {
+    let delegate = sqlx::query_as::<_, RecordDelegate>(
+        "SELECT name, owner, delegate, event_id, created_at, raw_event
+        FROM record_delegates WHERE name = ? AND owner = ?;",
+    )
+    .bind(name)
+    .bind(owner)
+    .fetch_optional(conn)
+    .await?;
+    Ok(delegate)
+}
@@ -0,0 +1,151 @@
+use nomen_core::NameKind;
+use nostr_sdk::{Filter, Kind};
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+
+/// The stored events of each kind served by the relay: the record events of valid names and children, the child and
+/// record delegations of current owners, and the published relay index.
+fn sources() -> [(NameKind, &'static str); 4] {
+    [
+        (
+            NameKind::Name,
+            "SELECT ne.raw_event FROM valid_names_vw vn JOIN name_events ne ON vn.nsid = ne.nsid
+        UNION ALL
+        SELECT raw_event FROM valid_children_records_vw WHERE raw_event IS NOT NULL",
+        ),
+        (
+            NameKind::Children,
+            "SELECT cd.raw_event FROM child_delegations cd
+        JOIN (
+            SELECT name, pubkey FROM valid_names_vw WHERE name IS NOT NULL
+            UNION
+            SELECT name, pubkey FROM valid_children_vw
+        ) owners ON cd.parent = owners.name AND cd.parent_pubkey = owners.pubkey",
+        ),
+        (
+            NameKind::RecordDelegate,
+            "SELECT rd.raw_event FROM record_delegates rd
+        JOIN (
+            SELECT name, pubkey FROM valid_names_vw WHERE name IS NOT NULL
+            UNION
+            SELECT name, pubkey FROM valid_children_vw
+        ) owners ON rd.name = owners.name AND rd.owner = owners.pubkey",
+        ),
+        (
+            NameKind::RelayIndex,
+            "SELECT raw_event FROM relay_index_published WHERE raw_event IS NOT NULL",
+        ),
+    ]
+}
+
+/// The raw events matching `filter`, newest first and at most `limit` of them.
+pub async fn events(
+    conn: &SqlitePool,
+    filter: &Filter,
+    limit: usize,
+) -> anyhow::Result<Vec<String>> {
+    let sources = sources_of(filter);
+    if sources.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut query = QueryBuilder::new("");
+    push_matching(&mut query, &sources, filter);
+    query.push(" ORDER BY json_extract(raw_event, '$.created_at') DESC LIMIT ");
+    query.push_bind(i64::try_from(limit).unwrap_or(i64::MAX));
+    let rows = query.build_query_as::<(String,)>().fetch_all(conn).await?;
+    Ok(rows.into_iter().map(|(e,)| e).collect())
+}
+
+/// The number of distinct events matching any of `filters`.
+pub async fn count(conn: &SqlitePool, filters: &[Filter]) -> anyhow::Result<i64> {
+    let mut query =
+        QueryBuilder::new("SELECT COUNT(DISTINCT json_extract(raw_event, '$.id')) FROM (");
+    let mut any = false;
+    for filter in filters {
+        let sources = sources_of(filter);
+        if sources.is_empty() {
+            continue;
+        }
+        if any {
+            query.push(" UNION ALL ");
+        }
+        any = true;
+        push_matching(&mut query, &sources, filter);
+    }
+    if !any {
+        return Ok(0);
+    }
+    query.push(")");
+    let (count,) = query.build_query_as::<(i64,)>().fetch_one(conn).await?;
+    Ok(count)
+}
+
+/// The sources of the kinds in `filter`, empty when it wants no kind the relay serves.
+fn sources_of(filter: &Filter) -> Vec<&'static str> {
+    sources()
+        .into_iter()
+        .filter_map(|(kind, source)| {
+            (filter.kinds.is_empty() || filter.kinds.contains(&Kind::from(kind))).then_some(source)
+        })
+        .collect()
+}
+
+/// Pushes a select of the raw events from `sources` which match `filter`.
+fn push_matching<'a>(query: &mut QueryBuilder<'a, Sqlite>, sources: &[&str], filter: &'a Filter) {
+    query.push("SELECT raw_event FROM (");
+    query.push(sources.join(" UNION ALL "));
+    query.push(") WHERE 1 = 1");
+
+    for (field, prefixes) in [("id", &filter.ids), ("pubkey", &filter.authors)] {
+        if prefixes.is_empty() {
+            continue;
+        }
+        query.push(" AND (");
+        let mut any = query.separated(" OR ");
+        for prefix in prefixes {
+            any.push(format!("instr(json_extract(raw_event, '$.{field}'), "));
+            any.push_bind_unseparated(prefix);
+            any.push_unseparated(") = 1");
+        }
+        query.push(")");
+    }
+    if let Some(since) = filter.since {
+        query.push(" AND json_extract(raw_event, '$.created_at') >= ");
+        query.push_bind(since.as_i64());
+    }
+    if let Some(until) = filter.until {
+        query.push(" AND json_extract(raw_event, '$.created_at') <= ");
+        query.push_bind(until.as_i64());
+    }
+
+    let mut tags = vec![
+        (
+            "e".to_owned(),
+            filter.events.iter().map(ToString::to_string).collect(),
+        ),
+        (
+            "p".to_owned(),
+            filter.pubkeys.iter().map(ToString::to_string).collect(),
+        ),
+        ("t".to_owned(), filter.hashtags.clone()),
+        ("r".to_owned(), filter.references.clone()),
+        ("d".to_owned(), filter.identifiers.clone()),
+    ];
+    tags.extend(
+        filter
+            .generic_tags
+            .iter()
+            .map(|(letter, values)| (letter.to_string(), values.clone())),
+    );
+    for (letter, values) in tags.into_iter().filter(|(_, values)| !values.is_empty()) {
+        query.push(
+            " AND EXISTS (SELECT 1 FROM json_each(raw_event, '$.tags') t WHERE json_extract(t.value, '$[0]') = ",
+        );
+        query.push_bind(letter);
+        query.push(" AND json_extract(t.value, '$[1]') IN (");
+        let mut values_list = query.separated(", ");
+        for value in values {
+            values_list.push_bind(value);
+        }
+        query.push("))");
+    }
+}
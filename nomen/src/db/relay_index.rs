@@ -24,7 +24,11 @@ pub async fn fetch_all_queued(
     let results = sqlx::query_as::<_, Name>(
         "SELECT vnr.name, vnr.pubkey, COALESCE(vnr.records, '{}') as records
         FROM valid_names_records_vw vnr
-        JOIN relay_index_queue riq ON vnr.name = riq.name;",
+        JOIN relay_index_queue riq ON vnr.name = riq.name
+        UNION ALL
+        SELECT vcr.name, vcr.pubkey, vcr.records
+        FROM valid_children_records_vw vcr
+        JOIN relay_index_queue riq ON vcr.name = riq.name;",
     )
     .fetch_all(conn)
     .await?;
@@ -36,7 +40,10 @@ pub async fn fetch_all(
 ) -> anyhow::Result<Vec<Name>> {
     let results = sqlx::query_as::<_, Name>(
         "SELECT vnr.name, vnr.pubkey, COALESCE(vnr.records, '{}') as records
-        FROM valid_names_records_vw vnr;",
+        FROM valid_names_records_vw vnr
+        UNION ALL
+        SELECT vcr.name, vcr.pubkey, vcr.records
+        FROM valid_children_records_vw vcr;",
     )
     .fetch_all(conn)
     .await?;
@@ -53,3 +60,76 @@ pub async fn delete(
         .await?;
     Ok(())
 }
+
+pub async fn mark_published(
+    conn: impl sqlx::Executor<'_, Database = Sqlite> + Copy,
+    name: &str,
+    pubkey: &str,
+    event_id: &str,
+    raw_event: &str,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO relay_index_published (name, pubkey, event_id, published_at, raw_event) VALUES (?, ?, ?, unixepoch(), ?)
+        ON CONFLICT (name) DO UPDATE SET pubkey = excluded.pubkey, event_id = excluded.event_id, published_at = excluded.published_at, raw_event = excluded.raw_event;",
+    )
+    .bind(name)
+    .bind(pubkey)
+    .bind(event_id)
+    .bind(raw_event)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Queue names whose owner changed since they were last published, e.g. after a transfer or a reorg.
+pub async fn queue_changed(
+    conn: impl sqlx::Executor<'_, Database = Sqlite> + Copy,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO relay_index_queue (name)
+        SELECT vn.name FROM (
+            SELECT name, pubkey FROM valid_names_vw
+            UNION ALL
+            SELECT name, pubkey FROM valid_children_vw
+        ) vn
+        JOIN relay_index_published rip ON vn.name = rip.name
+        WHERE vn.pubkey != rip.pubkey;",
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct Published {
+    pub name: String,
+    pub event_id: String,
+}
+
+/// Published names which no longer exist in the index.
+pub async fn fetch_stale(
+    conn: impl sqlx::Executor<'_, Database = Sqlite> + Copy,
+) -> anyhow::Result<Vec<Published>> {
+    let results = sqlx::query_as::<_, Published>(
+        "SELECT rip.name, rip.event_id FROM relay_index_published rip
+        WHERE rip.name NOT IN (
+            SELECT name FROM valid_names_vw WHERE name IS NOT NULL
+            UNION
+            SELECT name FROM valid_children_vw
+        );",
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(results)
+}
+
+pub async fn delete_published(
+    conn: impl sqlx::Executor<'_, Database = Sqlite> + Copy,
+    name: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM relay_index_published WHERE name = ?;")
+        .bind(name)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
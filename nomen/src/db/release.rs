@@ -0,0 +1,25 @@
+use sqlx::{FromRow, SqlitePool};
+
+#[derive(FromRow, Debug)]
+pub struct Release {
+    pub name: String,
+    pub pubkey: String,
+    pub txid: String,
+    pub blockheight: i64,
+    pub claim_txid: Option<String>,
+}
+
+/// Releases of `name`, newest first.
+pub async fn history(conn: &SqlitePool, name: &str) -> anyhow::Result<Vec<Release>> {
+    let releases = sqlx::query_as::<_, Release>(
+        "SELECT r.name, r.pubkey, r.txid, r.blockheight, b.txid AS claim_txid
+        FROM name_releases r
+        LEFT JOIN blockchain_index b ON b.id = r.index_id
+        WHERE r.name = ?
+        ORDER BY r.blockheight DESC, r.txheight DESC, r.vout DESC;",
+    )
+    .bind(name)
+    .fetch_all(conn)
+    .await?;
+    Ok(releases)
+}
@@ -0,0 +1,102 @@
+#![allow(clippy::module_name_repetitions)]
+
+use sqlx::{FromRow, SqlitePool};
+
+#[derive(FromRow, Debug)]
+pub struct TransferStatus {
+    pub name: String,
+    pub pubkey: String,
+    pub txid: String,
+    pub blockheight: i64,
+    pub outcome: String,
+    pub signature_txid: Option<String>,
+    pub signature_blockheight: Option<i64>,
+    /// The index height at which the transfer expired, or at which it will expire if it is still pending.
+    pub expires_at: i64,
+}
+
+impl TransferStatus {
+    /// Blocks left before a pending transfer is expired. Transfers which are no longer pending have no blocks left.
+    pub fn blocks_remaining(&self, index_height: i64) -> Option<i64> {
+        if self.is_pending() {
+            Some((self.expires_at - index_height).max(0))
+        } else {
+            None
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.outcome == "pending"
+    }
+
+    /// Whether the transfer was completed by a signature. A signature is only recorded for completed transfers.
+    pub fn signature_seen(&self) -> bool {
+        self.outcome == "completed"
+    }
+}
+
+/// Transfers of `name`, newest first. Transfers expire after `expiry_blocks`, see
+/// [`Config::transfer_expiry_blocks`](crate::config::Config::transfer_expiry_blocks).
+pub async fn status(
+    conn: &SqlitePool,
+    name: &str,
+    expiry_blocks: usize,
+) -> anyhow::Result<Vec<TransferStatus>> {
+    let transfers = sqlx::query_as::<_, TransferStatus>(
+        "SELECT name, pubkey, txid, blockheight, outcome, signature_txid, signature_blockheight,
+            COALESCE(expired_at, blockheight + ? + 1) AS expires_at
+        FROM transfer_status_vw
+        WHERE name = ?
+        ORDER BY blockheight DESC, txheight DESC, vout DESC;",
+    )
+    .bind(expiry_blocks as i64)
+    .bind(name)
+    .fetch_all(conn)
+    .await?;
+    Ok(transfers)
+}
+
+/// Pending transfers of every name, in the order they will expire.
+pub async fn expiring(
+    conn: &SqlitePool,
+    expiry_blocks: usize,
+) -> anyhow::Result<Vec<TransferStatus>> {
+    let transfers = sqlx::query_as::<_, TransferStatus>(
+        "SELECT name, pubkey, txid, blockheight, outcome, signature_txid, signature_blockheight,
+            blockheight + ? + 1 AS expires_at
+        FROM transfer_status_vw
+        WHERE outcome = 'pending'
+        ORDER BY blockheight, txheight, vout;",
+    )
+    .bind(expiry_blocks as i64)
+    .fetch_all(conn)
+    .await?;
+    Ok(transfers)
+}
+
+#[derive(FromRow, Debug)]
+pub struct CompletedTransfer {
+    pub blockhash: String,
+    pub txid: String,
+    pub signature_txid: String,
+    pub signature_blockheight: i64,
+}
+
+/// Completed transfers of a name since it was created, in the order they were applied.
+pub async fn completed(
+    conn: &SqlitePool,
+    name: &str,
+    since_blockheight: i64,
+) -> anyhow::Result<Vec<CompletedTransfer>> {
+    let transfers = sqlx::query_as::<_, CompletedTransfer>(
+        "SELECT blockhash, txid, signature_txid, signature_blockheight
+        FROM old_transfer_cache
+        WHERE name = ? AND outcome = 'completed' AND blockheight >= ?
+        ORDER BY signature_blockheight, blockheight, txheight, vout;",
+    )
+    .bind(name)
+    .bind(since_blockheight)
+    .fetch_all(conn)
+    .await?;
+    Ok(transfers)
+}
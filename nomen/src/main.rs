@@ -43,6 +43,16 @@ async fn main() -> anyhow::Result<()> {
             subcommands::rebroadcast(&config, &pool).await?;
         }
         config::Subcommand::Publish => subcommands::publish(&config, &pool).await?,
+        config::Subcommand::Audit => subcommands::audit(&config, &pool).await?,
+        config::Subcommand::Vectors {
+            from,
+            to,
+            description,
+            op_returns,
+        } => {
+            subcommands::vectors(&config, &pool, *from, *to, description.clone(), *op_returns)
+                .await?;
+        }
         config::Subcommand::Version => {
             subcommands::version();
         }
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use nomen_core::NameKind;
+use nostr_sdk::{Event, EventId, Filter, Timestamp};
+use secp256k1::XOnlyPublicKey;
+use sqlx::SqlitePool;
+
+use crate::{
+    config::Config,
+    db::{self, audit::Mismatch},
+    subcommands::{
+        index::events::relay_index::Content,
+        relays::{NostrRelays, Relays},
+    },
+};
+
+/// Owner pubkey and records for a name, keyed by name.
+type Index = HashMap<String, (String, HashMap<String, String>)>;
+
+/// Relay index events requested per page of a peer's index.
+const PAGE: usize = 500;
+
+pub async fn audit(config: &Config, pool: &SqlitePool) -> anyhow::Result<()> {
+    let relays = NostrRelays::new(config);
+    let audited = run(config, pool, &relays).await;
+    relays.disconnect().await;
+    audited
+}
+
+async fn run(config: &Config, pool: &SqlitePool, relays: &dyn Relays) -> anyhow::Result<()> {
+    let peers = config.peers();
+    if peers.is_empty() {
+        tracing::info!("No audit peers configured.");
+        return Ok(());
+    }
+
+    tracing::info!("Beginning relay index audit of {} peers.", peers.len());
+    let local = local_index(pool).await?;
+
+    for peer in peers {
+        // Without the peer's complete index, its earlier mismatches can't be confirmed and nothing new is known.
+        let peer_index = match peer_index(relays, &peer).await {
+            Ok(Some(peer_index)) => peer_index,
+            Ok(None) => {
+                tracing::warn!("No relay index found for audit peer {peer}.");
+                db::audit::unknown(pool, &peer.to_string()).await?;
+                continue;
+            }
+            Err(err) => {
+                tracing::warn!("Failed to fetch the relay index of audit peer {peer}: {err}");
+                db::audit::unknown(pool, &peer.to_string()).await?;
+                continue;
+            }
+        };
+        let mismatches = compare(&peer.to_string(), &local, &peer_index);
+        for m in &mismatches {
+            tracing::warn!(
+                "Audit mismatch with peer {} for '{}': {} (local: {:?}, remote: {:?})",
+                m.peer,
+                m.name,
+                m.kind,
+                m.local,
+                m.remote
+            );
+        }
+        tracing::info!(
+            "Audit of peer {peer} found {} mismatches.",
+            mismatches.len()
+        );
+        db::audit::replace(pool, &peer.to_string(), &mismatches).await?;
+    }
+
+    db::event_log::save(pool, "audit", "").await?;
+    tracing::info!("Relay index audit complete.");
+    Ok(())
+}
+
+async fn local_index(pool: &SqlitePool) -> anyhow::Result<Index> {
+    db::relay_index::fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|n| Ok((n.name, (n.pubkey, serde_json::from_str(&n.records)?))))
+        .collect()
+}
+
+/// The relay index published by `peer`, fetched page by page until the relays return no more events. `None` when the
+/// relays have no index for the peer.
+async fn peer_index(relays: &dyn Relays, peer: &XOnlyPublicKey) -> anyhow::Result<Option<Index>> {
+    let mut events: HashMap<EventId, Event> = HashMap::new();
+    let mut until = None;
+    loop {
+        let mut filter = Filter::new()
+            .kind(NameKind::RelayIndex.into())
+            .author(peer.to_string())
+            .limit(PAGE);
+        if let Some(until) = until {
+            filter = filter.until(until);
+        }
+        let page = relays.events(filter).await?;
+        let Some(oldest) = page.iter().map(|e| e.created_at).min() else {
+            break;
+        };
+        let fetched = events.len();
+        events.extend(page.into_iter().map(|e| (e.id, e)));
+        // The next page starts at the oldest event seen, as more events may share its timestamp, and moves past it
+        // once a page brings nothing new.
+        until = if events.len() > fetched {
+            Some(oldest)
+        } else if oldest.as_u64() > 0 {
+            Some(Timestamp::from(oldest.as_u64() - 1))
+        } else {
+            break;
+        };
+    }
+
+    // Relays may return several versions of a replaceable event, only the latest one counts.
+    let mut latest: HashMap<String, (Timestamp, Content)> = HashMap::new();
+    for event in events.into_values() {
+        if event.pubkey != *peer || event.verify().is_err() {
+            continue;
+        }
+        let Ok(content) = serde_json::from_str::<Content>(&event.content) else {
+            tracing::debug!("Invalid relay index event {}", event.id);
+            continue;
+        };
+        if latest
+            .get(&content.name)
+            .map_or(true, |(created_at, _)| *created_at < event.created_at)
+        {
+            latest.insert(content.name.clone(), (event.created_at, content));
+        }
+    }
+
+    if latest.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(
+        latest
+            .into_iter()
+            .map(|(name, (_, content))| (name, (content.pubkey, content.records)))
+            .collect(),
+    ))
+}
+
+fn compare(peer: &str, local: &Index, remote: &Index) -> Vec<Mismatch> {
+    let mismatch =
+        |name: &str, kind: &str, local: Option<String>, remote: Option<String>| Mismatch {
+            peer: peer.to_string(),
+            name: name.to_string(),
+            kind: kind.to_string(),
+            local,
+            remote,
+        };
+    let records = |r: &HashMap<String, String>| serde_json::to_string(r).ok();
+
+    let mut mismatches = vec![];
+    for (name, (pubkey, recs)) in local {
+        match remote.get(name) {
+            None => mismatches.push(mismatch(name, "missing_remote", Some(pubkey.clone()), None)),
+            Some((rpk, _)) if rpk != pubkey => mismatches.push(mismatch(
+                name,
+                "pubkey",
+                Some(pubkey.clone()),
+                Some(rpk.clone()),
+            )),
+            Some((_, rrecs)) if rrecs != recs => {
+                mismatches.push(mismatch(name, "records", records(recs), records(rrecs)));
+            }
+            Some(_) => {}
+        }
+    }
+    for (name, (rpk, _)) in remote {
+        if !local.contains_key(name) {
+            mismatches.push(mismatch(name, "missing_local", None, Some(rpk.clone())));
+        }
+    }
+    mismatches.sort_by(|a, b| a.name.cmp(&b.name));
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{future::BoxFuture, FutureExt};
+    use nostr_sdk::{EventBuilder, Keys, Tag};
+
+    use super::*;
+
+    /// A relay which answers with at most two events per request, newest first, whatever the filter's limit.
+    struct CappedRelay(Vec<Event>);
+
+    impl Relays for CappedRelay {
+        fn events(&self, filter: Filter) -> BoxFuture<'_, anyhow::Result<Vec<Event>>> {
+            let mut events = self
+                .0
+                .iter()
+                .filter(|e| filter.until.map_or(true, |until| e.created_at <= until))
+                .cloned()
+                .collect::<Vec<_>>();
+            events.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+            events.truncate(2);
+            async move { Ok(events) }.boxed()
+        }
+
+        fn send<'a>(&'a self, _url: &'a str, _event: Event) -> BoxFuture<'a, anyhow::Result<()>> {
+            async { Ok(()) }.boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_peer_index_pages() {
+        let keys = Keys::generate();
+        let event = |name: &str, created_at: u64| {
+            let content = Content {
+                name: name.to_string(),
+                pubkey: "pk1".to_string(),
+                records: HashMap::new(),
+            };
+            let mut unsigned = EventBuilder::new(
+                NameKind::RelayIndex.into(),
+                serde_json::to_string(&content).unwrap(),
+                &[Tag::Identifier(name.to_string())],
+            )
+            .to_unsigned_event(keys.public_key());
+            unsigned.created_at = Timestamp::from(created_at);
+            unsigned.id = EventId::new(
+                &unsigned.pubkey,
+                unsigned.created_at,
+                &unsigned.kind,
+                &unsigned.tags,
+                &unsigned.content,
+            );
+            unsigned.sign(&keys).unwrap()
+        };
+        // Two events share a timestamp across a page boundary.
+        let relay = CappedRelay(vec![
+            event("a", 5),
+            event("b", 4),
+            event("c", 3),
+            event("d", 3),
+            event("e", 1),
+        ]);
+        let index = peer_index(&relay, &keys.public_key())
+            .await
+            .unwrap()
+            .unwrap();
+        let mut names = index.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["a", "b", "c", "d", "e"]);
+
+        let empty = CappedRelay(vec![]);
+        assert!(peer_index(&empty, &keys.public_key())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    fn index(entries: &[(&str, &str, &str)]) -> Index {
+        entries
+            .iter()
+            .map(|(name, pk, recs)| {
+                let recs = serde_json::from_str(recs).unwrap();
+                ((*name).to_string(), ((*pk).to_string(), recs))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_compare() {
+        let local = index(&[
+            ("same", "pk1", r#"{"IP4":"127.0.0.1"}"#),
+            ("owner", "pk1", "{}"),
+            ("records", "pk1", r#"{"IP4":"127.0.0.1"}"#),
+            ("onlylocal", "pk1", "{}"),
+        ]);
+        let remote = index(&[
+            ("same", "pk1", r#"{"IP4":"127.0.0.1"}"#),
+            ("owner", "pk2", "{}"),
+            ("records", "pk1", r#"{"IP4":"10.0.0.1"}"#),
+            ("onlyremote", "pk1", "{}"),
+        ]);
+        let kinds = compare("peer", &local, &remote)
+            .into_iter()
+            .map(|m| (m.name, m.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                ("onlylocal".to_string(), "missing_remote".to_string()),
+                ("onlyremote".to_string(), "missing_local".to_string()),
+                ("owner".to_string(), "pubkey".to_string()),
+                ("records".to_string(), "records".to_string()),
+            ]
+        );
+    }
+}
@@ -1,8 +1,11 @@
-use bitcoin::BlockHash;
-use bitcoincore_rpc::{Client, RpcApi};
-use futures::TryStreamExt;
-use nomen_core::{CreateV0, CreateV1, NsidBuilder, SignatureV1, TransferBuilder, TransferV1};
-use secp256k1::{schnorr::Signature, XOnlyPublicKey};
+use std::sync::Arc;
+
+use bitcoin::{BlockHash, Txid};
+use nomen_core::{
+    CommitV1, CreateV0, CreateV1, NsidBuilder, ReleaseBuilder, ReleaseV1, RenewBuilder, RenewV1,
+    RevealV1, TransferSignature, TransferV1,
+};
+use secp256k1::XOnlyPublicKey;
 use sqlx::SqlitePool;
 
 use crate::{
@@ -10,19 +13,29 @@ use crate::{
     db::{self, index::BlockchainIndex, raw::RawBlockchain},
 };
 
+use super::chain::Chain;
+
 enum QueueMessage {
     RawBlockchain(RawBlockchain),
     Index {
         blockheight: i64,
         blockhash: BlockHash,
     },
+    Inputs {
+        blockheight: usize,
+        txid: Txid,
+        prev_txids: Vec<Txid>,
+    },
 }
 
-pub async fn index(config: &Config, pool: &sqlx::Pool<sqlx::Sqlite>) -> Result<(), anyhow::Error> {
+pub async fn index(
+    config: &Config,
+    pool: &sqlx::Pool<sqlx::Sqlite>,
+    chain: Arc<dyn Chain>,
+) -> Result<(), anyhow::Error> {
     // Check if the index is on a stale chain, and rewind the index if necessary
-    rewind_invalid_chain(config.rpc_client()?, pool.clone()).await?;
+    rewind_invalid_chain(chain.clone(), pool.clone()).await?;
 
-    let client = config.rpc_client()?;
     let index_height = db::index::next_index_height(pool)
         .await?
         .max(config.starting_block_height());
@@ -32,18 +45,28 @@ pub async fn index(config: &Config, pool: &sqlx::Pool<sqlx::Sqlite>) -> Result<(
     let min_confirmations = config.confirmations();
 
     // Spawn a thread to query the Bitcoin node for new block data. Messages are sent to the queue.
-    let _thread = spawn_index_thread(client, index_height, sender, min_confirmations);
+    let _thread = spawn_index_thread(chain, index_height, sender, min_confirmations);
 
     // Process the messages from the queue. This will push new NOM OP_RETURNs into the raw_blockchain table.
     process_messages(receiver, pool).await?;
 
+    update_index(config, pool).await?;
+
+    tracing::info!("Blockchain index complete.");
+    Ok(())
+}
+
+/// Indexes the outputs saved to the `raw_blockchain` table which haven't been indexed yet, as of the latest block in
+/// `index_height`.
+pub async fn update_index(
+    config: &Config,
+    pool: &sqlx::Pool<sqlx::Sqlite>,
+) -> Result<(), anyhow::Error> {
     // Update the blockchain index by looping through raw_blockchain table and pocessing the saved outputs.
     update_blockchain_index(config, pool).await?;
 
     // Expire unused transfer cache
-    expire_transfer_cache(pool).await?;
-
-    tracing::info!("Blockchain index complete.");
+    expire_transfer_cache(config, pool).await?;
     Ok(())
 }
 
@@ -67,6 +90,9 @@ async fn process_messages(
                     Some(QueueMessage::Index {blockheight, blockhash}) => {
                         db::index::insert_height(pool, blockheight, &blockhash).await?;
                     },
+                    Some(QueueMessage::Inputs {blockheight, txid, prev_txids}) => {
+                        db::raw::insert_inputs(pool, blockheight, &txid, &prev_txids).await?;
+                    },
                     None => break 'select,
                 }
             }
@@ -80,14 +106,14 @@ async fn process_messages(
 }
 
 fn spawn_index_thread(
-    client: Client,
+    chain: Arc<dyn Chain>,
     index_height: usize,
     sender: tokio::sync::mpsc::Sender<QueueMessage>,
     min_confirmations: usize,
 ) -> tokio::task::JoinHandle<Result<(), anyhow::Error>> {
     tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
-        let mut blockhash = client.get_block_hash(index_height as u64)?;
-        let mut blockinfo = client.get_block_header_info(&blockhash)?;
+        let mut blockhash = chain.block_hash(index_height)?;
+        let mut blockinfo = chain.block_header(&blockhash)?;
 
         loop {
             // If the channel is closed, let's stop
@@ -108,15 +134,17 @@ fn spawn_index_thread(
                 tracing::info!("Index block height {}", blockinfo.height);
             }
 
-            let block = client.get_block(&blockhash)?;
+            let block = chain.block(&blockhash)?;
 
             for (txheight, tx) in block.txdata.iter().enumerate() {
+                let mut nomen = false;
                 for (vout, output) in tx.output.iter().enumerate() {
                     if output.script_pubkey.is_op_return() && output.script_pubkey.len() >= 3 {
                         let b = &output.script_pubkey.as_bytes()[2..];
 
                         // Pre-check if it starts with NOM, so we can filter out some unnecessary errors from the logs
                         if b.starts_with(b"NOM") {
+                            nomen = true;
                             let raw_blockchain = RawBlockchain {
                                 blockhash,
                                 txid: tx.txid(),
@@ -146,11 +174,28 @@ fn spawn_index_thread(
                             .ok();
                     }
                 }
+
+                // Transfer signatures are bound to the transfer transaction they spend, if any.
+                if nomen {
+                    let mut prev_txids = tx
+                        .input
+                        .iter()
+                        .map(|input| input.previous_output.txid)
+                        .collect::<Vec<_>>();
+                    prev_txids.dedup();
+                    sender
+                        .blocking_send(QueueMessage::Inputs {
+                            blockheight: blockinfo.height,
+                            txid: tx.txid(),
+                            prev_txids,
+                        })
+                        .ok();
+                }
             }
             match blockinfo.next_block_hash {
                 Some(next_hash) => {
                     blockhash = next_hash;
-                    blockinfo = client.get_block_header_info(&blockhash)?;
+                    blockinfo = chain.block_header(&blockhash)?;
                 }
                 None => break,
             }
@@ -161,10 +206,12 @@ fn spawn_index_thread(
 }
 
 pub async fn update_blockchain_index(
-    _config: &Config,
+    config: &Config,
     pool: &sqlx::Pool<sqlx::Sqlite>,
 ) -> Result<(), anyhow::Error> {
-    let rows = sqlx::query_as::<_, RawBlockchain>("SELECT * FROM raw_blockchain rb WHERE rb.blockheight > (SELECT coalesce(max(blockheight), 0) FROM index_blockheights_vw);").fetch_all(pool).await?;
+    let expiry = config.name_expiry();
+    db::index::update_claims(pool, expiry).await?;
+    let rows = sqlx::query_as::<_, RawBlockchain>("SELECT * FROM raw_blockchain rb WHERE rb.blockheight > (SELECT coalesce(max(blockheight), 0) FROM index_blockheights_vw) ORDER BY rb.blockheight, rb.txheight, rb.vout;").fetch_all(pool).await?;
     for row in rows {
         if let Ok(create) = CreateV0::try_from(row.data.as_ref()) {
             let i = BlockchainIndex {
@@ -180,7 +227,7 @@ pub async fn update_blockchain_index(
                 txheight: row.txheight,
                 vout: row.vout,
             };
-            index_output(pool, i).await?;
+            index_output(pool, i, expiry).await?;
         } else if let Ok(create) = CreateV1::try_from(row.data.as_ref()) {
             let i = BlockchainIndex {
                 protocol: 1,
@@ -195,7 +242,36 @@ pub async fn update_blockchain_index(
                 txheight: row.txheight,
                 vout: row.vout,
             };
-            index_output(pool, i).await?;
+            index_output(pool, i, expiry).await?;
+        } else if let Ok(commit) = CommitV1::try_from(row.data.as_ref()) {
+            tracing::info!("Name commit found: {}", hex::encode(commit.commitment));
+            db::index::insert_commit(pool, &commit.commitment, &row).await?;
+        } else if let Ok(reveal) = RevealV1::try_from(row.data.as_ref()) {
+            let commit = match reveal.commitment() {
+                Ok(commitment) => {
+                    db::index::revealable_commit(pool, &commitment, row.blockheight).await?
+                }
+                Err(_) => None,
+            };
+            let Some(commit) = commit else {
+                tracing::info!("Ignoring reveal of {} without a matching commit", reveal.name);
+                continue;
+            };
+            let i = BlockchainIndex {
+                protocol: 1,
+                fingerprint: reveal.fingerprint(),
+                nsid: reveal.nsid(),
+                name: Some(reveal.name),
+                pubkey: Some(reveal.pubkey),
+                blockhash: row.blockhash,
+                txid: row.txid,
+                blocktime: row.blocktime,
+                blockheight: row.blockheight,
+                txheight: row.txheight,
+                vout: row.vout,
+            };
+            index_output(pool, i, expiry).await?;
+            db::index::set_commit(pool, &row.txid, row.vout, &commit).await?;
         } else if let Ok(transfer) = TransferV1::try_from(row.data.as_ref()) {
             tracing::info!("Caching transfer for {}", transfer.name);
             let i = BlockchainIndex {
@@ -212,52 +288,127 @@ pub async fn update_blockchain_index(
                 vout: row.vout,
             };
             cache_transfer(pool, i).await?;
-        } else if let Ok(signature) = SignatureV1::try_from(row.data.as_ref()) {
+        } else if let Ok(renew) = RenewV1::try_from(row.data.as_ref()) {
+            tracing::info!("Renewal found");
+            renew_name(pool, expiry, &renew, &row).await?;
+        } else if let Ok(release) = ReleaseV1::try_from(row.data.as_ref()) {
+            tracing::info!("Release found");
+            release_name(pool, expiry, &release, &row).await?;
+        } else if let Ok(signature) = TransferSignature::try_from(row.data.as_ref()) {
             tracing::info!("Signature found");
-            check_signature(pool, signature.signature).await?;
+            check_signature(pool, &signature, &row).await?;
         } else {
             tracing::error!("Index error");
         }
     }
+    db::index::update_claims(pool, expiry).await?;
     Ok(())
 }
 
+async fn release_name(
+    pool: &SqlitePool,
+    expiry: Option<(usize, usize)>,
+    release: &ReleaseV1,
+    row: &RawBlockchain,
+) -> anyhow::Result<()> {
+    db::index::update_name_claims(pool, expiry, &release.fingerprint).await?;
+    let Some(claim) = db::index::live_claim(pool, &release.fingerprint, row.blockheight).await?
+    else {
+        tracing::info!("Ignoring release of a name which isn't live");
+        return Ok(());
+    };
+    let owner: XOnlyPublicKey = claim.pubkey.parse()?;
+    let claim_txid: Txid = claim.txid.parse()?;
+    if ReleaseBuilder::new(&claim.name, &owner, &claim_txid)
+        .verify(&release.signature)
+        .is_err()
+    {
+        tracing::info!(
+            "Ignoring release of {} with an invalid signature",
+            claim.name
+        );
+        return Ok(());
+    }
+
+    tracing::info!("Releasing {} at height {}", claim.name, row.blockheight);
+    db::index::insert_release(pool, &claim, &release.fingerprint, row).await?;
+    // Releases decide which claims are eligible even when names don't expire.
+    db::index::mark_claims(pool, &release.fingerprint).await?;
+    Ok(())
+}
+
+async fn renew_name(
+    pool: &SqlitePool,
+    expiry: Option<(usize, usize)>,
+    renew: &RenewV1,
+    row: &RawBlockchain,
+) -> anyhow::Result<()> {
+    // Claims and renewals seen so far in this run must count when deciding whether the name is still live.
+    db::index::update_name_claims(pool, expiry, &renew.fingerprint).await?;
+    let Some(name) = db::index::live_claim(pool, &renew.fingerprint, row.blockheight).await? else {
+        tracing::info!("Ignoring renewal of a name which isn't live");
+        return Ok(());
+    };
+    let owner: XOnlyPublicKey = name.pubkey.parse()?;
+    let rb = RenewBuilder::new(&name.name, &owner, name.renewed_from as u64);
+    if rb.verify(&renew.signature).is_err() {
+        tracing::info!(
+            "Ignoring renewal of {} with an invalid signature",
+            name.name
+        );
+        return Ok(());
+    }
+
+    tracing::info!("Renewing {} at height {}", name.name, row.blockheight);
+    db::index::insert_renewal(pool, &name, &renew.fingerprint, row).await?;
+    if expiry.is_some() {
+        db::index::mark_claims(pool, &renew.fingerprint).await?;
+    }
+    Ok(())
+}
+
+/// Completes the first pending transfer, in the order of [`db::index::transfer_candidates`], which `signature`
+/// authorises.
 async fn check_signature(
     conn: &sqlx::Pool<sqlx::Sqlite>,
-    signature: Signature,
+    signature: &TransferSignature,
+    signature_row: &RawBlockchain,
 ) -> anyhow::Result<()> {
-    let mut data = sqlx::query_as::<_, (String, String, String, i64)>(
-        "SELECT tc.name, tc.pubkey AS new_owner, n.pubkey, tc.id AS old_owner
-        FROM transfer_cache tc
-        JOIN valid_names_vw n ON tc.fingerprint = n.fingerprint AND tc.name = n.name",
-    )
-    .fetch(conn);
-
-    while let Some(row) = data.try_next().await? {
-        let name = row.0;
-        let new_owner = {
-            let h = hex::decode(row.1.as_bytes())?;
-            XOnlyPublicKey::from_slice(&h)?
-        };
-        let old_owner = {
-            let h = hex::decode(row.2.as_bytes())?;
-            XOnlyPublicKey::from_slice(&h)?
-        };
-        let tb = TransferBuilder {
-            new_pubkey: &new_owner,
-            name: name.as_str(),
+    for candidate in db::index::transfer_candidates(conn, signature_row).await? {
+        let name = candidate.name;
+        let Some(old_owner) = db::index::current_owner(
+            conn,
+            &candidate.fingerprint,
+            &name,
+            signature_row.blockheight,
+        )
+        .await?
+        else {
+            continue;
         };
-        let unsigned_event = tb.unsigned_event(&old_owner);
-        if unsigned_event.add_signature(signature).is_ok() {
+        let new_owner: XOnlyPublicKey = candidate.new_owner.parse()?;
+        let old_owner: XOnlyPublicKey = old_owner.parse()?;
+        let transfer_txid: Txid = candidate.txid.parse()?;
+        if signature
+            .verify(&name, &new_owner, &old_owner, &transfer_txid)
+            .is_ok()
+        {
             tracing::info!(
                 "Valid signature found for {name}, updating owner to {}!",
                 hex::encode(new_owner.serialize())
             );
             let nsid = NsidBuilder::new(name.as_str(), &new_owner).finalize();
-            db::index::update_for_transfer(conn, nsid, new_owner, old_owner, name).await?;
-
-            tracing::info!("Deleting record from transfer_cache");
-            db::index::delete_from_transfer_cache(conn, row.3).await?;
+            db::index::update_for_transfer(conn, nsid, new_owner, old_owner, name.clone()).await?;
+            db::relay_index::queue(conn, &name).await?;
+
+            tracing::info!("Moving completed transfer out of transfer_cache");
+            db::index::complete_transfer_cache(
+                conn,
+                candidate.id,
+                signature_row.txid,
+                signature_row.blockheight,
+            )
+            .await?;
 
             break;
         }
@@ -266,7 +417,16 @@ async fn check_signature(
     Ok(())
 }
 
-async fn index_output(conn: &SqlitePool, index: BlockchainIndex) -> anyhow::Result<()> {
+async fn index_output(
+    conn: &SqlitePool,
+    index: BlockchainIndex,
+    expiry: Option<(usize, usize)>,
+) -> anyhow::Result<()> {
+    // Without expiry a new claim is ranked by its position alone, until a release marks the name.
+    if expiry.is_some() {
+        db::index::mark_claims(conn, &index.fingerprint).await?;
+    }
+
     tracing::info!(
         "NOM output found: {}, name: {:?}, protocol: {}",
         index.nsid,
@@ -314,7 +474,7 @@ async fn cache_transfer(
     Ok(())
 }
 
-async fn rewind_invalid_chain(client: Client, pool: SqlitePool) -> anyhow::Result<()> {
+async fn rewind_invalid_chain(chain: Arc<dyn Chain>, pool: SqlitePool) -> anyhow::Result<()> {
     // Get the latest indexed blockhash and blockheight
     let result = sqlx::query_as::<_, (i32, String)>(
         "SELECT blockheight, blockhash FROM index_height ORDER BY blockheight DESC LIMIT 1;",
@@ -336,7 +496,7 @@ async fn rewind_invalid_chain(client: Client, pool: SqlitePool) -> anyhow::Resul
             let mut stale_block = None;
 
             while let Some(next_blockhash) = next_block {
-                let blockinfo = client.get_block_info(&next_blockhash)?;
+                let blockinfo = chain.block_header(&next_blockhash)?;
                 if blockinfo.confirmations >= 0 {
                     next_block = None;
                 } else {
@@ -346,7 +506,7 @@ async fn rewind_invalid_chain(client: Client, pool: SqlitePool) -> anyhow::Resul
                         blockinfo.height
                     );
                     stale_block = Some(blockinfo.height);
-                    next_block = blockinfo.previousblockhash;
+                    next_block = blockinfo.previous_block_hash;
                 }
             }
 
@@ -357,11 +517,16 @@ async fn rewind_invalid_chain(client: Client, pool: SqlitePool) -> anyhow::Resul
     // Delete entries from blockchain table
     if let Some(stale_block) = stale_block {
         tracing::info!("Reindexing beginning at height {stale_block}");
+        db::index::unexpire_transfers(&pool, stale_block as i64).await?;
         let mut tx = pool.begin().await?;
         sqlx::query("DELETE FROM raw_blockchain WHERE blockheight >= ?;")
             .bind(stale_block as i64)
             .execute(&mut tx)
             .await?;
+        sqlx::query("DELETE FROM raw_blockchain_inputs WHERE blockheight >= ?;")
+            .bind(stale_block as i64)
+            .execute(&mut tx)
+            .await?;
         sqlx::query("DELETE FROM blockchain_index WHERE blockheight >= ?;")
             .bind(stale_block as i64)
             .execute(&mut tx)
@@ -374,6 +539,18 @@ async fn rewind_invalid_chain(client: Client, pool: SqlitePool) -> anyhow::Resul
             .bind(stale_block as i64)
             .execute(&mut tx)
             .await?;
+        sqlx::query("DELETE FROM name_commits WHERE blockheight >= ?;")
+            .bind(stale_block as i64)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("DELETE FROM name_renewals WHERE blockheight >= ?;")
+            .bind(stale_block as i64)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("DELETE FROM name_releases WHERE blockheight >= ?;")
+            .bind(stale_block as i64)
+            .execute(&mut tx)
+            .await?;
         sqlx::query("DELETE FROM index_height WHERE blockheight >= ?;")
             .bind(stale_block as i64)
             .execute(&mut tx)
@@ -384,16 +561,20 @@ async fn rewind_invalid_chain(client: Client, pool: SqlitePool) -> anyhow::Resul
     Ok(())
 }
 
-async fn expire_transfer_cache(pool: &sqlx::Pool<sqlx::Sqlite>) -> anyhow::Result<()> {
-    tracing::info!("Starting transfer cache expiration.");
+/// Expires the transfers which are too old to be signed. Most runs have nothing to expire, so nothing is written
+/// unless the oldest pending transfer is due.
+async fn expire_transfer_cache(config: &Config, pool: &SqlitePool) -> anyhow::Result<()> {
+    let expiry_blocks = config.transfer_expiry_blocks();
     let (index_height,) = sqlx::query_as::<_, (i64,)>("SELECT max(blockheight) FROM index_height;")
         .fetch_one(pool)
         .await?;
-    sqlx::query("INSERT INTO old_transfer_cache SELECT * FROM transfer_cache WHERE blockheight < (? - 100);").bind(index_height).execute(pool).await?;
-    sqlx::query("DELETE FROM transfer_cache WHERE blockheight < (? - 100);")
-        .bind(index_height)
-        .execute(pool)
-        .await?;
-    tracing::info!("Finished transfer cache expiration.");
+    match db::index::next_transfer_expiry(pool, expiry_blocks).await? {
+        Some(next_expiry) if next_expiry <= index_height => {
+            let expired =
+                db::index::expire_transfer_cache(pool, index_height, expiry_blocks).await?;
+            tracing::info!("Expired {expired} transfers at height {index_height}.");
+        }
+        _ => tracing::debug!("No transfers to expire at height {index_height}."),
+    }
     Ok(())
 }
@@ -0,0 +1,41 @@
+use bitcoin::{Block, BlockHash};
+use bitcoincore_rpc::RpcApi;
+
+/// A block header as seen by the node. Blocks which are no longer on the best chain have negative confirmations.
+pub struct BlockHeader {
+    pub hash: BlockHash,
+    pub height: usize,
+    pub time: usize,
+    pub confirmations: i32,
+    pub previous_block_hash: Option<BlockHash>,
+    pub next_block_hash: Option<BlockHash>,
+}
+
+/// The Bitcoin node scanned by the indexer. The RPC client implements it, and the indexer tests use an in-memory chain.
+pub trait Chain: Send + Sync {
+    fn block_hash(&self, height: usize) -> anyhow::Result<BlockHash>;
+    fn block_header(&self, hash: &BlockHash) -> anyhow::Result<BlockHeader>;
+    fn block(&self, hash: &BlockHash) -> anyhow::Result<Block>;
+}
+
+impl Chain for bitcoincore_rpc::Client {
+    fn block_hash(&self, height: usize) -> anyhow::Result<BlockHash> {
+        Ok(self.get_block_hash(height as u64)?)
+    }
+
+    fn block_header(&self, hash: &BlockHash) -> anyhow::Result<BlockHeader> {
+        let info = self.get_block_header_info(hash)?;
+        Ok(BlockHeader {
+            hash: info.hash,
+            height: info.height,
+            time: info.time,
+            confirmations: info.confirmations,
+            previous_block_hash: info.previous_block_hash,
+            next_block_hash: info.next_block_hash,
+        })
+    }
+
+    fn block(&self, hash: &BlockHash) -> anyhow::Result<Block> {
+        Ok(self.get_block(hash)?)
+    }
+}
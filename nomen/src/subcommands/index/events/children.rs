@@ -0,0 +1,115 @@
+use anyhow::bail;
+use nomen_core::{EventExtractor, Name, NameKind, Nsid, NsidBuilder};
+use nostr_sdk::{Event, Filter};
+use sqlx::SqlitePool;
+
+use crate::{
+    db::{
+        self,
+        children::{Child, Delegation},
+    },
+    subcommands::relays::Relays,
+};
+
+pub async fn children(relays: &dyn Relays, pool: &SqlitePool) -> anyhow::Result<()> {
+    tracing::info!("Beginning indexing child delegation events.");
+    let since = db::children::last_delegation_time(pool).await? + 1;
+    let filter = Filter::new()
+        .kind(NameKind::Children.into())
+        .since(since.into());
+
+    let events = relays.events(filter).await?;
+
+    for event in events {
+        match delegation(&event) {
+            Ok(delegation) => {
+                if db::children::save(pool, &delegation).await? {
+                    tracing::info!(
+                        "Saved {} children of {} from event {}",
+                        delegation.children.len(),
+                        delegation.parent,
+                        delegation.event_id
+                    );
+                }
+            }
+            Err(err) => tracing::debug!("Invalid child delegation event {}: {err}", event.id),
+        }
+    }
+
+    tracing::info!("Child delegation events indexing complete.");
+    Ok(())
+}
+
+/// Parses a kind 38302 delegation event. Its content is a JSON array of `[label, pubkey]` pairs, each of which
+/// delegates `label.parent` to `pubkey`.
+pub fn delegation(event: &Event) -> anyhow::Result<Delegation> {
+    let parent = Name::parse_hierarchical(&event.extract_name()?)?;
+    let calculated: Nsid = event.clone().try_into()?;
+    if event.extract_nsid()? != calculated {
+        bail!("Invalid nsid");
+    }
+
+    let children = event
+        .extract_children(parent.as_ref())?
+        .into_iter()
+        .map(|(name, pubkey)| {
+            let name = Name::parse_hierarchical(&name)?;
+            if name.parent() != Some(parent.as_ref()) {
+                bail!("Invalid child label in {name}");
+            }
+            Ok(Child {
+                nsid: NsidBuilder::new(name.as_ref(), &pubkey)
+                    .finalize()
+                    .to_string(),
+                name: name.to_string(),
+                pubkey: pubkey.to_string(),
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(Delegation {
+        parent: parent.to_string(),
+        parent_pubkey: event.pubkey.to_string(),
+        event_id: event.id.to_string(),
+        created_at: event.created_at.as_i64(),
+        raw_event: event.as_json(),
+        children,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::{EventBuilder, Keys, Tag, TagKind};
+
+    use super::*;
+
+    fn event(keys: &Keys, parent: &str, content: &str) -> Event {
+        let nsid = NsidBuilder::new(parent, &keys.public_key()).finalize();
+        EventBuilder::new(
+            NameKind::Children.into(),
+            content,
+            &[
+                Tag::Identifier(nsid.to_string()),
+                Tag::Generic(TagKind::Custom("nom".to_owned()), vec![parent.to_owned()]),
+            ],
+        )
+        .to_event(keys)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_delegation() {
+        let keys = Keys::generate();
+        let child = Keys::generate().public_key();
+        let content = format!(r#"[["team","{child}"]]"#);
+
+        let d = delegation(&event(&keys, "ourorg", &content)).unwrap();
+        assert_eq!(d.parent, "ourorg");
+        assert_eq!(d.children.len(), 1);
+        assert_eq!(d.children[0].name, "team.ourorg");
+        assert_eq!(d.children[0].pubkey, child.to_string());
+
+        let bad = format!(r#"[["dev.team","{child}"]]"#);
+        assert!(delegation(&event(&keys, "ourorg", &bad)).is_err());
+    }
+}
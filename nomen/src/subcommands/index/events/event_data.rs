@@ -1,7 +1,5 @@
-use std::collections::HashMap;
-
 use anyhow::bail;
-use nomen_core::{EventExtractor, Hash160, Name, Nsid};
+use nomen_core::{EventExtractor, Hash160, Name, Nsid, NsidBuilder, RecordDelegation, Records};
 use nostr_sdk::{Event, EventId};
 use secp256k1::XOnlyPublicKey;
 
@@ -15,7 +13,7 @@ pub struct EventData {
     pub name: Name,
     pub created_at: i64,
     pub raw_content: String,
-    pub records: Option<HashMap<String, String>>,
+    pub records: Option<Records>,
     pub raw_event: String,
 }
 
@@ -36,7 +34,7 @@ impl EventData {
             nsid,
             calculated_nsid,
             pubkey: event.pubkey,
-            name: name.parse()?,
+            name: Name::parse_hierarchical(&name)?,
             created_at: event.created_at.as_i64(),
             raw_content: event.content.clone(),
             records,
@@ -44,6 +42,18 @@ impl EventData {
         })
     }
 
+    /// Event data for a record event signed by a record delegate. The event is attributed to the delegating owner.
+    pub fn from_delegated_event(
+        event: &Event,
+        delegation: &RecordDelegation,
+    ) -> anyhow::Result<Self> {
+        delegation.authorizes(event)?;
+        let mut ed = EventData::from_event(event)?;
+        ed.pubkey = delegation.owner;
+        ed.calculated_nsid = NsidBuilder::new(ed.name.as_ref(), &delegation.owner).finalize();
+        Ok(ed)
+    }
+
     #[allow(unused)]
     pub fn validate(&self) -> anyhow::Result<()> {
         if self.nsid != self.calculated_nsid {
@@ -1,6 +1,10 @@
+mod children;
 mod event_data;
+mod record_delegates;
 mod records;
 pub mod relay_index;
 
+pub use children::*;
 pub use event_data::*;
+pub use record_delegates::*;
 pub use records::*;
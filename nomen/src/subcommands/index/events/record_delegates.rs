@@ -0,0 +1,48 @@
+use nomen_core::{NameKind, RecordDelegation};
+use nostr_sdk::{Event, Filter};
+use sqlx::SqlitePool;
+
+use crate::{
+    db::{self, record_delegate::RecordDelegate},
+    subcommands::relays::Relays,
+};
+
+pub async fn record_delegates(relays: &dyn Relays, pool: &SqlitePool) -> anyhow::Result<()> {
+    tracing::info!("Beginning indexing record delegation events.");
+    let since = db::record_delegate::last_delegation_time(pool).await? + 1;
+    let filter = Filter::new()
+        .kind(NameKind::RecordDelegate.into())
+        .since(since.into());
+
+    let events = relays.events(filter).await?;
+
+    for event in events {
+        match record_delegate(&event) {
+            Ok(delegate) => {
+                if db::record_delegate::save(pool, &delegate).await? {
+                    tracing::info!(
+                        "Saved record delegate of {} from event {}",
+                        delegate.name,
+                        delegate.event_id
+                    );
+                }
+            }
+            Err(err) => tracing::debug!("Invalid record delegation event {}: {err}", event.id),
+        }
+    }
+
+    tracing::info!("Record delegation events indexing complete.");
+    Ok(())
+}
+
+fn record_delegate(event: &Event) -> anyhow::Result<RecordDelegate> {
+    let delegation = RecordDelegation::try_from(event)?;
+    Ok(RecordDelegate {
+        name: delegation.name,
+        owner: delegation.owner.to_string(),
+        delegate: delegation.delegate.map(|pk| pk.to_string()),
+        event_id: event.id.to_string(),
+        created_at: event.created_at.as_i64(),
+        raw_event: event.as_json(),
+    })
+}
@@ -1,17 +1,24 @@
-use std::time::Duration;
-
-use nomen_core::NameKind;
+use anyhow::anyhow;
+use nomen_core::{EventExtractor, NameKind, RecordDelegation, Records};
 use nostr_sdk::{Event, Filter};
 use sqlx::SqlitePool;
 
-use crate::{config::Config, db, subcommands::index::events::EventData};
+use crate::{
+    db,
+    subcommands::{index::events::EventData, relays::Relays},
+};
 
-pub async fn records(config: &Config, pool: &SqlitePool) -> anyhow::Result<()> {
+pub async fn records(relays: &dyn Relays, pool: &SqlitePool) -> anyhow::Result<()> {
     tracing::info!("Beginning indexing record events.");
-    let events = latest_events(config, pool).await?;
+    let events = latest_events(relays, pool).await?;
     for event in events {
-        match EventData::from_event(&event) {
-            Ok(ed) => save_event(pool, ed).await?,
+        match event_data(pool, &event).await {
+            Ok(ed) => {
+                for err in ed.records.iter().flat_map(Records::validate) {
+                    tracing::warn!("Event {} for {} has {err}", ed.event_id, ed.name);
+                }
+                save_event(pool, ed).await?;
+            }
             Err(err) => tracing::debug!("Invalid event: {err}"),
         }
     }
@@ -20,7 +27,21 @@ pub async fn records(config: &Config, pool: &SqlitePool) -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn save_event(pool: &SqlitePool, ed: EventData) -> anyhow::Result<()> {
+/// Parses a record event. An event with an `owner` tag other than its signer is signed by a record delegate, and is
+/// only accepted while the owner's latest delegation names that signer.
+pub async fn event_data(pool: &SqlitePool, event: &Event) -> anyhow::Result<EventData> {
+    let Some(owner) = event.extract_owner()?.filter(|owner| *owner != event.pubkey) else {
+        return EventData::from_event(event);
+    };
+    let name = event.extract_name()?;
+    let delegate = db::record_delegate::find(pool, &name, &owner.to_string())
+        .await?
+        .ok_or_else(|| anyhow!("No record delegation for {name}"))?;
+    let delegation = RecordDelegation::try_from(&Event::from_json(delegate.raw_event)?)?;
+    EventData::from_delegated_event(event, &delegation)
+}
+
+pub async fn save_event(pool: &SqlitePool, ed: EventData) -> anyhow::Result<()> {
     tracing::info!("Saving valid event {}", ed.event_id);
     let EventData {
         event_id,
@@ -55,7 +76,7 @@ async fn save_event(pool: &SqlitePool, ed: EventData) -> anyhow::Result<()> {
 }
 
 async fn latest_events(
-    config: &Config,
+    relays: &dyn Relays,
     pool: &sqlx::Pool<sqlx::Sqlite>,
 ) -> anyhow::Result<Vec<Event>> {
     let records_time = db::name::last_records_time(pool).await? + 1;
@@ -63,10 +84,5 @@ async fn latest_events(
         .kind(NameKind::Name.into())
         .since(records_time.into());
 
-    let (_keys, client) = config.nostr_random_client().await?;
-    let events = client
-        .get_events_of(vec![filter], Some(Duration::from_secs(10)))
-        .await?;
-    client.disconnect().await?;
-    Ok(events)
+    relays.events(filter).await
 }
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 
+use nomen_core::NameKind;
 use nostr_sdk::{EventBuilder, Keys, Tag};
 use secp256k1::SecretKey;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 
 use crate::{
@@ -10,6 +11,7 @@ use crate::{
     db::{self, relay_index::Name},
 };
 
+/// Queues relay index events in the outbox. They are sent by `outbox::deliver`.
 pub async fn publish(config: &Config, pool: &SqlitePool, use_queue: bool) -> anyhow::Result<()> {
     if !config.publish_index() {
         return Ok(());
@@ -19,26 +21,26 @@ pub async fn publish(config: &Config, pool: &SqlitePool, use_queue: bool) -> any
         .expect("Missing config validation for secret")
         .into();
     let keys = Keys::new(sk);
-    let (_, client) = config.nostr_random_client().await?;
 
     tracing::info!("Publishing relay index.");
+    db::relay_index::queue_changed(pool).await?;
     let names = if use_queue {
         db::relay_index::fetch_all_queued(pool).await?
     } else {
         db::relay_index::fetch_all(pool).await?
     };
-    send_events(pool, names, keys, &client).await?;
+    queue_events(config, pool, names, &keys).await?;
+    retract_stale(config, pool, &keys).await?;
     tracing::info!("Publishing relay index complete.");
 
-    client.disconnect().await.ok();
     Ok(())
 }
 
-async fn send_events(
+async fn queue_events(
+    config: &Config,
     conn: &SqlitePool,
     names: Vec<Name>,
-    keys: Keys,
-    client: &nostr_sdk::Client,
+    keys: &Keys,
 ) -> Result<(), anyhow::Error> {
     for name in names {
         let records: HashMap<String, String> = serde_json::from_str(&name.records)?;
@@ -49,31 +51,68 @@ async fn send_events(
         };
         let content_serialize = serde_json::to_string(&content)?;
         let event = EventBuilder::new(
-            nostr_sdk::Kind::ParameterizedReplaceable(38301),
+            NameKind::RelayIndex.into(),
             content_serialize,
             &[Tag::Identifier(name.name.clone())],
         )
-        .to_event(&keys)?;
+        .to_event(keys)?;
 
-        match client.send_event(event.clone()).await {
-            Ok(s) => {
-                tracing::info!("Broadcast event id {s}");
-                db::relay_index::delete(conn, &name.name).await?;
-            }
-            Err(e) => {
-                tracing::error!(
-                    "Unable to broadcast event {} during relay index publish: {e}",
-                    event.id
-                );
-            }
-        }
+        tracing::info!("Queueing relay index event {} for {}", event.id, name.name);
+        db::outbox::enqueue(conn, "index", Some(&name.name), &event, &config.relays()).await?;
+        db::relay_index::mark_published(
+            conn,
+            &name.name,
+            &content.pubkey,
+            &event.id.to_string(),
+            &event.as_json(),
+        )
+        .await?;
+        db::relay_index::delete(conn, &name.name).await?;
+    }
+    Ok(())
+}
+
+/// Queues NIP-09 deletions for published index entries whose names are no longer valid, so that readers of the
+/// relay index do not see names which no longer exist.
+async fn retract_stale(config: &Config, conn: &SqlitePool, keys: &Keys) -> anyhow::Result<()> {
+    for stale in db::relay_index::fetch_stale(conn).await? {
+        let event = EventBuilder::new(
+            nostr_sdk::Kind::EventDeletion,
+            "name no longer indexed",
+            &[
+                Tag::Event(stale.event_id.parse()?, None, None),
+                Tag::A {
+                    kind: NameKind::RelayIndex.into(),
+                    public_key: keys.public_key(),
+                    identifier: stale.name.clone(),
+                    relay_url: None,
+                },
+            ],
+        )
+        .to_event(keys)?;
+
+        tracing::info!(
+            "Queueing retraction {} of relay index entry for {}",
+            event.id,
+            stale.name
+        );
+        db::outbox::enqueue(
+            conn,
+            "deletion",
+            Some(&stale.name),
+            &event,
+            &config.relays(),
+        )
+        .await?;
+        db::relay_index::delete_published(conn, &stale.name).await?;
     }
     Ok(())
 }
 
-#[derive(Serialize)]
-struct Content {
-    name: String,
-    pubkey: String,
-    records: HashMap<String, String>,
+/// Content of a kind 38301 relay index event.
+#[derive(Serialize, Deserialize)]
+pub struct Content {
+    pub name: String,
+    pub pubkey: String,
+    pub records: HashMap<String, String>,
 }
@@ -1,14 +1,45 @@
-use crate::{config::Config, db};
+use std::sync::Arc;
 
-mod blockchain;
+use sqlx::SqlitePool;
+
+use crate::{
+    config::Config,
+    db,
+    subcommands::{
+        outbox,
+        relays::{NostrRelays, Relays},
+    },
+};
+
+pub mod blockchain;
+pub mod chain;
 pub mod events;
+#[cfg(test)]
+mod tests;
 
 pub async fn index(config: &Config) -> anyhow::Result<()> {
     let pool = config.sqlite().await?;
-    blockchain::index(config, &pool).await?;
-    events::records(config, &pool).await?;
-    events::relay_index::publish(config, &pool, true).await?;
+    let chain = Arc::new(config.rpc_client()?);
+    let relays = NostrRelays::new(config);
+    let indexed = run(config, &pool, chain, &relays).await;
+    relays.disconnect().await;
+    indexed
+}
+
+/// Runs the indexing pipeline against `chain` and `relays`.
+pub async fn run(
+    config: &Config,
+    pool: &SqlitePool,
+    chain: Arc<dyn chain::Chain>,
+    relays: &dyn Relays,
+) -> anyhow::Result<()> {
+    blockchain::index(config, pool, chain).await?;
+    events::record_delegates(relays, pool).await?;
+    events::records(relays, pool).await?;
+    events::children(relays, pool).await?;
+    events::relay_index::publish(config, pool, true).await?;
+    outbox::deliver(config, pool, relays).await?;
 
-    db::event_log::save(&pool, "index", "").await?;
+    db::event_log::save(pool, "index", "").await?;
     Ok(())
 }
@@ -0,0 +1,865 @@
+//! End-to-end runs of the indexing pipeline against an in-memory chain and relays.
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::anyhow;
+use bitcoin::{
+    absolute::LockTime,
+    block::{Header, Version},
+    hash_types::TxMerkleNode,
+    hashes::Hash,
+    script::PushBytesBuf,
+    Block, BlockHash, CompactTarget, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid,
+    Witness,
+};
+use futures::{future::BoxFuture, FutureExt};
+use nomen_core::{
+    CreateBuilder, Name, NameKind, Records, RecordsBuilder, ReleaseBuilder, TransferAuthorization,
+    TransferBuilder,
+};
+use nostr_sdk::{Event, EventBuilder, Filter, Keys, Tag};
+use secp256k1::SecretKey;
+use sqlx::SqlitePool;
+
+use super::{
+    chain::{BlockHeader, Chain},
+    run,
+};
+use crate::{
+    config::{Cli, Config, ConfigFile, Subcommand},
+    db,
+    subcommands::{outbox, relays::Relays},
+};
+
+const RELAY: &str = "wss://relay.test";
+
+/// A chain starting with an empty genesis block. Blocks replaced by a reorg are kept as stale blocks, the way a node
+/// still knows about them.
+struct FakeChain {
+    state: Mutex<ChainState>,
+}
+
+struct ChainState {
+    best: Vec<Block>,
+    stale: HashMap<BlockHash, (usize, Block)>,
+    nonce: u32,
+}
+
+impl FakeChain {
+    fn new() -> FakeChain {
+        let chain = FakeChain {
+            state: Mutex::new(ChainState {
+                best: vec![],
+                stale: HashMap::new(),
+                nonce: 0,
+            }),
+        };
+        chain.mine(vec![]);
+        chain
+    }
+
+    fn tip(&self) -> usize {
+        self.state.lock().unwrap().best.len() - 1
+    }
+
+    fn hash(&self, height: usize) -> BlockHash {
+        self.state.lock().unwrap().best[height].block_hash()
+    }
+
+    /// Mines a block with one transaction per `OP_RETURN`, after a coinbase without one. Returns the txids of the
+    /// `OP_RETURN` transactions.
+    fn mine(&self, op_returns: Vec<Vec<u8>>) -> Vec<Txid> {
+        self.mine_txs(op_returns.into_iter().map(|o| (vec![], vec![o])).collect())
+    }
+
+    /// Mines a block with a transaction for each `(spent txids, OP_RETURNs)`, after a coinbase without one. Returns
+    /// their txids.
+    fn mine_txs(&self, txs: Vec<(Vec<Txid>, Vec<Vec<u8>>)>) -> Vec<Txid> {
+        let mut state = self.state.lock().unwrap();
+        state.nonce += 1;
+        let nonce = state.nonce;
+        let height = state.best.len();
+
+        let tx = |lock_time: u32, spends: Vec<Txid>, output: Vec<ScriptBuf>| Transaction {
+            version: 2,
+            lock_time: LockTime::from_consensus(lock_time),
+            input: spends
+                .into_iter()
+                .map(|txid| TxIn {
+                    previous_output: OutPoint::new(txid, 0),
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::MAX,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output: output
+                .into_iter()
+                .map(|script_pubkey| TxOut {
+                    value: 0,
+                    script_pubkey,
+                })
+                .collect(),
+        };
+        let mut txdata = vec![tx(nonce, vec![], vec![ScriptBuf::new()])];
+        for (i, (spends, op_returns)) in txs.into_iter().enumerate() {
+            let output = op_returns
+                .into_iter()
+                .map(|o| ScriptBuf::new_op_return(&PushBytesBuf::try_from(o).unwrap()))
+                .collect();
+            txdata.push(tx(nonce * 1000 + i as u32, spends, output));
+        }
+
+        let block = Block {
+            header: Header {
+                version: Version::ONE,
+                prev_blockhash: state
+                    .best
+                    .last()
+                    .map_or_else(BlockHash::all_zeros, Block::block_hash),
+                merkle_root: TxMerkleNode::all_zeros(),
+                time: 1_700_000_000 + height as u32 * 600,
+                bits: CompactTarget::from_consensus(0),
+                nonce,
+            },
+            txdata,
+        };
+        let txids = block.txdata[1..].iter().map(Transaction::txid).collect();
+        state.best.push(block);
+        txids
+    }
+
+    fn mine_to(&self, height: usize) {
+        while self.tip() < height {
+            self.mine(vec![]);
+        }
+    }
+
+    /// Replaces the blocks from `height` on, which become stale.
+    fn reorg(&self, height: usize) {
+        let mut state = self.state.lock().unwrap();
+        let replaced = state.best.split_off(height);
+        for (offset, block) in replaced.into_iter().enumerate() {
+            state
+                .stale
+                .insert(block.block_hash(), (height + offset, block));
+        }
+    }
+}
+
+impl Chain for FakeChain {
+    fn block_hash(&self, height: usize) -> anyhow::Result<BlockHash> {
+        let state = self.state.lock().unwrap();
+        state
+            .best
+            .get(height)
+            .map(Block::block_hash)
+            .ok_or_else(|| anyhow!("Block height out of range"))
+    }
+
+    fn block_header(&self, hash: &BlockHash) -> anyhow::Result<BlockHeader> {
+        let state = self.state.lock().unwrap();
+        if let Some(height) = state.best.iter().position(|b| b.block_hash() == *hash) {
+            let block = &state.best[height];
+            return Ok(BlockHeader {
+                hash: *hash,
+                height,
+                time: block.header.time as usize,
+                confirmations: i32::try_from(state.best.len() - height)?,
+                previous_block_hash: height.checked_sub(1).map(|_| block.header.prev_blockhash),
+                next_block_hash: state.best.get(height + 1).map(Block::block_hash),
+            });
+        }
+        let (height, block) = state
+            .stale
+            .get(hash)
+            .ok_or_else(|| anyhow!("Block not found"))?;
+        Ok(BlockHeader {
+            hash: *hash,
+            height: *height,
+            time: block.header.time as usize,
+            confirmations: -1,
+            previous_block_hash: Some(block.header.prev_blockhash),
+            next_block_hash: None,
+        })
+    }
+
+    fn block(&self, hash: &BlockHash) -> anyhow::Result<Block> {
+        let state = self.state.lock().unwrap();
+        state
+            .best
+            .iter()
+            .find(|b| b.block_hash() == *hash)
+            .or_else(|| state.stale.get(hash).map(|(_, b)| b))
+            .cloned()
+            .ok_or_else(|| anyhow!("Block not found"))
+    }
+}
+
+/// Relays which serve `events` and record what is sent to them.
+#[derive(Default)]
+struct FakeRelays {
+    events: Mutex<Vec<Event>>,
+    sent: Mutex<Vec<(String, Event)>>,
+}
+
+impl Relays for FakeRelays {
+    fn events(&self, filter: Filter) -> BoxFuture<'_, anyhow::Result<Vec<Event>>> {
+        let events = self
+            .events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| filter.kinds.is_empty() || filter.kinds.contains(&e.kind))
+            .filter(|e| filter.since.map_or(true, |since| e.created_at >= since))
+            .cloned()
+            .collect();
+        async move { Ok(events) }.boxed()
+    }
+
+    fn send<'a>(&'a self, url: &'a str, event: Event) -> BoxFuture<'a, anyhow::Result<()>> {
+        self.sent.lock().unwrap().push((url.to_owned(), event));
+        async { Ok(()) }.boxed()
+    }
+}
+
+struct Harness {
+    config: Config,
+    pool: SqlitePool,
+    path: PathBuf,
+    chain: Arc<FakeChain>,
+    relays: FakeRelays,
+}
+
+impl Harness {
+    /// A harness indexing regtest with one confirmation, after `configure` has adjusted the config.
+    async fn new(configure: impl FnOnce(&mut ConfigFile)) -> Harness {
+        static SCRATCH: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "nomen-index-{}-{}.db",
+            std::process::id(),
+            SCRATCH.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let mut file = ConfigFile {
+            data: Some(path.clone()),
+            ..Default::default()
+        };
+        file.rpc.network = Some(bitcoin::Network::Regtest);
+        file.server.confirmations = Some(1);
+        file.nostr.relays = Some(vec![RELAY.to_owned()]);
+        configure(&mut file);
+        let config = Config::new(
+            Cli {
+                config: "nomen.toml".into(),
+                subcommand: Subcommand::Index,
+            },
+            file,
+        );
+        let pool = db::initialize(&config).await.unwrap();
+
+        Harness {
+            config,
+            pool,
+            path,
+            chain: Arc::new(FakeChain::new()),
+            relays: FakeRelays::default(),
+        }
+    }
+
+    async fn index(&self) {
+        run(&self.config, &self.pool, self.chain.clone(), &self.relays)
+            .await
+            .unwrap();
+    }
+
+    /// `(name, pubkey)` of every valid name.
+    async fn names(&self) -> Vec<(Option<String>, Option<String>)> {
+        sqlx::query_as("SELECT name, pubkey FROM valid_names_vw ORDER BY name;")
+            .fetch_all(&self.pool)
+            .await
+            .unwrap()
+    }
+
+    /// `(txid, outcome)` of the transfers of `name`, in block order.
+    async fn transfers_of(&self, name: &str) -> Vec<(String, String)> {
+        sqlx::query_as(
+            "SELECT txid, outcome FROM transfer_status_vw WHERE name = ? ORDER BY blockheight, txheight, vout;",
+        )
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await
+        .unwrap()
+    }
+
+    /// `(name, outcome)` of every transfer.
+    async fn transfers(&self) -> Vec<(String, String)> {
+        sqlx::query_as("SELECT name, outcome FROM transfer_status_vw ORDER BY name;")
+            .fetch_all(&self.pool)
+            .await
+            .unwrap()
+    }
+
+    async fn close(self) {
+        self.pool.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let mut file = self.path.clone().into_os_string();
+            file.push(suffix);
+            std::fs::remove_file(PathBuf::from(file)).ok();
+        }
+    }
+}
+
+fn keys(n: u8) -> Keys {
+    Keys::new(SecretKey::from_slice(&[n; 32]).unwrap())
+}
+
+fn create(keys: &Keys, name: &str) -> Vec<u8> {
+    CreateBuilder::new(&keys.public_key(), name).v1_op_return()
+}
+
+fn transfer(new_owner: &Keys, name: &str) -> Vec<u8> {
+    TransferBuilder {
+        new_pubkey: &new_owner.public_key(),
+        name,
+    }
+    .transfer_op_return()
+}
+
+fn authorize(owner: &Keys, new_owner: &Keys, name: &str, transfer_txid: &Txid) -> Vec<u8> {
+    TransferAuthorization {
+        name,
+        new_owner: &new_owner.public_key(),
+        transfer_txid,
+    }
+    .signature_op_return(owner)
+    .unwrap()
+}
+
+fn sign_v1(owner: &Keys, new_owner: &Keys, name: &str) -> Vec<u8> {
+    TransferBuilder {
+        new_pubkey: &new_owner.public_key(),
+        name,
+    }
+    .signature_op_return(*owner)
+    .unwrap()
+}
+
+fn release(owner: &Keys, name: &str, claim_txid: &Txid) -> Vec<u8> {
+    ReleaseBuilder::new(name, &owner.public_key(), claim_txid)
+        .op_return(owner)
+        .unwrap()
+}
+
+fn owned(name: &str, keys: &Keys) -> (Option<String>, Option<String>) {
+    (
+        Some(name.to_owned()),
+        Some(hex::encode(keys.public_key().serialize())),
+    )
+}
+
+#[tokio::test]
+async fn test_conflicting_creates() {
+    let h = Harness::new(|_| {}).await;
+    let (k1, k2) = (keys(1), keys(2));
+    h.chain
+        .mine(vec![create(&k2, "alice"), create(&k1, "alice")]);
+    h.chain.mine(vec![create(&k1, "alice"), create(&k1, "bob")]);
+    h.index().await;
+
+    assert_eq!(
+        h.names().await,
+        vec![owned("alice", &k2), owned("bob", &k1)]
+    );
+    h.close().await;
+}
+
+#[tokio::test]
+async fn test_v0_upgrade() {
+    let h = Harness::new(|_| {}).await;
+    let k1 = keys(1);
+    h.chain.mine(vec![
+        CreateBuilder::new(&k1.public_key(), "alice").v0_op_return()
+    ]);
+    h.index().await;
+    assert_eq!(h.names().await, vec![(None, None)]);
+
+    h.chain.mine(vec![]);
+    h.chain.mine(vec![create(&k1, "alice")]);
+    h.index().await;
+
+    let upgraded: Vec<(String, i64, i64)> =
+        sqlx::query_as("SELECT name, protocol, v1_upgrade_blockheight FROM valid_names_vw;")
+            .fetch_all(&h.pool)
+            .await
+            .unwrap();
+    assert_eq!(upgraded, vec![("alice".to_owned(), 1, 3)]);
+    assert_eq!(h.names().await, vec![owned("alice", &k1)]);
+    h.close().await;
+}
+
+#[tokio::test]
+async fn test_transfer_window() {
+    let h = Harness::new(|_| {}).await;
+    let (k1, k2) = (keys(1), keys(2));
+    h.chain.mine(vec![create(&k1, "alice"), create(&k1, "bob")]);
+    let txids = h
+        .chain
+        .mine(vec![transfer(&k2, "alice"), transfer(&k2, "bob")]);
+    h.index().await;
+    assert_eq!(
+        h.transfers().await,
+        vec![
+            ("alice".to_owned(), "pending".to_owned()),
+            ("bob".to_owned(), "pending".to_owned())
+        ]
+    );
+
+    // Within the window
+    h.chain.mine_to(101);
+    h.chain.mine_txs(vec![(
+        vec![txids[0]],
+        vec![authorize(&k1, &k2, "alice", &txids[0])],
+    )]);
+    h.index().await;
+
+    // Past the window, the transfer expires before the signature is seen
+    h.chain.mine_to(103);
+    h.index().await;
+    h.chain.mine_txs(vec![(
+        vec![txids[1]],
+        vec![authorize(&k1, &k2, "bob", &txids[1])],
+    )]);
+    h.index().await;
+
+    assert_eq!(
+        h.names().await,
+        vec![owned("alice", &k2), owned("bob", &k1)]
+    );
+    assert_eq!(
+        h.transfers().await,
+        vec![
+            ("alice".to_owned(), "completed".to_owned()),
+            ("bob".to_owned(), "expired".to_owned())
+        ]
+    );
+    h.close().await;
+}
+
+#[tokio::test]
+async fn test_reorg() {
+    let h = Harness::new(|_| {}).await;
+    let (k1, k2) = (keys(1), keys(2));
+    h.chain.mine(vec![create(&k1, "alice")]);
+    h.chain.mine(vec![create(&k1, "bob")]);
+    h.index().await;
+    assert_eq!(
+        h.names().await,
+        vec![owned("alice", &k1), owned("bob", &k1)]
+    );
+
+    // The fork replaces both blocks, and is longer
+    h.chain.reorg(1);
+    h.chain.mine(vec![create(&k2, "alice")]);
+    h.chain.mine(vec![]);
+    h.chain.mine(vec![create(&k2, "carol")]);
+    h.index().await;
+
+    assert_eq!(
+        h.names().await,
+        vec![owned("alice", &k2), owned("carol", &k2)]
+    );
+    let heights: Vec<(i64, String)> =
+        sqlx::query_as("SELECT blockheight, blockhash FROM index_height ORDER BY blockheight;")
+            .fetch_all(&h.pool)
+            .await
+            .unwrap();
+    let expected = (1..=3)
+        .map(|height| (height as i64, h.chain.hash(height).to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(heights, expected);
+    h.close().await;
+}
+
+#[tokio::test]
+async fn test_relay_events() {
+    let index_keys = keys(9);
+    let h = Harness::new(|file| {
+        file.nostr.publish = Some(true);
+        file.nostr.secret = Some(index_keys.secret_key().unwrap().into());
+    })
+    .await;
+    let k1 = keys(1);
+    let mut records = Records::new();
+    records.insert("IP4", "127.0.0.1").unwrap();
+    let event = RecordsBuilder::new(&k1.public_key(), "alice", &records)
+        .signed_event(&k1)
+        .unwrap();
+    h.relays.events.lock().unwrap().push(event);
+
+    h.chain.mine(vec![create(&k1, "alice")]);
+    h.index().await;
+
+    let (records,): (String,) =
+        sqlx::query_as("SELECT records FROM valid_names_records_vw WHERE name = 'alice';")
+            .fetch_one(&h.pool)
+            .await
+            .unwrap();
+    assert_eq!(records, r#"{"IP4":"127.0.0.1"}"#);
+
+    let sent = h.relays.sent.lock().unwrap().clone();
+    assert_eq!(sent.len(), 1);
+    let (relay, event) = &sent[0];
+    assert_eq!(relay, RELAY);
+    assert_eq!(event.kind, NameKind::RelayIndex.into());
+    assert_eq!(event.pubkey, index_keys.public_key());
+    assert!(event.tags.contains(&Tag::Identifier("alice".to_owned())));
+
+    // Nothing new to deliver
+    h.index().await;
+    assert_eq!(h.relays.sent.lock().unwrap().len(), 1);
+    h.close().await;
+}
+
+#[tokio::test]
+async fn test_owner_event_with_p_tag() {
+    let h = Harness::new(|_| {}).await;
+    let (k1, k2) = (keys(1), keys(2));
+    let mut records = Records::new();
+    records.insert("IP4", "127.0.0.1").unwrap();
+    // A mention of someone else doesn't make the owner's own event a delegated one.
+    let unsigned = RecordsBuilder::new(&k1.public_key(), "alice", &records).unsigned_event();
+    let mut tags = unsigned.tags;
+    tags.push(Tag::PubKey(k2.public_key(), None));
+    let event = EventBuilder::new(unsigned.kind, unsigned.content, &tags)
+        .to_event(&k1)
+        .unwrap();
+    h.relays.events.lock().unwrap().push(event);
+
+    h.chain.mine(vec![create(&k1, "alice")]);
+    h.index().await;
+
+    let (pubkey, records): (String, String) =
+        sqlx::query_as("SELECT pubkey, records FROM valid_names_records_vw WHERE name = 'alice';")
+            .fetch_one(&h.pool)
+            .await
+            .unwrap();
+    assert_eq!(pubkey, hex::encode(k1.public_key().serialize()));
+    assert_eq!(records, r#"{"IP4":"127.0.0.1"}"#);
+    h.close().await;
+}
+
+#[tokio::test]
+async fn test_relay_queries() {
+    let index_keys = keys(9);
+    let h = Harness::new(|file| {
+        file.nostr.publish = Some(true);
+        file.nostr.secret = Some(index_keys.secret_key().unwrap().into());
+    })
+    .await;
+    let (k1, k2) = (keys(1), keys(2));
+    let mut records = Records::new();
+    records.insert("IP4", "127.0.0.1").unwrap();
+    for (keys, name) in [(&k1, "alice"), (&k2, "bob")] {
+        let event = RecordsBuilder::new(&keys.public_key(), name, &records)
+            .signed_event(keys)
+            .unwrap();
+        h.relays.events.lock().unwrap().push(event);
+    }
+    h.chain.mine(vec![create(&k1, "alice"), create(&k2, "bob")]);
+    h.index().await;
+
+    let query = |json: &str, limit: usize| {
+        let filter = Filter::from_json(json).unwrap();
+        let pool = h.pool.clone();
+        async move {
+            let events = db::relay::events(&pool, &filter, limit).await.unwrap();
+            let count = db::relay::count(&pool, std::slice::from_ref(&filter))
+                .await
+                .unwrap();
+            (events.len(), count)
+        }
+    };
+    let author = hex::encode(k1.public_key().serialize());
+    assert_eq!(query("{}", 10).await, (4, 4));
+    assert_eq!(query("{}", 1).await, (1, 4));
+    assert_eq!(query(r#"{"kinds":[38300]}"#, 10).await, (2, 2));
+    assert_eq!(query(r#"{"kinds":[38302]}"#, 10).await, (0, 0));
+    assert_eq!(
+        query(
+            &format!(r#"{{"kinds":[38300],"authors":["{}"]}}"#, &author[..8]),
+            10
+        )
+        .await,
+        (1, 1)
+    );
+    assert_eq!(
+        query(r##"{"kinds":[38301],"#d":["alice"]}"##, 10).await,
+        (1, 1)
+    );
+    assert_eq!(query(r#"{"until":0}"#, 10).await, (0, 0));
+
+    // Events matching several filters are counted once.
+    let filters = [
+        Filter::from_json("{}").unwrap(),
+        Filter::from_json(r#"{"kinds":[38300]}"#).unwrap(),
+        Filter::from_json(r#"{"kinds":[38302]}"#).unwrap(),
+    ];
+    assert_eq!(db::relay::count(&h.pool, &filters).await.unwrap(), 4);
+    h.close().await;
+}
+
+/// `(txid, outcome)` pairs for `transfers_of`.
+fn outcomes(outcomes: &[(&Txid, &str)]) -> Vec<(String, String)> {
+    outcomes
+        .iter()
+        .map(|(txid, outcome)| (txid.to_string(), (*outcome).to_owned()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_signature_in_transfer_tx() {
+    let h = Harness::new(|_| {}).await;
+    let (k1, k2) = (keys(1), keys(2));
+    h.chain.mine(vec![create(&k1, "alice")]);
+    let earlier = h.chain.mine(vec![transfer(&k2, "alice")]);
+    // A v1 signature would match either transfer, but it is in the same transaction as the second one.
+    let bound = h.chain.mine_txs(vec![(
+        vec![],
+        vec![transfer(&k2, "alice"), sign_v1(&k1, &k2, "alice")],
+    )]);
+    h.index().await;
+
+    assert_eq!(h.names().await, vec![owned("alice", &k2)]);
+    assert_eq!(
+        h.transfers_of("alice").await,
+        outcomes(&[(&earlier[0], "pending"), (&bound[0], "completed")])
+    );
+    h.close().await;
+}
+
+#[tokio::test]
+async fn test_signature_spending_transfer_tx() {
+    let h = Harness::new(|_| {}).await;
+    let (k1, k2) = (keys(1), keys(2));
+    h.chain.mine(vec![create(&k1, "alice")]);
+    let txids = h
+        .chain
+        .mine(vec![transfer(&k2, "alice"), transfer(&k2, "alice")]);
+    h.chain
+        .mine_txs(vec![(vec![txids[1]], vec![sign_v1(&k1, &k2, "alice")])]);
+    h.index().await;
+
+    assert_eq!(h.names().await, vec![owned("alice", &k2)]);
+    assert_eq!(
+        h.transfers_of("alice").await,
+        outcomes(&[(&txids[0], "pending"), (&txids[1], "completed")])
+    );
+    h.close().await;
+}
+
+#[tokio::test]
+async fn test_unbound_signature() {
+    let h = Harness::new(|_| {}).await;
+    let (k1, k2) = (keys(1), keys(2));
+    h.chain.mine(vec![create(&k1, "alice")]);
+    let first = h.chain.mine(vec![transfer(&k2, "alice")]);
+    // A signature in an unrelated transaction is never matched to a transfer.
+    h.chain.mine(vec![sign_v1(&k1, &k2, "alice")]);
+    // Nor is one which comes before the transfer in its own transaction.
+    let second = h.chain.mine_txs(vec![(
+        vec![],
+        vec![sign_v1(&k1, &k2, "alice"), transfer(&k2, "alice")],
+    )]);
+    h.index().await;
+
+    assert_eq!(h.names().await, vec![owned("alice", &k1)]);
+    assert_eq!(
+        h.transfers_of("alice").await,
+        outcomes(&[(&first[0], "pending"), (&second[0], "pending")])
+    );
+    h.close().await;
+}
+
+#[tokio::test]
+async fn test_transfer_expiry_override() {
+    let h = Harness::new(|file| file.server.transfer_expiry_blocks = Some(5)).await;
+    let (k1, k2) = (keys(1), keys(2));
+    h.chain.mine(vec![create(&k1, "alice")]);
+    let txids = h.chain.mine(vec![transfer(&k2, "alice")]);
+    h.chain.mine_to(7);
+    h.index().await;
+    assert_eq!(
+        h.transfers_of("alice").await,
+        outcomes(&[(&txids[0], "pending")])
+    );
+
+    h.chain.mine(vec![]);
+    h.index().await;
+    assert_eq!(
+        h.transfers_of("alice").await,
+        outcomes(&[(&txids[0], "expired")])
+    );
+    let history = db::history::events(&h.pool, "alice").await.unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].event, "transfer_expired");
+    assert_eq!(history[0].txid, Some(txids[0].to_string()));
+    assert_eq!(history[0].blockheight, 8);
+
+    // The block the transfer expired at is replaced by one with its signature.
+    h.chain.reorg(8);
+    h.chain.mine_txs(vec![(
+        vec![txids[0]],
+        vec![authorize(&k1, &k2, "alice", &txids[0])],
+    )]);
+    h.index().await;
+    assert_eq!(h.names().await, vec![owned("alice", &k2)]);
+    assert_eq!(
+        h.transfers_of("alice").await,
+        outcomes(&[(&txids[0], "completed")])
+    );
+    assert!(db::history::events(&h.pool, "alice")
+        .await
+        .unwrap()
+        .is_empty());
+    h.close().await;
+}
+
+#[tokio::test]
+async fn test_signature_before_name_expiry() {
+    let h = Harness::new(|file| {
+        file.server.expiry_activation_height = Some(1);
+        file.server.expiry_blocks = Some(10);
+    })
+    .await;
+    let (k1, k2) = (keys(1), keys(2));
+    h.chain.mine(vec![create(&k1, "alice")]);
+    h.index().await;
+    let txids = h.chain.mine(vec![transfer(&k2, "alice")]);
+    h.chain.mine_txs(vec![(
+        vec![txids[0]],
+        vec![authorize(&k1, &k2, "alice", &txids[0])],
+    )]);
+
+    // The name has lapsed by the time the indexer catches up, but it was live when the transfer was signed.
+    h.chain.mine_to(20);
+    h.index().await;
+    assert_eq!(
+        h.transfers_of("alice").await,
+        outcomes(&[(&txids[0], "completed")])
+    );
+    h.close().await;
+}
+
+#[tokio::test]
+async fn test_release_without_expiry() {
+    let h = Harness::new(|_| {}).await;
+    let (k1, k2, k3) = (keys(1), keys(2), keys(3));
+    let claim = h.chain.mine(vec![create(&k1, "alice")]);
+    h.chain.mine(vec![create(&k2, "alice")]);
+    h.index().await;
+    assert_eq!(h.names().await, vec![owned("alice", &k1)]);
+
+    // The claim made while the name was held stays invalid after the release, and a claim after it takes the name.
+    h.chain.mine(vec![release(&k1, "alice", &claim[0])]);
+    h.chain.mine(vec![create(&k3, "alice")]);
+    h.index().await;
+    assert_eq!(h.names().await, vec![owned("alice", &k3)]);
+    h.close().await;
+}
+
+#[tokio::test]
+async fn test_confusable_names() {
+    let h = Harness::new(|_| {}).await;
+    let (k1, k2) = (keys(1), keys(2));
+    let claims = h.chain.mine(vec![
+        create(&k1, "apple"),
+        create(&k2, "app1e"),
+        create(&k1, "banana"),
+    ]);
+    h.index().await;
+    let confusable = |name: &str| {
+        let name: Name = name.parse().unwrap();
+        let pool = h.pool.clone();
+        async move { db::name::confusable_names(&pool, &name).await.unwrap() }
+    };
+    assert_eq!(confusable("apple").await, ["app1e"]);
+    assert_eq!(confusable("a-pple").await, ["app1e", "apple"]);
+    assert!(confusable("banana").await.is_empty());
+
+    // Skeletons missing from names indexed before they were stored are filled in.
+    sqlx::query("UPDATE blockchain_index SET skeleton = NULL;")
+        .execute(&h.pool)
+        .await
+        .unwrap();
+    db::index::fill_skeletons(&h.pool).await.unwrap();
+    assert_eq!(confusable("apple").await, ["app1e"]);
+
+    // Released names are no longer confusable.
+    h.chain.mine(vec![release(&k2, "app1e", &claims[1])]);
+    h.index().await;
+    assert!(confusable("apple").await.is_empty());
+    h.close().await;
+}
+
+#[tokio::test]
+async fn test_outbox_delivery_failure() {
+    let h = Harness::new(|file| file.nostr.send_interval_ms = Some(1)).await;
+    let relays = ["wss://a.example".to_owned(), "wss://b.example".to_owned()];
+    // An entry which can't be delivered is queued for the first relay ahead of one which can.
+    let broken = sqlx::query(
+        "INSERT INTO relay_outbox (kind, event_id, raw_event, created_at) VALUES ('index', '', 'not json', unixepoch());",
+    )
+    .execute(&h.pool)
+    .await
+    .unwrap()
+    .last_insert_rowid();
+    sqlx::query(
+        "INSERT INTO relay_outbox_delivery (outbox_id, relay, status, attempts, next_attempt_at, updated_at)
+        VALUES (?, ?, 'pending', 0, unixepoch(), unixepoch());",
+    )
+    .bind(broken)
+    .bind(&relays[0])
+    .execute(&h.pool)
+    .await
+    .unwrap();
+    let event = EventBuilder::new_text_note("hello", &[])
+        .to_event(&keys(1))
+        .unwrap();
+    db::outbox::enqueue(&h.pool, "index", None, &event, &relays)
+        .await
+        .unwrap();
+
+    outbox::deliver(&h.config, &h.pool, &h.relays)
+        .await
+        .unwrap();
+
+    let mut sent = h
+        .relays
+        .sent
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(relay, e)| (relay.clone(), e.id))
+        .collect::<Vec<_>>();
+    sent.sort();
+    assert_eq!(
+        sent,
+        [(relays[0].clone(), event.id), (relays[1].clone(), event.id)]
+    );
+    let (status, error): (String, Option<String>) =
+        sqlx::query_as("SELECT status, last_error FROM relay_outbox_delivery WHERE outbox_id = ?;")
+            .bind(broken)
+            .fetch_one(&h.pool)
+            .await
+            .unwrap();
+    assert_eq!(status, "pending");
+    assert!(error.is_some());
+    h.close().await;
+}
@@ -1,11 +1,17 @@
+mod audit;
 mod index;
+pub mod outbox;
+pub mod relays;
 mod server;
 pub mod util;
+mod vectors;
 
+pub use audit::*;
 pub use index::*;
 use nostr_sdk::Event;
 pub use server::*;
 use sqlx::SqlitePool;
+pub use vectors::*;
 
 use crate::{
     config::{Config, ConfigFile},
@@ -44,6 +50,10 @@ pub(crate) async fn rescan(
         .bind(blockheight)
         .execute(pool)
         .await?;
+    sqlx::query("DELETE FROM raw_blockchain_inputs WHERE blockheight >= ?;")
+        .bind(blockheight)
+        .execute(pool)
+        .await?;
 
     Ok(())
 }
@@ -54,8 +64,8 @@ pub(crate) fn version() {
 }
 
 pub(crate) async fn rebroadcast(config: &Config, pool: &SqlitePool) -> anyhow::Result<()> {
-    let events = sqlx::query_as::<_, (String,)>(
-        "select ne.raw_event from valid_names_vw vn join name_events ne on vn.nsid = ne.nsid;",
+    let events = sqlx::query_as::<_, (String, String)>(
+        "select ne.name, ne.raw_event from valid_names_vw vn join name_events ne on vn.nsid = ne.nsid;",
     )
     .fetch_all(pool)
     .await?;
@@ -64,16 +74,23 @@ pub(crate) async fn rebroadcast(config: &Config, pool: &SqlitePool) -> anyhow::R
         events.len(),
         config.relays().len()
     );
-    let (_, client) = config.nostr_random_client().await?;
-    for (event,) in events {
+    let relays = config.relays();
+    for (name, event) in events {
         let event = Event::from_json(event)?;
-        client.send_event(event).await?;
+        db::outbox::enqueue(pool, "record", Some(&name), &event, &relays).await?;
     }
-
-    Ok(())
+    deliver(config, pool).await
 }
 
 pub(crate) async fn publish(config: &Config, pool: &SqlitePool) -> anyhow::Result<()> {
     println!("Publishing full relay index");
-    index::events::relay_index::publish(config, pool, false).await
+    index::events::relay_index::publish(config, pool, false).await?;
+    deliver(config, pool).await
+}
+
+async fn deliver(config: &Config, pool: &SqlitePool) -> anyhow::Result<()> {
+    let relays = relays::NostrRelays::new(config);
+    let delivered = outbox::deliver(config, pool, &relays).await;
+    relays.disconnect().await;
+    delivered
 }
@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use futures::future::join_all;
+use itertools::Itertools;
+use nostr_sdk::Event;
+use sqlx::SqlitePool;
+
+use crate::{
+    config::Config,
+    db::{self, outbox::Delivery},
+    subcommands::relays::Relays,
+};
+
+const BACKOFF_BASE: i64 = 30;
+const BACKOFF_MAX: i64 = 60 * 60 * 6;
+
+/// Seconds to wait before retrying a delivery which has failed `attempts` times.
+fn backoff(attempts: i64) -> i64 {
+    let exp = (attempts - 1).clamp(0, 20) as u32;
+    BACKOFF_BASE.saturating_mul(2_i64.pow(exp)).min(BACKOFF_MAX)
+}
+
+/// Delivers due outbox events. Relays are served concurrently, but events to the same relay are rate limited. A relay
+/// whose deliveries fail doesn't stop the others, its failure is recorded on its pending deliveries.
+pub async fn deliver(
+    config: &Config,
+    pool: &SqlitePool,
+    relays: &dyn Relays,
+) -> anyhow::Result<()> {
+    let due = db::outbox::due(pool).await?;
+    if due.is_empty() {
+        return Ok(());
+    }
+
+    tracing::info!("Delivering {} outbox events.", due.len());
+    let by_relay = due.into_iter().into_group_map_by(|d| d.relay.clone());
+    let results = join_all(by_relay.into_iter().map(|(relay, deliveries)| async move {
+        let delivered = deliver_to_relay(config, pool, relays, &relay, deliveries).await;
+        (relay, delivered)
+    }))
+    .await;
+    for (relay, delivered) in results {
+        if let Err(e) = delivered {
+            tracing::error!("Outbox delivery to {relay} failed: {e}");
+            db::outbox::relay_failed(pool, &relay, &e.to_string()).await?;
+        }
+    }
+
+    db::outbox::prune(pool).await?;
+    tracing::info!("Outbox delivery complete.");
+    Ok(())
+}
+
+async fn deliver_to_relay(
+    config: &Config,
+    pool: &SqlitePool,
+    relays: &dyn Relays,
+    relay: &str,
+    deliveries: Vec<Delivery>,
+) -> anyhow::Result<()> {
+    let mut interval = tokio::time::interval(Duration::from_millis(config.send_interval_ms()));
+    for delivery in deliveries {
+        interval.tick().await;
+        let sent = match Event::from_json(&delivery.raw_event) {
+            Ok(event) => relays.send(relay, event).await,
+            Err(e) => Err(e.into()),
+        };
+        match sent {
+            Ok(_) => {
+                tracing::debug!("Delivered outbox event {} to {relay}", delivery.outbox_id);
+                db::outbox::mark_sent(pool, delivery.outbox_id, relay).await?;
+            }
+            Err(e) => {
+                let attempts = delivery.attempts + 1;
+                let dead = attempts >= config.max_send_attempts();
+                if dead {
+                    tracing::error!(
+                        "Giving up on outbox event {} to {relay} after {attempts} attempts: {e}",
+                        delivery.outbox_id
+                    );
+                } else {
+                    tracing::warn!(
+                        "Unable to deliver outbox event {} to {relay}: {e}",
+                        delivery.outbox_id
+                    );
+                }
+                db::outbox::mark_failed(
+                    pool,
+                    delivery.outbox_id,
+                    relay,
+                    &e.to_string(),
+                    backoff(attempts),
+                    dead,
+                )
+                .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(1), 30);
+        assert_eq!(backoff(2), 60);
+        assert_eq!(backoff(5), 480);
+        assert_eq!(backoff(100), BACKOFF_MAX);
+    }
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use futures::{future::BoxFuture, FutureExt};
+use nostr_sdk::{Client, Event, Filter};
+use tokio::sync::OnceCell;
+
+use crate::config::Config;
+
+/// The Nostr relays the indexer reads events from and delivers its outbox to. [`NostrRelays`] talks to the configured
+/// relays, and the indexer tests use an in-memory stand-in.
+pub trait Relays: Send + Sync {
+    /// Events matching `filter` on any relay.
+    fn events(&self, filter: Filter) -> BoxFuture<'_, anyhow::Result<Vec<Event>>>;
+
+    /// Sends `event` to the relay at `url`.
+    fn send<'a>(&'a self, url: &'a str, event: Event) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+/// The configured relays, through a client which connects on first use.
+#[allow(clippy::module_name_repetitions)]
+pub struct NostrRelays<'a> {
+    config: &'a Config,
+    client: OnceCell<Client>,
+}
+
+impl<'a> NostrRelays<'a> {
+    pub fn new(config: &'a Config) -> NostrRelays<'a> {
+        NostrRelays {
+            config,
+            client: OnceCell::new(),
+        }
+    }
+
+    async fn client(&self) -> anyhow::Result<&Client> {
+        self.client
+            .get_or_try_init(|| async {
+                let (_keys, client) = self.config.nostr_random_client().await?;
+                anyhow::Ok(client)
+            })
+            .await
+    }
+
+    pub async fn disconnect(&self) {
+        if let Some(client) = self.client.get() {
+            client.disconnect().await.ok();
+        }
+    }
+}
+
+impl Relays for NostrRelays<'_> {
+    fn events(&self, filter: Filter) -> BoxFuture<'_, anyhow::Result<Vec<Event>>> {
+        async move {
+            let events = self
+                .client()
+                .await?
+                .get_events_of(vec![filter], Some(Duration::from_secs(10)))
+                .await?;
+            Ok(events)
+        }
+        .boxed()
+    }
+
+    fn send<'a>(&'a self, url: &'a str, event: Event) -> BoxFuture<'a, anyhow::Result<()>> {
+        async move {
+            self.client().await?.send_event_to(url, event).await?;
+            Ok(())
+        }
+        .boxed()
+    }
+}
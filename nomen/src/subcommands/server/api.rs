@@ -5,19 +5,29 @@ use axum::{
     extract::{Query, State},
     Json,
 };
-use nomen_core::{CreateBuilder, Name, TransferBuilder};
+use bitcoin::Txid;
+use nomen_core::{
+    CommitBuilder, CreateBuilder, Hash160, Name, NameEntity, OpReturn, RecordDelegateBuilder,
+    Records, ReleaseBuilder, RenewBuilder, SignatureV2, TransferAuthorization, TransferBuilder,
+};
+
+use secp256k1::XOnlyPublicKey;
 
-use crate::db;
+use crate::{
+    db,
+    subcommands::util::{children_event, confusable_warnings},
+    util::{canonical_name, display_name},
+};
 
 use self::models::{OpReturnResponse, TransferEventResponse};
 
-use super::AppState;
+use super::{proof, AppState};
 
 mod models {
-    use std::collections::HashMap;
-
     use askama_axum::IntoResponse;
     use axum::{http::StatusCode, Json};
+    use bitcoin::Txid;
+    use nomen_core::{NameProof, Records};
     use nostr_sdk::UnsignedEvent;
     use secp256k1::{schnorr::Signature, XOnlyPublicKey};
     use serde::{Deserialize, Serialize};
@@ -56,16 +66,50 @@ mod models {
     #[derive(Deserialize)]
     pub struct NameQuery {
         pub name: String,
+        pub proof: Option<bool>,
     }
 
     #[derive(Serialize)]
     pub struct NameResult {
+        pub pubkey: String,
         pub blockhash: String,
         pub txid: String,
         pub fingerprint: String,
         pub nsid: String,
         pub protocol: i64,
-        pub records: HashMap<String, String>,
+        pub records: Records,
+        pub display_name: String,
+        pub expires_at: Option<i64>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub warnings: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub proof: Option<NameProof>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub parent: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct ChildrenEventQuery {
+        pub name: String,
+        pub pubkey: XOnlyPublicKey,
+        pub children: String,
+    }
+
+    #[derive(Serialize)]
+    pub struct ChildrenEventResponse {
+        pub event: UnsignedEvent,
+    }
+
+    #[derive(Deserialize)]
+    pub struct DelegateEventQuery {
+        pub name: String,
+        pub pubkey: XOnlyPublicKey,
+        pub delegate: Option<XOnlyPublicKey>,
+    }
+
+    #[derive(Serialize)]
+    pub struct DelegateEventResponse {
+        pub event: UnsignedEvent,
     }
 
     #[derive(Deserialize)]
@@ -74,11 +118,36 @@ mod models {
         pub pubkey: XOnlyPublicKey,
     }
 
+    #[derive(Serialize)]
+    pub struct CreateDataResponse {
+        pub op_return: Vec<String>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub warnings: Vec<String>,
+    }
+
     #[derive(Serialize, Default)]
     pub struct OpReturnResponse {
         pub op_return: Vec<String>,
     }
 
+    #[derive(Deserialize)]
+    pub struct DecodeQuery {
+        pub data: String,
+    }
+
+    #[derive(Deserialize)]
+    pub struct CommitQuery {
+        pub name: String,
+        pub pubkey: XOnlyPublicKey,
+        pub salt: Option<String>,
+    }
+
+    #[derive(Serialize)]
+    pub struct CommitResponse {
+        pub salt: String,
+        pub op_return: Vec<String>,
+    }
+
     #[derive(Deserialize)]
     pub struct TransferEventQuery {
         pub name: String,
@@ -98,6 +167,63 @@ mod models {
         pub signature: Signature,
     }
 
+    #[derive(Deserialize)]
+    pub struct TransferV2Query {
+        pub name: String,
+        pub new_owner: XOnlyPublicKey,
+        pub old_owner: XOnlyPublicKey,
+        pub txid: Txid,
+        pub signature: Option<Signature>,
+    }
+
+    #[derive(Serialize)]
+    pub struct TransferMessageResponse {
+        pub message: String,
+    }
+
+    #[derive(Deserialize)]
+    pub struct RenewQuery {
+        pub name: String,
+        pub signature: Option<Signature>,
+    }
+
+    #[derive(Serialize)]
+    pub struct RenewMessageResponse {
+        pub name: String,
+        pub pubkey: String,
+        pub renewed_from: i64,
+        pub expires_at: Option<i64>,
+        pub message: String,
+    }
+
+    #[derive(Deserialize)]
+    pub struct ReleaseQuery {
+        pub name: String,
+        pub signature: Option<Signature>,
+    }
+
+    #[derive(Serialize)]
+    pub struct ReleaseMessageResponse {
+        pub name: String,
+        pub pubkey: String,
+        pub claim_txid: String,
+        pub message: String,
+    }
+
+    #[derive(Serialize)]
+    pub struct ReleaseResult {
+        pub pubkey: String,
+        pub txid: String,
+        pub blockheight: i64,
+        pub claim_txid: Option<String>,
+    }
+
+    #[derive(Serialize)]
+    pub struct ReleaseHistoryResponse {
+        pub name: String,
+        pub releases: Vec<ReleaseResult>,
+    }
+
     #[derive(Serialize)]
     pub struct NameResponse {
         pub name: String,
@@ -108,6 +234,102 @@ mod models {
     pub struct NamesResponse {
         pub names: Vec<NameResponse>,
     }
+
+    #[derive(Serialize)]
+    pub struct TransferStatusResult {
+        pub new_owner: String,
+        pub txid: String,
+        pub blockheight: i64,
+        pub outcome: String,
+        pub expires_at: i64,
+        pub blocks_remaining: Option<i64>,
+        pub signature_seen: bool,
+        pub signature_txid: Option<String>,
+        pub signature_blockheight: Option<i64>,
+    }
+
+    #[derive(Serialize)]
+    pub struct AuditMismatch {
+        pub peer: String,
+        pub name: String,
+        pub kind: String,
+        pub local: Option<String>,
+        pub remote: Option<String>,
+    }
+
+    #[derive(Serialize)]
+    pub struct AuditPeer {
+        pub peer: String,
+        pub status: String,
+        pub checked_at: i64,
+    }
+
+    #[derive(Serialize)]
+    pub struct AuditResponse {
+        pub last_audit_time: Option<i64>,
+        pub peers: Vec<AuditPeer>,
+        pub mismatches: Vec<AuditMismatch>,
+    }
+
+    #[derive(Serialize)]
+    pub struct DeadLetter {
+        pub kind: String,
+        pub name: Option<String>,
+        pub event_id: String,
+        pub relay: String,
+        pub attempts: i64,
+        pub last_error: Option<String>,
+        pub updated_at: i64,
+    }
+
+    #[derive(Serialize)]
+    pub struct DeadLettersResponse {
+        pub pending: i64,
+        pub dead: Vec<DeadLetter>,
+    }
+
+    #[derive(Serialize)]
+    pub struct TransferStatusResponse {
+        pub name: String,
+        pub index_height: i64,
+        pub transfers: Vec<TransferStatusResult>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct ExpiringQuery {
+        pub within: Option<i64>,
+    }
+
+    #[derive(Serialize)]
+    pub struct ExpiringTransfer {
+        pub name: String,
+        pub new_owner: String,
+        pub txid: String,
+        pub blockheight: i64,
+        pub expires_at: i64,
+        pub blocks_remaining: i64,
+    }
+
+    #[derive(Serialize)]
+    pub struct ExpiringTransfersResponse {
+        pub index_height: i64,
+        pub expiry_blocks: usize,
+        pub transfers: Vec<ExpiringTransfer>,
+    }
+
+    #[derive(Serialize)]
+    pub struct HistoryEvent {
+        pub event: String,
+        pub txid: Option<String>,
+        pub blockheight: i64,
+        pub created_at: i64,
+    }
+
+    #[derive(Serialize)]
+    pub struct NameHistoryResponse {
+        pub name: String,
+        pub events: Vec<HistoryEvent>,
+    }
 }
 
 pub async fn names(
@@ -125,52 +347,149 @@ pub async fn names(
 }
 
 pub async fn name(
-    Query(name): Query<models::NameQuery>,
+    Query(query): Query<models::NameQuery>,
     State(state): State<AppState>,
 ) -> Result<Json<models::NameResult>, models::JsonError> {
     let conn = state.pool;
-    let name = db::name::records(&conn, name.name).await?;
-
-    name.and_then(|nr| {
-        Some(models::NameResult {
-            blockhash: nr.blockhash,
-            txid: nr.txid,
-            fingerprint: nr.fingerprint,
-            nsid: nr.nsid,
-            protocol: nr.protocol,
-            records: serde_json::from_str(&nr.records).ok()?,
-        })
-    })
-    .map(Json)
-    .ok_or_else(|| models::JsonError::message("Name not found"))
+    let name = match NameEntity::from_str(&query.name) {
+        Ok(entity) => db::name::by_nsid(&conn, &entity.nsid)
+            .await?
+            .unwrap_or(query.name.clone()),
+        Err(_) => canonical_name(&query.name),
+    };
+    let Some(nr) = db::name::records(&conn, name).await? else {
+        return child_name(&conn, &query).await;
+    };
+    let proof = if query.proof.unwrap_or_default() {
+        Some(proof::for_name(&state.config, &conn, &nr).await?)
+    } else {
+        None
+    };
+
+    let records: Records = serde_json::from_str(&nr.records)
+        .map_err(|_| models::JsonError::message("Name not found"))?;
+
+    Ok(Json(models::NameResult {
+        warnings: records.validate().iter().map(ToString::to_string).collect(),
+        records,
+        display_name: display_name(&nr.name),
+        pubkey: nr.pubkey,
+        blockhash: nr.blockhash,
+        txid: nr.txid,
+        fingerprint: nr.fingerprint,
+        nsid: nr.nsid,
+        protocol: nr.protocol,
+        expires_at: nr.expires_at,
+        proof,
+        parent: None,
+    }))
+}
+
+/// Resolves a child name. The on-chain fields are those of the top-level name the child is delegated from.
+async fn child_name(
+    conn: &sqlx::SqlitePool,
+    query: &models::NameQuery,
+) -> Result<Json<models::NameResult>, models::JsonError> {
+    let child = db::children::records(conn, &query.name)
+        .await?
+        .ok_or_else(|| models::JsonError::message("Name not found"))?;
+    if query.proof.unwrap_or_default() {
+        return Err(models::JsonError::message(
+            "Proofs are not available for child names",
+        ));
+    }
+    let root = db::name::records(conn, child.root)
+        .await?
+        .ok_or_else(|| models::JsonError::message("Name not found"))?;
+    let fingerprint = Hash160::default()
+        .chain_update(child.name.as_bytes())
+        .fingerprint();
+
+    let records: Records = serde_json::from_str(&child.records)
+        .map_err(|_| models::JsonError::message("Name not found"))?;
+
+    Ok(Json(models::NameResult {
+        warnings: records.validate().iter().map(ToString::to_string).collect(),
+        records,
+        display_name: display_name(&child.name),
+        pubkey: child.pubkey,
+        blockhash: root.blockhash,
+        txid: root.txid,
+        fingerprint: hex::encode(fingerprint),
+        nsid: child.nsid,
+        protocol: root.protocol,
+        expires_at: root.expires_at,
+        proof: None,
+        parent: Some(child.parent),
+    }))
 }
 
-#[allow(clippy::unused_async)]
 pub async fn op_return_v1(
     Query(query): Query<models::OpReturnQuery>,
-) -> Result<Json<models::OpReturnResponse>, models::JsonError> {
+    State(state): State<AppState>,
+) -> Result<Json<models::CreateDataResponse>, models::JsonError> {
     let name = Name::from_str(&query.name).map_err(|_| anyhow!("Invalid name"))?;
     let bytes = CreateBuilder::new(&query.pubkey, name.as_ref()).v1_op_return();
-    let orr = models::OpReturnResponse {
+    let orr = models::CreateDataResponse {
         op_return: vec![hex::encode(bytes)],
+        warnings: confusable_warnings(&state.pool, &name).await?,
     };
 
     Ok(Json(orr))
 }
 
-#[allow(clippy::unused_async)]
 pub async fn op_return_v0(
     Query(query): Query<models::OpReturnQuery>,
-) -> Result<Json<models::OpReturnResponse>, models::JsonError> {
+    State(state): State<AppState>,
+) -> Result<Json<models::CreateDataResponse>, models::JsonError> {
     let name = Name::from_str(&query.name).map_err(|_| anyhow!("Invalid name"))?;
     let bytes = CreateBuilder::new(&query.pubkey, name.as_ref()).v0_op_return();
-    let orr = models::OpReturnResponse {
+    let orr = models::CreateDataResponse {
         op_return: vec![hex::encode(bytes)],
+        warnings: confusable_warnings(&state.pool, &name).await?,
     };
 
     Ok(Json(orr))
 }
 
+#[allow(clippy::unused_async)]
+pub async fn decode_op_return(
+    Query(query): Query<models::DecodeQuery>,
+) -> Result<Json<OpReturn>, models::JsonError> {
+    let data =
+        hex::decode(query.data.trim()).map_err(|_| models::JsonError::message("Invalid hex"))?;
+    let op_return = OpReturn::try_from(data.as_slice())
+        .map_err(|_| models::JsonError::message("Not a Nomen OP_RETURN"))?;
+    Ok(Json(op_return))
+}
+
+#[allow(clippy::unused_async)]
+pub async fn commit_data(
+    Query(query): Query<models::CommitQuery>,
+) -> Result<Json<models::CommitResponse>, models::JsonError> {
+    let name = Name::from_str(&query.name).map_err(|_| anyhow!("Invalid name"))?;
+    let cb = match &query.salt {
+        Some(salt) => {
+            let salt = hex::decode(salt)
+                .ok()
+                .and_then(|s| s.try_into().ok())
+                .ok_or_else(|| models::JsonError::message("Salt must be 8 hex-encoded bytes"))?;
+            CommitBuilder::with_salt(&query.pubkey, name.as_ref(), salt)
+        }
+        None => CommitBuilder::new(&query.pubkey, name.as_ref()),
+    };
+    let commit = cb
+        .commit_op_return()
+        .map_err(|e| models::JsonError::message(&e.to_string()))?;
+    let reveal = cb
+        .reveal_op_return()
+        .map_err(|e| models::JsonError::message(&e.to_string()))?;
+    Ok(Json(models::CommitResponse {
+        salt: hex::encode(cb.salt),
+        op_return: vec![hex::encode(commit), hex::encode(reveal)],
+    }))
+}
+
 #[allow(clippy::unused_async)]
 pub async fn get_transfer_event(
     Query(query): Query<models::TransferEventQuery>,
@@ -184,6 +503,43 @@ pub async fn get_transfer_event(
     }))
 }
 
+#[allow(clippy::unused_async)]
+pub async fn get_children_event(
+    Query(query): Query<models::ChildrenEventQuery>,
+) -> Result<Json<models::ChildrenEventResponse>, models::JsonError> {
+    Name::parse_hierarchical(&query.name).map_err(|_| anyhow!("Invalid name"))?;
+    let children = query
+        .children
+        .split(',')
+        .filter(|c| !c.is_empty())
+        .map(|c| -> anyhow::Result<_> {
+            let (label, pubkey) = c
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Invalid child {c}"))?;
+            let label =
+                Name::from_str(label).map_err(|_| anyhow!("Invalid child label {label}"))?;
+            Ok((label.to_string(), pubkey.parse()?))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map_err(|e| models::JsonError::message(&e.to_string()))?;
+    let event = children_event(query.pubkey, &children, &query.name)?;
+    Ok(Json(models::ChildrenEventResponse { event }))
+}
+
+#[allow(clippy::unused_async)]
+pub async fn get_delegate_event(
+    Query(query): Query<models::DelegateEventQuery>,
+) -> Result<Json<models::DelegateEventResponse>, models::JsonError> {
+    Name::parse_hierarchical(&query.name).map_err(|_| anyhow!("Invalid name"))?;
+    let builder = match &query.delegate {
+        Some(delegate) => RecordDelegateBuilder::new(&query.pubkey, &query.name, delegate),
+        None => RecordDelegateBuilder::revoke(&query.pubkey, &query.name),
+    };
+    Ok(Json(models::DelegateEventResponse {
+        event: builder.unsigned_event(),
+    }))
+}
+
 #[allow(clippy::unused_async)]
 pub async fn get_transfer(
     Query(query): Query<models::TransferQuery>,
@@ -198,3 +554,286 @@ pub async fn get_transfer(
         op_return: vec![or1, or2],
     }))
 }
+
+#[allow(clippy::unused_async)]
+pub async fn get_transfer_v2_message(
+    Query(query): Query<models::TransferV2Query>,
+) -> Result<Json<models::TransferMessageResponse>, models::JsonError> {
+    let name =
+        Name::from_str(&query.name).map_err(|_| models::JsonError::message("Invalid name"))?;
+    let auth = TransferAuthorization {
+        name: name.as_ref(),
+        new_owner: &query.new_owner,
+        transfer_txid: &query.txid,
+    };
+    let message = auth
+        .message(&query.old_owner)
+        .map_err(anyhow::Error::from)?;
+    Ok(Json(models::TransferMessageResponse {
+        message: hex::encode(message.as_ref()),
+    }))
+}
+
+#[allow(clippy::unused_async)]
+pub async fn get_transfer_v2_signature(
+    Query(query): Query<models::TransferV2Query>,
+) -> Result<Json<models::OpReturnResponse>, models::JsonError> {
+    let signature = query
+        .signature
+        .ok_or_else(|| models::JsonError::message("Missing signature"))?;
+    let name =
+        Name::from_str(&query.name).map_err(|_| models::JsonError::message("Invalid name"))?;
+    TransferAuthorization {
+        name: name.as_ref(),
+        new_owner: &query.new_owner,
+        transfer_txid: &query.txid,
+    }
+    .verify(&signature, &query.old_owner)
+    .map_err(|e| models::JsonError::message(&e.to_string()))?;
+    Ok(Json(OpReturnResponse {
+        op_return: vec![hex::encode(SignatureV2::new(&signature).serialize())],
+    }))
+}
+
+pub async fn get_renew_message(
+    Query(query): Query<models::RenewQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<models::RenewMessageResponse>, models::JsonError> {
+    let name = Name::from_str(query.name.trim())
+        .map_err(|_| models::JsonError::message("Invalid name"))?
+        .to_string();
+    let nr = db::name::records(&state.pool, name)
+        .await?
+        .ok_or_else(|| models::JsonError::message("Name not found"))?;
+    let owner: XOnlyPublicKey = nr.pubkey.parse().map_err(anyhow::Error::from)?;
+    let message = RenewBuilder::new(&nr.name, &owner, nr.renewed_from as u64)
+        .message()
+        .map_err(anyhow::Error::from)?;
+    Ok(Json(models::RenewMessageResponse {
+        message: hex::encode(message.as_ref()),
+        name: nr.name,
+        pubkey: nr.pubkey,
+        renewed_from: nr.renewed_from,
+        expires_at: nr.expires_at,
+    }))
+}
+
+pub async fn get_renew(
+    Query(query): Query<models::RenewQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<models::OpReturnResponse>, models::JsonError> {
+    let signature = query
+        .signature
+        .ok_or_else(|| models::JsonError::message("Missing signature"))?;
+    let name = Name::from_str(query.name.trim())
+        .map_err(|_| models::JsonError::message("Invalid name"))?
+        .to_string();
+    let nr = db::name::records(&state.pool, name)
+        .await?
+        .ok_or_else(|| models::JsonError::message("Name not found"))?;
+    let owner: XOnlyPublicKey = nr.pubkey.parse().map_err(anyhow::Error::from)?;
+    let rb = RenewBuilder::new(&nr.name, &owner, nr.renewed_from as u64);
+    rb.verify(&signature)
+        .map_err(|e| models::JsonError::message(&e.to_string()))?;
+    Ok(Json(OpReturnResponse {
+        op_return: vec![hex::encode(rb.signature_provided_op_return(signature))],
+    }))
+}
+
+pub async fn get_release_message(
+    Query(query): Query<models::ReleaseQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<models::ReleaseMessageResponse>, models::JsonError> {
+    let name = Name::from_str(query.name.trim())
+        .map_err(|_| models::JsonError::message("Invalid name"))?
+        .to_string();
+    let nr = db::name::records(&state.pool, name)
+        .await?
+        .ok_or_else(|| models::JsonError::message("Name not found"))?;
+    let owner: XOnlyPublicKey = nr.pubkey.parse().map_err(anyhow::Error::from)?;
+    let claim_txid: Txid = nr.txid.parse().map_err(anyhow::Error::from)?;
+    let message = ReleaseBuilder::new(&nr.name, &owner, &claim_txid)
+        .message()
+        .map_err(anyhow::Error::from)?;
+    Ok(Json(models::ReleaseMessageResponse {
+        message: hex::encode(message.as_ref()),
+        name: nr.name,
+        pubkey: nr.pubkey,
+        claim_txid: nr.txid,
+    }))
+}
+
+pub async fn get_release(
+    Query(query): Query<models::ReleaseQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<models::OpReturnResponse>, models::JsonError> {
+    let signature = query
+        .signature
+        .ok_or_else(|| models::JsonError::message("Missing signature"))?;
+    let name = Name::from_str(query.name.trim())
+        .map_err(|_| models::JsonError::message("Invalid name"))?
+        .to_string();
+    let nr = db::name::records(&state.pool, name)
+        .await?
+        .ok_or_else(|| models::JsonError::message("Name not found"))?;
+    let owner: XOnlyPublicKey = nr.pubkey.parse().map_err(anyhow::Error::from)?;
+    let claim_txid: Txid = nr.txid.parse().map_err(anyhow::Error::from)?;
+    let rb = ReleaseBuilder::new(&nr.name, &owner, &claim_txid);
+    rb.verify(&signature)
+        .map_err(|e| models::JsonError::message(&e.to_string()))?;
+    Ok(Json(OpReturnResponse {
+        op_return: vec![hex::encode(rb.signature_provided_op_return(signature))],
+    }))
+}
+
+pub async fn get_release_history(
+    Query(query): Query<models::NameQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<models::ReleaseHistoryResponse>, models::JsonError> {
+    let name = Name::from_str(query.name.trim())
+        .map_err(|_| models::JsonError::message("Invalid name"))?
+        .to_string();
+    let releases = db::release::history(&state.pool, &name)
+        .await?
+        .into_iter()
+        .map(|r| models::ReleaseResult {
+            pubkey: r.pubkey,
+            txid: r.txid,
+            blockheight: r.blockheight,
+            claim_txid: r.claim_txid,
+        })
+        .collect();
+    Ok(Json(models::ReleaseHistoryResponse { name, releases }))
+}
+
+pub async fn get_transfer_status(
+    Query(query): Query<models::NameQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<models::TransferStatusResponse>, models::JsonError> {
+    let name = Name::from_str(query.name.trim())
+        .map_err(|_| models::JsonError::message("Invalid name"))?
+        .to_string();
+    let index_height = db::stats::index_height(&state.pool).await?;
+    let expiry_blocks = state.config.transfer_expiry_blocks();
+    let transfers = db::transfer::status(&state.pool, &name, expiry_blocks)
+        .await?
+        .into_iter()
+        .map(|ts| models::TransferStatusResult {
+            expires_at: ts.expires_at,
+            blocks_remaining: ts.blocks_remaining(index_height),
+            signature_seen: ts.signature_seen(),
+            new_owner: ts.pubkey,
+            txid: ts.txid,
+            blockheight: ts.blockheight,
+            outcome: ts.outcome,
+            signature_txid: ts.signature_txid,
+            signature_blockheight: ts.signature_blockheight,
+        })
+        .collect();
+    Ok(Json(models::TransferStatusResponse {
+        name,
+        index_height,
+        transfers,
+    }))
+}
+
+pub async fn get_expiring_transfers(
+    Query(query): Query<models::ExpiringQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<models::ExpiringTransfersResponse>, models::JsonError> {
+    let index_height = db::stats::index_height(&state.pool).await?;
+    let expiry_blocks = state.config.transfer_expiry_blocks();
+    let transfers = db::transfer::expiring(&state.pool, expiry_blocks)
+        .await?
+        .into_iter()
+        .filter_map(|ts| {
+            let blocks_remaining = ts.blocks_remaining(index_height)?;
+            if query.within.is_some_and(|within| blocks_remaining > within) {
+                return None;
+            }
+            Some(models::ExpiringTransfer {
+                name: ts.name,
+                new_owner: ts.pubkey,
+                txid: ts.txid,
+                blockheight: ts.blockheight,
+                expires_at: ts.expires_at,
+                blocks_remaining,
+            })
+        })
+        .collect();
+    Ok(Json(models::ExpiringTransfersResponse {
+        index_height,
+        expiry_blocks,
+        transfers,
+    }))
+}
+
+pub async fn get_name_history(
+    Query(query): Query<models::NameQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<models::NameHistoryResponse>, models::JsonError> {
+    let name = Name::from_str(query.name.trim())
+        .map_err(|_| models::JsonError::message("Invalid name"))?
+        .to_string();
+    let events = db::history::events(&state.pool, &name)
+        .await?
+        .into_iter()
+        .map(|e| models::HistoryEvent {
+            event: e.event,
+            txid: e.txid,
+            blockheight: e.blockheight,
+            created_at: e.created_at,
+        })
+        .collect();
+    Ok(Json(models::NameHistoryResponse { name, events }))
+}
+
+pub async fn audit(
+    State(state): State<AppState>,
+) -> Result<Json<models::AuditResponse>, models::JsonError> {
+    let mismatches = db::audit::fetch_all(&state.pool)
+        .await?
+        .into_iter()
+        .map(|m| models::AuditMismatch {
+            peer: m.peer,
+            name: m.name,
+            kind: m.kind,
+            local: m.local,
+            remote: m.remote,
+        })
+        .collect();
+    let peers = db::audit::peers(&state.pool)
+        .await?
+        .into_iter()
+        .map(|p| models::AuditPeer {
+            peer: p.peer,
+            status: p.status,
+            checked_at: p.checked_at,
+        })
+        .collect();
+    Ok(Json(models::AuditResponse {
+        last_audit_time: db::audit::last_audit_time(&state.pool).await?,
+        peers,
+        mismatches,
+    }))
+}
+
+pub async fn outbox_dead(
+    State(state): State<AppState>,
+) -> Result<Json<models::DeadLettersResponse>, models::JsonError> {
+    let (pending, _) = db::outbox::counts(&state.pool).await?;
+    let dead = db::outbox::dead_letters(&state.pool)
+        .await?
+        .into_iter()
+        .map(|d| models::DeadLetter {
+            kind: d.kind,
+            name: d.name,
+            event_id: d.event_id,
+            relay: d.relay,
+            attempts: d.attempts,
+            last_error: d.last_error,
+            updated_at: d.updated_at,
+        })
+        .collect();
+    Ok(Json(models::DeadLettersResponse { pending, dead }))
+}
@@ -7,14 +7,13 @@ use axum::{
 };
 use axum_extra::extract::WithRejection;
 use bitcoin::psbt::Psbt;
-use itertools::Itertools;
-use nomen_core::{CreateBuilder, Name};
+use nomen_core::{CreateBuilder, Name, Nsid, Records, RecordsBuilder};
 use serde::Deserialize;
 
 use crate::{
-    db::{self, name::NameDetails},
-    subcommands::util::{extend_psbt, name_event},
-    util::{format_time, KeyVal, Npub},
+    db::{self, children::ChildRecords, name::NameDetails, release::Release},
+    subcommands::util::{confusable_warnings, extend_psbt},
+    util::{display_name, format_time, lookup_query, Npub},
 };
 
 use super::{AppState, WebError};
@@ -60,7 +59,14 @@ pub async fn explorer(
 
     Ok(ExplorerTemplate {
         q: q.clone().unwrap_or_default(),
-        names: db::name::top_level_names(&conn, q).await?,
+        names: db::name::top_level_names(&conn, q)
+            .await?
+            .into_iter()
+            .map(|(_, name)| {
+                let display = display_name(&name);
+                (name, display)
+            })
+            .collect(),
         last_index_time,
     })
 }
@@ -69,8 +75,11 @@ pub async fn explorer(
 #[template(path = "name.html")]
 pub struct NameTemplate {
     name: String,
+    display_name: String,
+    nsid: String,
     record_keys: Vec<String>,
     records: HashMap<String, String>,
+    warnings: Vec<String>,
     blockhash: String,
     blocktime: String,
     txid: String,
@@ -80,21 +89,54 @@ pub struct NameTemplate {
     protocol: i64,
     v1_upgrade_blockheight: Option<i64>,
     v1_upgrade_txid: Option<String>,
+    expires_at: Option<i64>,
+    parent: Option<String>,
+    children: Vec<(String, String)>,
+}
+
+fn bech32_nsid(nsid: &str) -> String {
+    nsid.parse::<Nsid>()
+        .map_or_else(|_| nsid.to_string(), |n| n.to_bech32())
+}
+
+/// Records are stored as published by the owner, so malformed values are shown as warnings rather than hidden.
+fn warnings(records: &Records) -> Vec<String> {
+    records.validate().iter().map(ToString::to_string).collect()
+}
+
+impl NameTemplate {
+    /// A child name has no blockchain info of its own, so it shows that of the top-level name it is delegated from.
+    fn child(root: NameDetails, child: ChildRecords) -> anyhow::Result<Self> {
+        let records: Records = serde_json::from_str(&child.records)?;
+
+        Ok(NameTemplate {
+            display_name: display_name(&child.name),
+            nsid: bech32_nsid(&child.nsid),
+            name: child.name,
+            record_keys: records.iter().map(|(k, _)| k.clone()).collect(),
+            warnings: warnings(&records),
+            records: records.into(),
+            pubkey: child.pubkey,
+            parent: Some(child.parent),
+            ..root.try_into()?
+        })
+    }
 }
 
 impl TryFrom<NameDetails> for NameTemplate {
     type Error = anyhow::Error;
 
     fn try_from(value: NameDetails) -> Result<Self, Self::Error> {
-        let records: HashMap<String, String> = serde_json::from_str(&value.records)?;
-        let mut record_keys = records.keys().cloned().collect_vec();
-        record_keys.sort();
+        let records: Records = serde_json::from_str(&value.records)?;
         let blocktime = format_time(value.blocktime)?;
 
         Ok(NameTemplate {
+            display_name: display_name(&value.name),
+            nsid: bech32_nsid(&value.nsid),
             name: value.name,
-            record_keys,
-            records,
+            record_keys: records.iter().map(|(k, _)| k.clone()).collect(),
+            warnings: warnings(&records),
+            records: records.into(),
             blockhash: value.blockhash,
             blocktime,
             txid: value.txid,
@@ -104,6 +146,9 @@ impl TryFrom<NameDetails> for NameTemplate {
             protocol: value.protocol,
             v1_upgrade_blockheight: value.v1_upgrade_blockheight,
             v1_upgrade_txid: value.v1_upgrade_txid,
+            expires_at: value.expires_at,
+            parent: None,
+            children: vec![],
         })
     }
 }
@@ -113,15 +158,24 @@ pub async fn show_name(
     Path(nsid): Path<String>,
 ) -> Result<NameTemplate, WebError> {
     let conn = state.pool;
-    let details = db::name::details(&conn, &nsid).await?;
+    let query = lookup_query(&nsid);
+    let mut template = match db::children::records(&conn, &query).await? {
+        Some(child) => {
+            let root = db::name::details(&conn, &child.root).await?;
+            NameTemplate::child(root, child)?
+        }
+        None => db::name::details(&conn, &query).await?.try_into()?,
+    };
+    template.children = db::children::of_parent(&conn, &template.name).await?;
 
-    Ok(details.try_into()?)
+    Ok(template)
 }
 
 #[derive(askama::Template, Default)]
 #[template(path = "newname.html")]
 pub struct NewNameTemplate {
     upgrade: bool,
+    warnings: Vec<String>,
     data: String,
     name: String,
     pubkey: String,
@@ -159,29 +213,35 @@ pub async fn new_name_submit(
     State(state): State<AppState>,
     WithRejection(Form(form), _): WithRejection<Form<NewNameForm>, WebError>,
 ) -> Result<NewNameTemplate, WebError> {
-    let _name = Name::from_str(&form.name).map_err(|_| anyhow!("Invalid name"))?;
+    let name = Name::from_str(&form.name).map_err(|_| anyhow!("Invalid name"))?;
 
     // If we're upgrading an existing name, we don't actually want to error if the name exists.
     let available = if form.upgrade {
         true
     } else {
-        db::name::check_availability(&state.pool, form.name.as_ref()).await?
+        db::name::check_availability(&state.pool, name.as_ref()).await?
     };
     if !available {
         Err(anyhow!("Name unavailable"))?;
     }
     let (is_psbt, data) = if form.psbt.is_empty() {
-        let d = CreateBuilder::new(form.pubkey.as_ref(), &form.name).v1_op_return();
+        let d = CreateBuilder::new(form.pubkey.as_ref(), name.as_ref()).v1_op_return();
         (false, hex::encode(d))
     } else {
         let mut psbt: Psbt = form.psbt.parse()?;
-        extend_psbt(&mut psbt, &form.name, form.pubkey.as_ref());
+        extend_psbt(&mut psbt, name.as_ref(), form.pubkey.as_ref());
         (true, psbt.to_string())
     };
+    let warnings = if form.upgrade {
+        vec![]
+    } else {
+        confusable_warnings(&state.pool, &name).await?
+    };
     Ok(NewNameTemplate {
         upgrade: form.upgrade,
+        warnings,
         data,
-        name: form.name,
+        name: name.to_string(),
         pubkey: form.pubkey.to_string(),
         confirmations: state.config.confirmations(),
         is_psbt,
@@ -222,18 +282,17 @@ async fn records_from_query(query: &NewRecordsQuery, state: &AppState) -> Result
     let records = match &query.name {
         Some(name) => {
             let (records,) = sqlx::query_as::<_, (String,)>(
-                "SELECT records FROM valid_names_records_vw WHERE name = ?;",
+                "SELECT records FROM valid_names_records_vw WHERE name = ?
+                UNION ALL
+                SELECT records FROM valid_children_records_vw WHERE name = ?;",
             )
             .bind(name)
+            .bind(name)
             .fetch_optional(&state.pool)
             .await?
             .unwrap_or_else(|| (String::from(r#"{"KEY":"value"}"#),));
-            let records: HashMap<String, String> = serde_json::from_str(&records)?;
-            records
-                .iter()
-                .map(|(k, v)| format!("{k}={v}"))
-                .collect_vec()
-                .join("\n")
+            let records: Records = serde_json::from_str(&records)?;
+            records.to_lines()
         }
         None => "KEY=value".into(),
     };
@@ -252,15 +311,8 @@ pub async fn new_records_submit(
     State(state): State<AppState>,
     Form(form): Form<NewRecordsForm>,
 ) -> Result<NewRecordsTemplate, WebError> {
-    let records = form
-        .records
-        .lines()
-        .map(str::parse)
-        .collect::<Result<Vec<KeyVal>, _>>()?
-        .iter()
-        .map(|kv| kv.clone().pair())
-        .collect::<HashMap<_, _>>();
-    let event = name_event(*form.pubkey.as_ref(), &records, &form.name)?;
+    let records: Records = form.records.parse()?;
+    let event = RecordsBuilder::new(form.pubkey.as_ref(), &form.name, &records).unsigned_event();
     let unsigned_event = serde_json::to_string_pretty(&event)?;
     Ok(NewRecordsTemplate {
         name: form.name.to_string(),
@@ -271,6 +323,29 @@ pub async fn new_records_submit(
     })
 }
 
+#[derive(Deserialize)]
+pub struct ReleasesQuery {
+    name: String,
+}
+
+#[derive(askama::Template)]
+#[template(path = "releases.html")]
+pub struct ReleasesTemplate {
+    name: String,
+    releases: Vec<Release>,
+}
+
+pub async fn releases(
+    State(state): State<AppState>,
+    Query(query): Query<ReleasesQuery>,
+) -> Result<ReleasesTemplate, WebError> {
+    let name = query.name.trim().to_string();
+    Ok(ReleasesTemplate {
+        releases: db::release::history(&state.pool, &name).await?,
+        name,
+    })
+}
+
 #[derive(askama::Template)]
 #[template(path = "stats.html")]
 pub struct IndexerInfo {
@@ -280,9 +355,14 @@ pub struct IndexerInfo {
     known_names: i64,
     index_height: i64,
     nostr_events: i64,
+    audit_peers: usize,
+    audit_mismatches: i64,
+    outbox_pending: i64,
+    outbox_dead: i64,
 }
 
 pub async fn index_stats(State(state): State<AppState>) -> Result<IndexerInfo, WebError> {
+    let (outbox_pending, outbox_dead) = db::outbox::counts(&state.pool).await?;
     Ok(IndexerInfo {
         version: env!("CARGO_PKG_VERSION"),
         commit: env!("VERGEN_GIT_DESCRIBE"),
@@ -290,17 +370,24 @@ pub async fn index_stats(State(state): State<AppState>) -> Result<IndexerInfo, W
         known_names: db::stats::known_names(&state.pool).await?,
         index_height: db::stats::index_height(&state.pool).await?,
         nostr_events: db::stats::nostr_events(&state.pool).await?,
+        audit_peers: state.config.peers().len(),
+        audit_mismatches: db::audit::count(&state.pool).await?,
+        outbox_pending,
+        outbox_dead,
     })
 }
 
 pub mod transfer {
-    use axum::{extract::State, Form};
+    use axum::{
+        extract::{Query, State},
+        Form,
+    };
     use nomen_core::{SignatureV1, TransferBuilder, TransferV1};
     use secp256k1::schnorr::Signature;
     use serde::Deserialize;
 
     use crate::{
-        db,
+        db::{self, transfer::TransferStatus},
         subcommands::{AppState, WebError},
         util::Npub,
     };
@@ -408,6 +495,36 @@ pub mod transfer {
             data2: hex::encode(data2),
         })
     }
+
+    #[derive(Deserialize)]
+    pub struct StatusQuery {
+        name: String,
+    }
+
+    #[derive(askama::Template)]
+    #[template(path = "transfer/status.html")]
+    pub struct StatusTemplate {
+        name: String,
+        index_height: i64,
+        transfers: Vec<TransferStatus>,
+    }
+
+    pub async fn status(
+        State(state): State<AppState>,
+        Query(query): Query<StatusQuery>,
+    ) -> Result<StatusTemplate, WebError> {
+        let name = query.name.trim().to_string();
+        Ok(StatusTemplate {
+            index_height: db::stats::index_height(&state.pool).await?,
+            transfers: db::transfer::status(
+                &state.pool,
+                &name,
+                state.config.transfer_expiry_blocks(),
+            )
+            .await?,
+            name,
+        })
+    }
 }
 
 pub mod well_known {
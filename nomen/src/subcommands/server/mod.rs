@@ -1,5 +1,7 @@
 mod api;
 mod explorer;
+mod proof;
+mod relay;
 
 use std::time::Duration;
 
@@ -47,6 +49,9 @@ pub async fn start(config: &Config, conn: &SqlitePool) -> anyhow::Result<()> {
     if config.indexer() {
         let _indexer = tokio::spawn(indexer(config.clone()));
     }
+    if config.audit() {
+        let _auditor = tokio::spawn(auditor(config.clone()));
+    }
     let mut app = Router::new();
 
     if config.explorer() {
@@ -61,6 +66,8 @@ pub async fn start(config: &Config, conn: &SqlitePool) -> anyhow::Result<()> {
             .route("/transfer", get(explorer::transfer::initiate))
             .route("/transfer", post(explorer::transfer::submit_initiate))
             .route("/transfer/sign", post(explorer::transfer::complete))
+            .route("/transfer/status", get(explorer::transfer::status))
+            .route("/releases", get(explorer::releases))
             .route("/stats", get(explorer::index_stats));
     }
 
@@ -74,12 +81,35 @@ pub async fn start(config: &Config, conn: &SqlitePool) -> anyhow::Result<()> {
             .route("/name", get(api::name))
             .route("/create/data", get(api::op_return_v1))
             .route("/v0/create/data", get(api::op_return_v0))
+            .route("/commit/data", get(api::commit_data))
+            .route("/op_return/decode", get(api::decode_op_return))
             .route("/transfer/event", get(api::get_transfer_event))
             .route("/transfer/data", get(api::get_transfer))
+            .route("/transfer/v2/message", get(api::get_transfer_v2_message))
+            .route(
+                "/transfer/v2/signature",
+                get(api::get_transfer_v2_signature),
+            )
+            .route("/transfer/status", get(api::get_transfer_status))
+            .route("/transfer/expiring", get(api::get_expiring_transfers))
+            .route("/renew/message", get(api::get_renew_message))
+            .route("/renew/data", get(api::get_renew))
+            .route("/release/message", get(api::get_release_message))
+            .route("/release/data", get(api::get_release))
+            .route("/release/history", get(api::get_release_history))
+            .route("/name/history", get(api::get_name_history))
+            .route("/children/event", get(api::get_children_event))
+            .route("/delegate/event", get(api::get_delegate_event))
+            .route("/audit", get(api::audit))
+            .route("/outbox/dead", get(api::outbox_dead))
             .layer(CorsLayer::new().allow_origin(Any).allow_methods(Any));
         app = app.nest("/api", api_router);
     }
 
+    if config.relay() {
+        app = app.route("/relay", get(relay::relay));
+    }
+
     let state = AppState {
         config: config.clone(),
         pool: conn.clone(),
@@ -114,3 +144,19 @@ async fn indexer(config: Config) -> anyhow::Result<()> {
         interval.tick().await;
     }
 }
+
+async fn auditor(config: Config) -> anyhow::Result<()> {
+    let mut interval = interval(Duration::from_secs(config.audit_delay()));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        interval.tick().await;
+        let result = match config.sqlite().await {
+            Ok(pool) => subcommands::audit(&config, &pool).await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            tracing::error!("Audit error: {}", err);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use bitcoin::{BlockHash, Txid};
+use bitcoincore_rpc::{Client, RpcApi};
+use nomen_core::{NameProof, TransferProof, TxProof};
+use nostr_sdk::Event;
+
+use crate::{
+    config::Config,
+    db::{self, name::NameRecords},
+};
+
+enum BlockRef {
+    Hash(BlockHash),
+    Height(u64),
+}
+
+/// Builds an SPV proof bundle for a resolved name by fetching the relevant blocks from the Bitcoin node.
+pub async fn for_name(
+    config: &Config,
+    pool: &sqlx::SqlitePool,
+    nr: &NameRecords,
+) -> anyhow::Result<NameProof> {
+    let create = (nr.blockhash.parse()?, nr.txid.parse()?);
+    let upgrade = match (nr.v1_upgrade_blockheight, &nr.v1_upgrade_txid) {
+        (Some(height), Some(txid)) => Some((height as u64, txid.parse()?)),
+        _ => None,
+    };
+    let transfers = db::transfer::completed(pool, &nr.name, nr.blockheight)
+        .await?
+        .into_iter()
+        .map(|ct| -> anyhow::Result<_> {
+            Ok((
+                (ct.blockhash.parse()?, ct.txid.parse()?),
+                (ct.signature_blockheight as u64, ct.signature_txid.parse()?),
+            ))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let event = Event::from_json(&nr.raw_event)?;
+    let delegation = if event.pubkey.to_string() == nr.pubkey {
+        None
+    } else {
+        db::record_delegate::find(pool, &nr.name, &nr.pubkey)
+            .await?
+            .map(|rd| Event::from_json(rd.raw_event))
+            .transpose()?
+    };
+
+    let client = config.rpc_client()?;
+    tokio::task::spawn_blocking(move || -> anyhow::Result<NameProof> {
+        let transfers = transfers
+            .into_iter()
+            .map(|((bh, txid), (height, sig_txid))| -> anyhow::Result<_> {
+                Ok(TransferProof {
+                    transfer: tx_proof(&client, &BlockRef::Hash(bh), &txid)?,
+                    signature: tx_proof(&client, &BlockRef::Height(height), &sig_txid)?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(NameProof {
+            create: tx_proof(&client, &BlockRef::Hash(create.0), &create.1)?,
+            upgrade: upgrade
+                .map(|(height, txid)| tx_proof(&client, &BlockRef::Height(height), &txid))
+                .transpose()?,
+            transfers,
+            event: Some(event),
+            delegation,
+        })
+    })
+    .await?
+}
+
+fn tx_proof(client: &Client, block: &BlockRef, txid: &Txid) -> anyhow::Result<TxProof> {
+    let blockhash = match block {
+        BlockRef::Hash(bh) => *bh,
+        BlockRef::Height(height) => client.get_block_hash(*height)?,
+    };
+    let block = client.get_block(&blockhash)?;
+    Ok(TxProof::from_block(&block, txid)?)
+}
@@ -0,0 +1,206 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        State, WebSocketUpgrade,
+    },
+    response::Response,
+};
+use nomen_core::NameKind;
+use nostr_sdk::{ClientMessage, Event, Filter, RelayMessage};
+
+use crate::{
+    db,
+    subcommands::index::events::{event_data, save_event},
+};
+
+use super::AppState;
+
+/// NIP-01 relay serving the record and child delegation events of valid names, and this indexer's relay index. New subscriptions are
+/// answered from the index and then closed with EOSE, there are no live updates.
+#[allow(clippy::unused_async)]
+pub async fn relay(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
+    ws.on_upgrade(move |socket| handle(socket, state))
+}
+
+async fn handle(mut socket: WebSocket, state: AppState) {
+    while let Some(Ok(msg)) = socket.recv().await {
+        let text = match msg {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let replies = match respond(&state, &text).await {
+            Ok(replies) => replies,
+            Err(err) => vec![RelayMessage::new_notice(format!("error: {err}"))],
+        };
+        for reply in replies {
+            if socket.send(Message::Text(reply.as_json())).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+async fn respond(state: &AppState, text: &str) -> anyhow::Result<Vec<RelayMessage>> {
+    let replies = match ClientMessage::from_json(text)? {
+        ClientMessage::Req {
+            subscription_id,
+            filters,
+        } => {
+            let mut replies = query(state, &filters)
+                .await?
+                .into_iter()
+                .map(|e| RelayMessage::new_event(subscription_id.clone(), e))
+                .collect::<Vec<_>>();
+            replies.push(RelayMessage::new_eose(subscription_id));
+            replies
+        }
+        ClientMessage::Count {
+            subscription_id,
+            filters,
+        } => {
+            let count = count(state, &filters).await?;
+            vec![RelayMessage::new_count(subscription_id, count)]
+        }
+        ClientMessage::Event(event) => {
+            let event_id = event.id;
+            let (accepted, message) = match accept(state, *event).await? {
+                Ok(()) => (true, String::new()),
+                Err(reason) => (false, reason),
+            };
+            vec![RelayMessage::new_ok(event_id, accepted, message)]
+        }
+        ClientMessage::Close(_) => vec![],
+        ClientMessage::Auth(_) => vec![RelayMessage::new_notice("auth is not supported")],
+    };
+    Ok(replies)
+}
+
+/// The most events a filter is answered with, whatever its `limit`.
+const MAX_EVENTS: usize = 500;
+
+/// Events matching any of the filters, newest first for each filter.
+async fn query(state: &AppState, filters: &[Filter]) -> anyhow::Result<Vec<Event>> {
+    let mut results: Vec<Event> = vec![];
+    for filter in filters {
+        let limit = filter.limit.map_or(MAX_EVENTS, |l| l.min(MAX_EVENTS));
+        for raw in db::relay::events(&state.pool, filter, limit).await? {
+            let Ok(event) = Event::from_json(raw) else {
+                continue;
+            };
+            if matches(filter, &event) && !results.iter().any(|r| r.id == event.id) {
+                results.push(event);
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// The number of events matching any of the filters, each counted once.
+async fn count(state: &AppState, filters: &[Filter]) -> anyhow::Result<usize> {
+    Ok(usize::try_from(
+        db::relay::count(&state.pool, filters).await?,
+    )?)
+}
+
+/// Accepts a record event from the current owner of a name or their record delegate. The inner error is the reason for rejection, reported
+/// back to the client in the `OK` message.
+async fn accept(state: &AppState, event: Event) -> anyhow::Result<Result<(), String>> {
+    if !state.config.relay_accept() {
+        return Ok(Err("blocked: this relay is read-only".into()));
+    }
+    if event.kind != NameKind::Name.into() {
+        return Ok(Err("blocked: only name record events are accepted".into()));
+    }
+    if event.verify().is_err() {
+        return Ok(Err("invalid: bad signature".into()));
+    }
+    let ed = match event_data(&state.pool, &event).await {
+        Ok(ed) => ed,
+        Err(err) => return Ok(Err(format!("invalid: {err}"))),
+    };
+    if ed.validate().is_err() {
+        return Ok(Err("invalid: nsid does not match name and pubkey".into()));
+    }
+    match &ed.records {
+        None => return Ok(Err("invalid: content is not a records object".into())),
+        Some(records) => {
+            if let Some(err) = records.validate().first() {
+                return Ok(Err(format!("invalid: {err}")));
+            }
+        }
+    }
+    if !db::name::is_owner(&state.pool, ed.name.as_ref(), &ed.pubkey.to_string()).await? {
+        return Ok(Err("blocked: pubkey does not own this name".into()));
+    }
+
+    save_event(&state.pool, ed).await?;
+    Ok(Ok(()))
+}
+
+fn matches(filter: &Filter, event: &Event) -> bool {
+    let prefixed = |prefixes: &[String], value: String| {
+        prefixes.is_empty() || prefixes.iter().any(|p| value.starts_with(p.as_str()))
+    };
+    let tagged = |letter: &str, values: &[String]| {
+        values.is_empty()
+            || event.tags.iter().any(|t| {
+                let t = t.as_vec();
+                t.len() > 1 && t[0] == letter && values.contains(&t[1])
+            })
+    };
+    let events = filter
+        .events
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    let pubkeys = filter
+        .pubkeys
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+    prefixed(&filter.ids, event.id.to_hex())
+        && prefixed(&filter.authors, event.pubkey.to_string())
+        && (filter.kinds.is_empty() || filter.kinds.contains(&event.kind))
+        && filter.since.map_or(true, |since| event.created_at >= since)
+        && filter.until.map_or(true, |until| event.created_at <= until)
+        && tagged("e", &events)
+        && tagged("p", &pubkeys)
+        && tagged("t", &filter.hashtags)
+        && tagged("r", &filter.references)
+        && tagged("d", &filter.identifiers)
+        && filter
+            .generic_tags
+            .iter()
+            .all(|(letter, values)| tagged(&letter.to_string(), values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVENT: &str = r#"{"id":"4fb5485ad12706f3ddbde1cdeab3199fcbef01b4c2456a7420ef5acb400d29e5","pubkey":"d57b873363d2233d3cd54453416deff9546df50d963bb1208da37f10a4c23d6f","created_at":1682476154,"kind":38300,"tags":[["d","28d63a9a61c6c5ce6be37a830105c92cf7a8f365"],["nom","smith"]],"content":"{\"IP4\":\"127.0.0.1\",\"NPUB\":\"npub1234\"}","sig":"53a629c8169c29abc971653b71ebf8ceb185735170b702dd48377a3336819680577ef28a257b8e4db5e8101531232e1c886a35721b5af1399c32cb526fd61bb6"}"#;
+
+    fn filter(json: &str) -> Filter {
+        Filter::from_json(json).unwrap()
+    }
+
+    #[test]
+    fn test_matches() {
+        let event = Event::from_json(EVENT).unwrap();
+        assert!(matches(&filter("{}"), &event));
+        assert!(matches(&filter(r#"{"kinds":[38300]}"#), &event));
+        assert!(!matches(&filter(r#"{"kinds":[38301]}"#), &event));
+        assert!(matches(&filter(r#"{"ids":["4fb5485a"]}"#), &event));
+        assert!(matches(&filter(r#"{"authors":["d57b8733"]}"#), &event));
+        assert!(!matches(&filter(r#"{"authors":["0000"]}"#), &event));
+        assert!(matches(
+            &filter(r##"{"#d":["28d63a9a61c6c5ce6be37a830105c92cf7a8f365"]}"##),
+            &event
+        ));
+        assert!(!matches(&filter(r##"{"#d":["other"]}"##), &event));
+        assert!(matches(&filter(r#"{"since":1682476154}"#), &event));
+        assert!(!matches(&filter(r#"{"until":1682476153}"#), &event));
+    }
+}
@@ -1,13 +1,14 @@
-use std::collections::HashMap;
-
 use bitcoin::{
     psbt::{Output, Psbt},
     script::PushBytesBuf,
     ScriptBuf, TxOut,
 };
-use nomen_core::{CreateBuilder, NameKind, NsidBuilder};
+use nomen_core::{CreateBuilder, Name, NameKind, NsidBuilder};
 use nostr_sdk::{EventBuilder, Tag, TagKind, UnsignedEvent};
 use secp256k1::XOnlyPublicKey;
+use sqlx::SqlitePool;
+
+use crate::{db, util::display_name};
 
 pub fn extend_psbt(psbt: &mut Psbt, name: &str, pubkey: &XOnlyPublicKey) {
     let data = CreateBuilder::new(pubkey, name).v1_op_return();
@@ -24,16 +25,17 @@ pub fn extend_psbt(psbt: &mut Psbt, name: &str, pubkey: &XOnlyPublicKey) {
     });
 }
 
-pub fn name_event(
+/// Delegation event for the children of `name`, given as `(label, pubkey)` pairs.
+pub fn children_event(
     pubkey: XOnlyPublicKey,
-    records: &HashMap<String, String>,
+    children: &[(String, XOnlyPublicKey)],
     name: &str,
 ) -> anyhow::Result<UnsignedEvent> {
-    let records = serde_json::to_string(&records)?;
+    let children = serde_json::to_string(&children)?;
     let nsid = NsidBuilder::new(name, &pubkey).finalize();
     let event = EventBuilder::new(
-        NameKind::Name.into(),
-        records,
+        NameKind::Children.into(),
+        children,
         &[
             Tag::Identifier(nsid.to_string()),
             Tag::Generic(TagKind::Custom("nom".to_owned()), vec![name.to_owned()]),
@@ -43,3 +45,18 @@ pub fn name_event(
 
     Ok(event)
 }
+
+/// Warnings for a new name which could be mistaken for an existing one.
+pub async fn confusable_warnings(pool: &SqlitePool, name: &Name) -> anyhow::Result<Vec<String>> {
+    Ok(db::name::confusable_names(pool, name)
+        .await?
+        .iter()
+        .map(|other| {
+            format!(
+                "{} is confusable with the existing name {}",
+                name.to_unicode(),
+                display_name(other)
+            )
+        })
+        .collect())
+}
@@ -0,0 +1,336 @@
+//! Indexing scenarios for the conformance vectors in `docs/vectors`: the Nomen `OP_RETURN`s found in a range of
+//! blocks, and the names and transfers the indexer derives from them.
+
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use bitcoin::{hashes::Hash, BlockHash, Txid};
+use nomen_core::OpReturn;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+
+use crate::{
+    config::Config,
+    db::{self, raw::RawBlockchain},
+    subcommands::blockchain,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Scenario {
+    pub description: String,
+    /// Name expiry to index with, see [`Config::name_expiry`]. Names don't expire without it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiry: Option<Expiry>,
+    /// Blocks with Nomen outputs, in order. Unlisted heights between them are empty blocks.
+    pub blocks: Vec<Block>,
+    pub expected: Outcome,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Expiry {
+    pub activation_height: usize,
+    pub blocks: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Block {
+    pub height: usize,
+    pub hash: BlockHash,
+    pub time: usize,
+    #[serde(default)]
+    pub outputs: Vec<Output>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Output {
+    pub txid: Txid,
+    pub txheight: usize,
+    pub vout: usize,
+    #[serde(with = "hex::serde")]
+    pub op_return: Vec<u8>,
+    /// The transactions spent by the output's transaction, which bind a transfer signature to its transfer.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spends: Vec<Txid>,
+}
+
+/// The state of the index once every block of a scenario has been indexed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Outcome {
+    pub names: Vec<NameOutcome>,
+    pub transfers: Vec<TransferOutcome>,
+}
+
+#[derive(Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct NameOutcome {
+    pub name: Option<String>,
+    pub fingerprint: String,
+    pub nsid: String,
+    pub pubkey: Option<String>,
+    pub protocol: i64,
+    pub txid: String,
+    pub blockheight: i64,
+}
+
+#[derive(Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct TransferOutcome {
+    pub name: String,
+    pub pubkey: String,
+    pub txid: String,
+    pub outcome: String,
+}
+
+/// Indexes the blocks of `scenario` in a scratch database, as if the indexer had run after every block.
+pub async fn replay(config: &Config, scenario: &Scenario) -> anyhow::Result<Outcome> {
+    static SCRATCH: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "nomen-vectors-{}-{}.db",
+        std::process::id(),
+        SCRATCH.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let mut scratch = config.clone();
+    scratch.file.data = Some(path.clone());
+    scratch.file.server.expiry_activation_height = scenario.expiry.map(|e| e.activation_height);
+    scratch.file.server.expiry_blocks = scenario.expiry.map(|e| e.blocks);
+
+    let outcome = replay_blocks(&scratch, &scenario.blocks).await;
+    for suffix in ["", "-wal", "-shm"] {
+        let mut file = path.clone().into_os_string();
+        file.push(suffix);
+        std::fs::remove_file(PathBuf::from(file)).ok();
+    }
+    outcome
+}
+
+async fn replay_blocks(config: &Config, blocks: &[Block]) -> anyhow::Result<Outcome> {
+    let pool = db::initialize(config).await?;
+    let (Some(first), Some(last)) = (blocks.first(), blocks.last()) else {
+        return Ok(Outcome::default());
+    };
+
+    let mut blocks = blocks.iter().peekable();
+    for height in first.height..=last.height {
+        let hash = match blocks.next_if(|b| b.height == height) {
+            Some(block) => {
+                for output in &block.outputs {
+                    let raw = RawBlockchain {
+                        blockhash: block.hash,
+                        txid: output.txid,
+                        blocktime: block.time,
+                        blockheight: block.height,
+                        txheight: output.txheight,
+                        vout: output.vout,
+                        data: output.op_return.clone(),
+                    };
+                    db::raw::insert_raw_blockchain(&pool, &raw).await?;
+                    db::raw::insert_inputs(&pool, block.height, &output.txid, &output.spends)
+                        .await?;
+                }
+                block.hash
+            }
+            None => BlockHash::all_zeros(),
+        };
+        db::index::insert_height(&pool, height as i64, &hash).await?;
+        blockchain::update_index(config, &pool).await?;
+    }
+
+    let outcome = outcome(&pool).await;
+    pool.close().await;
+    outcome
+}
+
+async fn outcome(pool: &SqlitePool) -> anyhow::Result<Outcome> {
+    let names = sqlx::query_as::<_, NameOutcome>(
+        "SELECT name, fingerprint, nsid, pubkey, protocol, txid, blockheight FROM valid_names_vw ORDER BY fingerprint;",
+    )
+    .fetch_all(pool)
+    .await?;
+    let transfers = sqlx::query_as::<_, TransferOutcome>(
+        "SELECT name, pubkey, txid, outcome FROM transfer_status_vw ORDER BY blockheight, txheight, vout;",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(Outcome { names, transfers })
+}
+
+/// The indexed blocks between `from` and `to` which have Nomen outputs, and the last indexed block of the range so
+/// that expiries up to it are replayed.
+async fn exported_blocks(pool: &SqlitePool, from: i64, to: i64) -> anyhow::Result<Vec<Block>> {
+    let rows = sqlx::query_as::<_, RawBlockchain>(
+        "SELECT * FROM raw_blockchain WHERE blockheight BETWEEN ? AND ? ORDER BY blockheight, txheight, vout;",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    let mut blocks = BTreeMap::new();
+    for row in rows {
+        let block = blocks.entry(row.blockheight).or_insert_with(|| Block {
+            height: row.blockheight,
+            hash: row.blockhash,
+            time: row.blocktime,
+            outputs: vec![],
+        });
+        block.outputs.push(Output {
+            txid: row.txid,
+            txheight: row.txheight,
+            vout: row.vout,
+            op_return: row.data,
+            spends: vec![],
+        });
+    }
+
+    let inputs = sqlx::query_as::<_, (String, String)>(
+        "SELECT txid, prev_txid FROM raw_blockchain_inputs WHERE blockheight BETWEEN ? AND ? ORDER BY txid, prev_txid;",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+    let mut spends = BTreeMap::<Txid, Vec<Txid>>::new();
+    for (txid, prev_txid) in inputs {
+        spends
+            .entry(txid.parse()?)
+            .or_default()
+            .push(prev_txid.parse()?);
+    }
+    for output in blocks.values_mut().flat_map(|b| &mut b.outputs) {
+        if let Some(prev_txids) = spends.get(&output.txid) {
+            output.spends = prev_txids.clone();
+        }
+    }
+
+    let tip = sqlx::query_as::<_, (i64, String)>(
+        "SELECT blockheight, blockhash FROM index_height WHERE blockheight BETWEEN ? AND ? ORDER BY blockheight DESC LIMIT 1;",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_optional(pool)
+    .await?;
+    if let Some((height, hash)) = tip {
+        let time = blocks.values().last().map_or(0, |b: &Block| b.time);
+        blocks.entry(height as usize).or_insert(Block {
+            height: height as usize,
+            hash: hash.parse()?,
+            time,
+            outputs: vec![],
+        });
+    }
+
+    Ok(blocks.into_values().collect())
+}
+
+#[derive(Serialize)]
+struct OpReturnVectors {
+    valid: Vec<ValidOpReturn>,
+    invalid: Vec<InvalidOpReturn>,
+}
+
+#[derive(Serialize)]
+struct ValidOpReturn {
+    description: String,
+    #[serde(with = "hex::serde")]
+    op_return: Vec<u8>,
+    decoded: OpReturn,
+}
+
+#[derive(Serialize)]
+struct InvalidOpReturn {
+    description: String,
+    #[serde(with = "hex::serde")]
+    op_return: Vec<u8>,
+}
+
+fn op_return_vectors(blocks: &[Block]) -> OpReturnVectors {
+    let mut vectors = OpReturnVectors {
+        valid: vec![],
+        invalid: vec![],
+    };
+    for block in blocks {
+        for output in &block.outputs {
+            let description = format!("{}:{} at height {}", output.txid, output.vout, block.height);
+            let op_return = output.op_return.clone();
+            match OpReturn::try_from(op_return.as_slice()) {
+                Ok(decoded) => vectors.valid.push(ValidOpReturn {
+                    description,
+                    op_return,
+                    decoded,
+                }),
+                Err(_) => vectors.invalid.push(InvalidOpReturn {
+                    description,
+                    op_return,
+                }),
+            }
+        }
+    }
+    vectors
+}
+
+/// Prints conformance vectors for the blocks indexed between `from` and `to`: an indexing scenario, with the outcome
+/// found by replaying the range in a scratch database, or with `op_returns` the decoding of every output.
+pub(crate) async fn vectors(
+    config: &Config,
+    pool: &SqlitePool,
+    from: Option<i64>,
+    to: Option<i64>,
+    description: String,
+    op_returns: bool,
+) -> anyhow::Result<()> {
+    let blocks = exported_blocks(pool, from.unwrap_or_default(), to.unwrap_or(i64::MAX)).await?;
+    let json = if op_returns {
+        serde_json::to_string_pretty(&op_return_vectors(&blocks))?
+    } else {
+        let mut scenario = Scenario {
+            description,
+            expiry: config
+                .name_expiry()
+                .map(|(activation_height, blocks)| Expiry {
+                    activation_height,
+                    blocks,
+                }),
+            blocks,
+            expected: Outcome::default(),
+        };
+        scenario.expected = replay(config, &scenario).await?;
+        serde_json::to_string_pretty(&scenario)?
+    };
+    println!("{json}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{Cli, ConfigFile, Subcommand};
+
+    use super::*;
+
+    const SCENARIOS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../docs/vectors/scenarios");
+
+    #[tokio::test]
+    async fn test_scenarios() {
+        let config = Config::new(
+            Cli {
+                config: "nomen.toml".into(),
+                subcommand: Subcommand::Index,
+            },
+            ConfigFile::default(),
+        );
+        let mut files = std::fs::read_dir(SCENARIOS)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert!(!files.is_empty());
+
+        for file in files {
+            let scenario: Scenario =
+                serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+            let outcome = replay(&config, &scenario).await.unwrap();
+            assert_eq!(outcome, scenario.expected, "{}", file.display());
+        }
+    }
+}
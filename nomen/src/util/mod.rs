@@ -1,11 +1,12 @@
-mod keyval;
 mod npub;
 mod nsec;
 
-pub use keyval::*;
 pub use npub::*;
 pub use nsec::*;
 
+use std::str::FromStr;
+
+use nomen_core::{Name, NameEntity};
 use time::{macros::format_description, OffsetDateTime};
 
 pub fn format_time(timestamp: i64) -> anyhow::Result<String> {
@@ -13,3 +14,20 @@ pub fn format_time(timestamp: i64) -> anyhow::Result<String> {
     let format = format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
     Ok(dt.format(format)?)
 }
+
+/// The canonical form of a name typed by a user. Child names and input which isn't a valid name are returned as they
+/// are.
+pub fn canonical_name(name: &str) -> String {
+    Name::from_str(name).map_or_else(|_| name.to_string(), |n| n.to_string())
+}
+
+/// A stored name for display, with punycode labels decoded to Unicode.
+pub fn display_name(name: &str) -> String {
+    Name::parse_hierarchical(name).map_or_else(|_| name.to_string(), |n| n.to_unicode())
+}
+
+/// A name or nsid typed by a user, for lookup by either. Nsids in any of their forms become hex, names their canonical
+/// form.
+pub fn lookup_query(query: &str) -> String {
+    NameEntity::from_str(query).map_or_else(|_| canonical_name(query), |e| e.nsid.to_string())
+}
@@ -0,0 +1,126 @@
+use secp256k1::XOnlyPublicKey;
+use sha2::{Digest, Sha256};
+
+use super::{CommitV1, RevealV1};
+
+/// Domain separation tag of the commit–reveal commitment.
+pub const COMMIT_TAG: &str = "nomen/commit/v1";
+
+/// A reveal must be mined at least this many blocks after its commit, so that a front-runner who only learns the name
+/// from the reveal can't get a commit of their own in ahead of it.
+pub const REVEAL_MIN_DELAY: usize = 6;
+
+/// A reveal must be mined at most this many blocks after its commit. Older commits can no longer be revealed.
+pub const REVEAL_MAX_DELAY: usize = 1008;
+
+/// Longest name that fits in a reveal `OP_RETURN` alongside the pubkey and salt. Longer names can only be created
+/// directly, without commit–reveal.
+pub const MAX_REVEAL_NAME_LEN: usize = 80 - 5 - 32 - 8;
+
+/// The commitment to a name registration, the BIP340 style tagged hash `sha256(sha256(tag) || sha256(tag) ||
+/// len(name) || name || pubkey || salt)` with [`COMMIT_TAG`] as tag and the name length as a single byte.
+pub fn commitment(
+    name: &str,
+    pubkey: &XOnlyPublicKey,
+    salt: &[u8; 8],
+) -> Result<[u8; 32], super::UtilError> {
+    let tag = Sha256::digest(COMMIT_TAG.as_bytes());
+    Ok(Sha256::new()
+        .chain_update(tag)
+        .chain_update(tag)
+        .chain_update([super::length_prefix(name)?])
+        .chain_update(name.as_bytes())
+        .chain_update(pubkey.serialize())
+        .chain_update(salt)
+        .finalize()
+        .into())
+}
+
+/// Builds the two `OP_RETURN`s of a commit–reveal registration. Claims are ranked by the position of the commit, so
+/// the reveal can't be front-run. The salt must be kept until the reveal is broadcast.
+pub struct CommitBuilder<'a> {
+    pub pubkey: &'a XOnlyPublicKey,
+    pub name: &'a str,
+    pub salt: [u8; 8],
+}
+
+impl<'a> CommitBuilder<'a> {
+    /// A builder with a random salt.
+    pub fn new(pubkey: &'a XOnlyPublicKey, name: &'a str) -> CommitBuilder<'a> {
+        CommitBuilder::with_salt(pubkey, name, rand::random())
+    }
+
+    pub fn with_salt(
+        pubkey: &'a XOnlyPublicKey,
+        name: &'a str,
+        salt: [u8; 8],
+    ) -> CommitBuilder<'a> {
+        CommitBuilder { pubkey, name, salt }
+    }
+
+    pub fn commit_op_return(&self) -> Result<Vec<u8>, super::UtilError> {
+        Ok(CommitV1::new(commitment(self.name, self.pubkey, &self.salt)?).serialize())
+    }
+
+    pub fn reveal_op_return(&self) -> Result<Vec<u8>, super::UtilError> {
+        if self.name.len() > MAX_REVEAL_NAME_LEN {
+            return Err(super::UtilError::RevealNameTooLong);
+        }
+        Ok(RevealV1 {
+            pubkey: *self.pubkey,
+            salt: self.salt,
+            name: self.name.to_string(),
+        }
+        .serialize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_op_returns() {
+        let pk = "60de6fbc4a78209942c62706d904ff9592c2e856f219793f7f73e62fc33bfc18"
+            .parse()
+            .unwrap();
+        let cb = CommitBuilder::with_salt(&pk, "hello-world", *b"saltsalt");
+
+        assert_eq!(
+            hex::encode(cb.commit_op_return().unwrap()),
+            "4e4f4d0103f45dc44be70e88fe6e6d369ffacc76eca3e56d8861771b09f96b24b6517f258f"
+        );
+        assert_eq!(hex::encode(cb.reveal_op_return().unwrap()), "4e4f4d010460de6fbc4a78209942c62706d904ff9592c2e856f219793f7f73e62fc33bfc1873616c7473616c7468656c6c6f2d776f726c64");
+    }
+
+    #[test]
+    fn test_reveal_opens_commit() {
+        let pk = "60de6fbc4a78209942c62706d904ff9592c2e856f219793f7f73e62fc33bfc18"
+            .parse()
+            .unwrap();
+        let cb = CommitBuilder::new(&pk, "hello-world");
+        let commit = CommitV1::try_from(cb.commit_op_return().unwrap().as_ref()).unwrap();
+        let reveal = RevealV1::try_from(cb.reveal_op_return().unwrap().as_ref()).unwrap();
+        assert_eq!(reveal.name, "hello-world");
+        assert_eq!(reveal.pubkey, pk);
+        assert_eq!(reveal.commitment().unwrap(), commit.commitment);
+
+        let other = CommitBuilder::with_salt(&pk, "hello-world", [0; 8]);
+        assert_ne!(
+            CommitV1::try_from(other.commit_op_return().unwrap().as_ref()).unwrap(),
+            commit
+        );
+        assert!(RevealV1::try_from(cb.commit_op_return().unwrap().as_ref()).is_err());
+        assert!(CommitV1::try_from(cb.reveal_op_return().unwrap().as_ref()).is_err());
+
+        let long = "a".repeat(MAX_REVEAL_NAME_LEN + 1);
+        let cb = CommitBuilder::new(&pk, &long);
+        assert!(cb.reveal_op_return().is_err());
+        assert!(cb.commit_op_return().is_ok());
+
+        let too_long = "a".repeat(256);
+        assert!(CommitBuilder::new(&pk, &too_long)
+            .commit_op_return()
+            .is_err());
+    }
+}
@@ -0,0 +1,109 @@
+use super::Name;
+
+/// Single characters which look alike, mapped to the ASCII letter they are mistaken for. Based on the Unicode
+/// confusables data (UTS #39), limited to digits and the Cyrillic and Greek letters which pass name normalisation.
+const CONFUSABLE_CHARS: &[(char, char)] = &[
+    ('0', 'o'),
+    ('1', 'l'),
+    ('3', 'e'),
+    ('4', 'a'),
+    ('5', 's'),
+    ('6', 'b'),
+    ('8', 'b'),
+    ('9', 'g'),
+    ('а', 'a'),
+    ('в', 'b'),
+    ('г', 'r'),
+    ('д', 'a'),
+    ('е', 'e'),
+    ('ё', 'e'),
+    ('з', 'e'),
+    ('к', 'k'),
+    ('м', 'm'),
+    ('н', 'h'),
+    ('о', 'o'),
+    ('п', 'n'),
+    ('р', 'p'),
+    ('с', 'c'),
+    ('т', 't'),
+    ('у', 'y'),
+    ('х', 'x'),
+    ('ь', 'b'),
+    ('і', 'i'),
+    ('ї', 'i'),
+    ('ј', 'j'),
+    ('ѕ', 's'),
+    ('ӏ', 'l'),
+    ('ԁ', 'd'),
+    ('ԛ', 'q'),
+    ('ԝ', 'w'),
+    ('α', 'a'),
+    ('β', 'b'),
+    ('ε', 'e'),
+    ('η', 'n'),
+    ('ι', 'i'),
+    ('κ', 'k'),
+    ('ν', 'v'),
+    ('ο', 'o'),
+    ('ρ', 'p'),
+    ('τ', 't'),
+    ('υ', 'u'),
+    ('χ', 'x'),
+];
+
+/// Letter sequences which look like a single letter.
+const CONFUSABLE_SEQUENCES: &[(&str, &str)] = &[("rn", "m"), ("vv", "w"), ("cl", "d")];
+
+/// The skeleton of a name: two names with the same skeleton are likely to be mistaken for one another. Punycode
+/// labels are decoded, lookalike characters and sequences are mapped to a single ASCII letter, and hyphens are
+/// dropped.
+pub fn skeleton(name: &Name) -> String {
+    let mut skeleton: String = name
+        .to_unicode()
+        .chars()
+        .filter(|c| *c != '-')
+        .map(|c| {
+            CONFUSABLE_CHARS
+                .iter()
+                .find(|(from, _)| *from == c)
+                .map_or(c, |(_, to)| *to)
+        })
+        .collect();
+    for (from, to) in CONFUSABLE_SEQUENCES {
+        skeleton = skeleton.replace(from, to);
+    }
+    skeleton
+}
+
+/// Whether two different names share a skeleton.
+pub fn is_confusable(a: &Name, b: &Name) -> bool {
+    a != b && skeleton(a) == skeleton(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn name(s: &str) -> Name {
+        Name::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_confusable() {
+        assert!(is_confusable(&name("paypa1"), &name("paypal")));
+        assert!(is_confusable(&name("pay-pal"), &name("paypal")));
+        assert!(is_confusable(&name("g00gle"), &name("google")));
+        assert!(is_confusable(&name("modern"), &name("modem")));
+        assert!(is_confusable(&name("раураl"), &name("paypal")));
+        assert!(!is_confusable(&name("paypal"), &name("paypal")));
+        assert!(!is_confusable(&name("paypal"), &name("paypals")));
+    }
+
+    #[test]
+    fn test_skeleton() {
+        assert_eq!(skeleton(&name("i-1-l")), "ill");
+        assert_eq!(skeleton(&name("xn--80ak6aa92e")), skeleton(&name("apple")));
+    }
+}
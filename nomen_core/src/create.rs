@@ -1,5 +1,4 @@
 use crate::NomenKind;
-use nostr_sdk::{EventId, UnsignedEvent};
 use secp256k1::XOnlyPublicKey;
 
 use super::{CreateV0, CreateV1, Hash160, NsidBuilder};
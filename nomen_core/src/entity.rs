@@ -0,0 +1,154 @@
+use std::{fmt::Display, str::FromStr};
+
+use bitcoin::bech32::{self, FromBase32, ToBase32, Variant};
+
+use super::Nsid;
+
+/// Human readable part of a [`NameEntity`].
+pub const NAME_ENTITY_HRP: &str = "nname";
+
+const TLV_NSID: u8 = 0;
+const TLV_RELAY: u8 = 1;
+const TLV_NAME: u8 = 2;
+
+/// A NIP-19 style bech32 `nname1…` entity bundling an nsid with the name and indexer relays it can be found through.
+/// It is a list of TLV records: type 0 is the 20 byte nsid, type 1 a relay URL, which may repeat, and type 2 the name.
+/// Unknown types are ignored.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_with::SerializeDisplay, serde_with::DeserializeFromStr)
+)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameEntity {
+    pub nsid: Nsid,
+    pub name: Option<String>,
+    pub relays: Vec<String>,
+}
+
+impl NameEntity {
+    pub fn new(nsid: Nsid) -> NameEntity {
+        NameEntity {
+            nsid,
+            name: None,
+            relays: vec![],
+        }
+    }
+
+    pub fn to_bech32(&self) -> Result<String, super::UtilError> {
+        let mut tlv = vec![];
+        let mut push = |t: u8, value: &[u8]| -> Result<(), super::UtilError> {
+            let len = u8::try_from(value.len())
+                .or(Err(super::UtilError::InvalidEntity("value is too long")))?;
+            tlv.push(t);
+            tlv.push(len);
+            tlv.extend_from_slice(value);
+            Ok(())
+        };
+        push(TLV_NSID, self.nsid.as_ref())?;
+        for relay in &self.relays {
+            push(TLV_RELAY, relay.as_bytes())?;
+        }
+        if let Some(name) = &self.name {
+            push(TLV_NAME, name.as_bytes())?;
+        }
+        Ok(bech32::encode(
+            NAME_ENTITY_HRP,
+            tlv.to_base32(),
+            Variant::Bech32,
+        )?)
+    }
+
+    pub fn from_bech32(s: &str) -> Result<NameEntity, super::UtilError> {
+        let (hrp, data, variant) = bech32::decode(s)?;
+        if hrp != NAME_ENTITY_HRP || variant != Variant::Bech32 {
+            return Err(super::UtilError::InvalidEntity("not a name entity"));
+        }
+        let bytes = Vec::<u8>::from_base32(&data)?;
+
+        let mut nsid = None;
+        let mut name = None;
+        let mut relays = vec![];
+        let mut rest = bytes.as_slice();
+        while let [t, len, tail @ ..] = rest {
+            let len = *len as usize;
+            if tail.len() < len {
+                return Err(super::UtilError::InvalidEntity("truncated record"));
+            }
+            let (value, tail) = tail.split_at(len);
+            match *t {
+                TLV_NSID => nsid = Some(Nsid::from_slice(value)?),
+                TLV_RELAY => relays.push(String::from_utf8(value.to_vec())?),
+                TLV_NAME => name = Some(String::from_utf8(value.to_vec())?),
+                _ => {}
+            }
+            rest = tail;
+        }
+        if !rest.is_empty() {
+            return Err(super::UtilError::InvalidEntity("truncated record"));
+        }
+
+        Ok(NameEntity {
+            nsid: nsid.ok_or(super::UtilError::InvalidEntity("missing nsid"))?,
+            name,
+            relays,
+        })
+    }
+}
+
+/// Parses an nsid in any of the forms users may paste: hex, bech32 `nsid1…` or a `nname1…` entity.
+impl FromStr for NameEntity {
+    type Err = super::UtilError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let lower = s.to_lowercase();
+        if lower.starts_with(NAME_ENTITY_HRP) {
+            NameEntity::from_bech32(s)
+        } else if lower.starts_with(super::NSID_HRP) {
+            Ok(NameEntity::new(Nsid::from_bech32(s)?))
+        } else {
+            Ok(NameEntity::new(s.parse()?))
+        }
+    }
+}
+
+impl Display for NameEntity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = self.to_bech32().or(Err(std::fmt::Error))?;
+        f.write_str(&s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NSID: &str = "28d63a9a61c6c5ce6be37a830105c92cf7a8f365";
+
+    #[test]
+    fn test_nsid_bech32() {
+        let nsid: Nsid = NSID.parse().unwrap();
+        let encoded = nsid.to_bech32();
+        assert!(encoded.starts_with("nsid1"));
+        assert_eq!(Nsid::from_bech32(&encoded).unwrap(), nsid);
+        assert_eq!(NameEntity::from_str(&encoded).unwrap().nsid, nsid);
+        assert_eq!(NameEntity::from_str(NSID).unwrap().nsid, nsid);
+
+        let mut typo = encoded.clone().into_bytes();
+        typo[10] = if typo[10] == b'q' { b'p' } else { b'q' };
+        assert!(Nsid::from_bech32(std::str::from_utf8(&typo).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_name_entity() {
+        let entity = NameEntity {
+            nsid: NSID.parse().unwrap(),
+            name: Some("smith".into()),
+            relays: vec!["wss://relay.example.com".into(), "wss://nos.lol".into()],
+        };
+        let encoded = entity.to_bech32().unwrap();
+        assert!(encoded.starts_with("nname1"));
+        assert_eq!(NameEntity::from_str(&encoded).unwrap(), entity);
+        assert!(Nsid::from_bech32(&encoded).is_err());
+    }
+}
@@ -4,7 +4,7 @@ use itertools::Itertools;
 use nostr_sdk::Event;
 use secp256k1::XOnlyPublicKey;
 
-use super::Nsid;
+use super::{Nsid, Records};
 
 #[derive(thiserror::Error, Debug)]
 #[error("event extractor")]
@@ -13,10 +13,12 @@ pub struct ExtractorError;
 pub trait EventExtractor {
     fn extract_children(&self, name: &str)
         -> Result<Vec<(String, XOnlyPublicKey)>, ExtractorError>;
-    fn extract_records(&self) -> Result<HashMap<String, String>, ExtractorError>;
+    fn extract_records(&self) -> Result<Records, ExtractorError>;
     fn extract_name(&self) -> Result<String, ExtractorError>;
     fn extract_nsid(&self) -> Result<Nsid, ExtractorError>;
     fn extract_prev_nsid(&self) -> Result<Option<Nsid>, ExtractorError>;
+    fn extract_owner(&self) -> Result<Option<XOnlyPublicKey>, ExtractorError>;
+    fn extract_delegate(&self) -> Result<Option<XOnlyPublicKey>, ExtractorError>;
 }
 
 impl EventExtractor for Event {
@@ -33,7 +35,7 @@ impl EventExtractor for Event {
         Ok(children)
     }
 
-    fn extract_records(&self) -> Result<HashMap<String, String>, ExtractorError> {
+    fn extract_records(&self) -> Result<Records, ExtractorError> {
         serde_json::from_str(&self.content).or(Err(ExtractorError))
     }
 
@@ -82,4 +84,34 @@ impl EventExtractor for Event {
             .and_then(|s| s.parse::<Nsid>().ok());
         Ok(nsid)
     }
+
+    fn extract_owner(&self) -> Result<Option<XOnlyPublicKey>, ExtractorError> {
+        self.tags
+            .iter()
+            .find_map(|t| match t {
+                nostr_sdk::Tag::Generic(nostr_sdk::prelude::TagKind::Custom(tn), values)
+                    if tn == "owner" =>
+                {
+                    Some(values.first().cloned().unwrap_or_default())
+                }
+                _ => None,
+            })
+            .map(|pk| pk.parse().or(Err(ExtractorError)))
+            .transpose()
+    }
+
+    fn extract_delegate(&self) -> Result<Option<XOnlyPublicKey>, ExtractorError> {
+        self.tags
+            .iter()
+            .find_map(|t| match t {
+                nostr_sdk::Tag::Generic(nostr_sdk::prelude::TagKind::Custom(tn), values)
+                    if tn == "delegate" =>
+                {
+                    Some(values.first().cloned().unwrap_or_default())
+                }
+                _ => None,
+            })
+            .map(|pk| pk.parse().or(Err(ExtractorError)))
+            .transpose()
+    }
 }
@@ -1,16 +1,23 @@
 use std::{fmt::Display, str::FromStr};
 
-use nostr_sdk::{EventBuilder, UnsignedEvent};
 use secp256k1::{schnorr::Signature, XOnlyPublicKey};
 
 use crate::Name;
 
-use super::{CreateBuilder, Hash160, Nsid, NsidBuilder, TransferBuilder};
+use super::{CreateBuilder, Hash160, Nsid, NsidBuilder, TransferBuilder, TransferSignature};
 
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_with::SerializeDisplay, serde_with::DeserializeFromStr)
+)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum NomenKind {
     Create,
     Transfer,
+    Commit,
+    Reveal,
+    Renew,
+    Release,
 }
 
 impl From<NomenKind> for u8 {
@@ -18,6 +25,10 @@ impl From<NomenKind> for u8 {
         match value {
             NomenKind::Create => 0x00,
             NomenKind::Transfer => 0x01,
+            NomenKind::Commit => 0x03,
+            NomenKind::Reveal => 0x04,
+            NomenKind::Renew => 0x05,
+            NomenKind::Release => 0x06,
         }
     }
 }
@@ -27,6 +38,10 @@ impl Display for NomenKind {
         let s = match self {
             NomenKind::Create => "create",
             NomenKind::Transfer => "transfer",
+            NomenKind::Commit => "commit",
+            NomenKind::Reveal => "reveal",
+            NomenKind::Renew => "renew",
+            NomenKind::Release => "release",
         };
         write!(f, "{s}")
     }
@@ -39,13 +54,21 @@ impl FromStr for NomenKind {
         match s {
             "create" => Ok(NomenKind::Create),
             "transfer" => Ok(NomenKind::Transfer),
+            "commit" => Ok(NomenKind::Commit),
+            "reveal" => Ok(NomenKind::Reveal),
+            "renew" => Ok(NomenKind::Renew),
+            "release" => Ok(NomenKind::Release),
             _ => Err(super::UtilError::NomenKind(s.to_string())),
         }
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct CreateV0 {
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
     pub fingerprint: [u8; 5],
     pub nsid: Nsid,
 }
@@ -56,6 +79,9 @@ impl CreateV0 {
     }
 
     pub fn parse_create(value: &[u8]) -> Result<CreateV0, super::UtilError> {
+        if value.len() < 5 {
+            return Err(super::UtilError::UnexpectedNomenTxType);
+        }
         Ok(CreateV0::create(
             value[..5].try_into()?,
             value[5..].try_into()?,
@@ -88,8 +114,11 @@ impl TryFrom<&[u8]> for CreateV0 {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CreateV1 {
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
     pub pubkey: XOnlyPublicKey,
     pub name: String,
 }
@@ -112,8 +141,11 @@ impl CreateV1 {
     }
 
     pub fn parse_create(value: &[u8]) -> Result<CreateV1, super::UtilError> {
+        if value.len() < 32 {
+            return Err(super::UtilError::UnexpectedNomenTxType);
+        }
         let name = String::from_utf8(value[32..].to_vec())?;
-        let _ = Name::from_str(&name)?;
+        let _ = Name::canonical(&name)?;
         Ok(CreateV1 {
             pubkey: XOnlyPublicKey::from_slice(&value[..32])?,
             name,
@@ -146,8 +178,11 @@ impl TryFrom<&[u8]> for CreateV1 {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TransferV1 {
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
     pub pubkey: XOnlyPublicKey,
     pub name: String,
 }
@@ -160,8 +195,11 @@ impl TransferV1 {
     }
 
     pub fn parse_create(value: &[u8]) -> Result<TransferV1, super::UtilError> {
+        if value.len() < 32 {
+            return Err(super::UtilError::UnexpectedNomenTxType);
+        }
         let name = String::from_utf8(value[32..].to_vec())?;
-        let _ = Name::from_str(&name)?;
+        let _ = Name::canonical(&name)?;
         Ok(TransferV1 {
             pubkey: XOnlyPublicKey::from_slice(&value[..32])?,
             name,
@@ -204,8 +242,223 @@ impl TryFrom<&[u8]> for TransferV1 {
     }
 }
 
+/// The first half of a commit–reveal registration. It only carries the commitment, so watching the mempool reveals
+/// nothing about the name being claimed.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CommitV1 {
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub commitment: [u8; 32],
+}
+impl CommitV1 {
+    pub fn new(commitment: [u8; 32]) -> CommitV1 {
+        CommitV1 { commitment }
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        b"NOM\x01"
+            .iter()
+            .chain([NomenKind::Commit.into()].iter())
+            .chain(self.commitment.iter())
+            .copied()
+            .collect()
+    }
+}
+
+impl TryFrom<&[u8]> for CommitV1 {
+    type Error = super::UtilError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if !value.starts_with(b"NOM\x01") {
+            return Err(super::UtilError::UnexpectedNomenTxType);
+        }
+        let value = &value[4..];
+
+        match value.first() {
+            Some(&kind) if kind == u8::from(NomenKind::Commit) => {
+                Ok(CommitV1::new(value[1..].try_into()?))
+            }
+            _ => Err(super::UtilError::UnexpectedNomenTxType),
+        }
+    }
+}
+
+/// The second half of a commit–reveal registration, opening an earlier [`CommitV1`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RevealV1 {
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub pubkey: XOnlyPublicKey,
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub salt: [u8; 8],
+    pub name: String,
+}
+impl RevealV1 {
+    pub fn fingerprint(&self) -> [u8; 5] {
+        Hash160::default()
+            .chain_update(self.name.as_bytes())
+            .fingerprint()
+    }
+
+    pub fn nsid(&self) -> Nsid {
+        NsidBuilder::new(&self.name, &self.pubkey).finalize()
+    }
+
+    /// The commitment this reveal opens.
+    pub fn commitment(&self) -> Result<[u8; 32], super::UtilError> {
+        super::commitment(&self.name, &self.pubkey, &self.salt)
+    }
+
+    pub fn parse_reveal(value: &[u8]) -> Result<RevealV1, super::UtilError> {
+        if value.len() < 40 {
+            return Err(super::UtilError::UnexpectedNomenTxType);
+        }
+        let name = String::from_utf8(value[40..].to_vec())?;
+        let _ = Name::canonical(&name)?;
+        Ok(RevealV1 {
+            pubkey: XOnlyPublicKey::from_slice(&value[..32])?,
+            salt: value[32..40].try_into()?,
+            name,
+        })
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        b"NOM\x01"
+            .iter()
+            .chain([NomenKind::Reveal.into()].iter())
+            .chain(self.pubkey.serialize().iter())
+            .chain(self.salt.iter())
+            .chain(self.name.as_bytes().iter())
+            .copied()
+            .collect()
+    }
+}
+
+impl TryFrom<&[u8]> for RevealV1 {
+    type Error = super::UtilError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if !value.starts_with(b"NOM\x01") {
+            return Err(super::UtilError::UnexpectedNomenTxType);
+        }
+        let value = &value[4..];
+
+        match value.first() {
+            Some(&kind) if kind == u8::from(NomenKind::Reveal) => {
+                Ok(RevealV1::parse_reveal(&value[1..])?)
+            }
+            _ => Err(super::UtilError::UnexpectedNomenTxType),
+        }
+    }
+}
+
+/// Renews the name with `fingerprint`, see [`RenewBuilder`](super::RenewBuilder) for the signed message.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenewV1 {
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub fingerprint: [u8; 5],
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub signature: Signature,
+}
+impl RenewV1 {
+    pub fn parse_renew(value: &[u8]) -> Result<RenewV1, super::UtilError> {
+        if value.len() < 5 {
+            return Err(super::UtilError::UnexpectedNomenTxType);
+        }
+        Ok(RenewV1 {
+            fingerprint: value[..5].try_into()?,
+            signature: Signature::from_slice(&value[5..])?,
+        })
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        b"NOM\x01"
+            .iter()
+            .chain([NomenKind::Renew.into()].iter())
+            .chain(self.fingerprint.iter())
+            .chain(self.signature.as_ref().iter())
+            .copied()
+            .collect()
+    }
+}
+
+impl TryFrom<&[u8]> for RenewV1 {
+    type Error = super::UtilError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if !value.starts_with(b"NOM\x01") {
+            return Err(super::UtilError::UnexpectedNomenTxType);
+        }
+        let value = &value[4..];
+
+        match value.first() {
+            Some(0x05) => Ok(RenewV1::parse_renew(&value[1..])?),
+            _ => Err(super::UtilError::UnexpectedNomenTxType),
+        }
+    }
+}
+
+/// Releases the name with `fingerprint`, see [`ReleaseBuilder`](super::ReleaseBuilder) for the signed message.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReleaseV1 {
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub fingerprint: [u8; 5],
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub signature: Signature,
+}
+impl ReleaseV1 {
+    pub fn parse_release(value: &[u8]) -> Result<ReleaseV1, super::UtilError> {
+        if value.len() < 5 {
+            return Err(super::UtilError::UnexpectedNomenTxType);
+        }
+        Ok(ReleaseV1 {
+            fingerprint: value[..5].try_into()?,
+            signature: Signature::from_slice(&value[5..])?,
+        })
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        b"NOM\x01"
+            .iter()
+            .chain([NomenKind::Release.into()].iter())
+            .chain(self.fingerprint.iter())
+            .chain(self.signature.as_ref().iter())
+            .copied()
+            .collect()
+    }
+}
+
+impl TryFrom<&[u8]> for ReleaseV1 {
+    type Error = super::UtilError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if !value.starts_with(b"NOM\x01") {
+            return Err(super::UtilError::UnexpectedNomenTxType);
+        }
+        let value = &value[4..];
+
+        match value.first() {
+            Some(0x06) => Ok(ReleaseV1::parse_release(&value[1..])?),
+            _ => Err(super::UtilError::UnexpectedNomenTxType),
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SignatureV1 {
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
     pub signature: Signature,
 }
 impl SignatureV1 {
@@ -246,6 +499,104 @@ impl TryFrom<&[u8]> for SignatureV1 {
     }
 }
 
+/// Transfer signature over the v2 authorisation message, see [`TransferAuthorization`](super::TransferAuthorization).
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureV2 {
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub signature: Signature,
+}
+impl SignatureV2 {
+    pub fn new(signature: &Signature) -> SignatureV2 {
+        SignatureV2 {
+            signature: *signature,
+        }
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        b"NOM\x02\x02"
+            .iter()
+            .chain(self.signature.as_ref().iter())
+            .copied()
+            .collect()
+    }
+}
+
+impl TryFrom<&[u8]> for SignatureV2 {
+    type Error = super::UtilError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if !value.starts_with(b"NOM\x02") {
+            return Err(super::UtilError::UnexpectedNomenTxType);
+        }
+        let value = &value[4..];
+
+        match value.first() {
+            Some(0x02) => Ok(SignatureV2 {
+                signature: Signature::from_slice(&value[1..])?,
+            }),
+            _ => Err(super::UtilError::UnexpectedNomenTxType),
+        }
+    }
+}
+
+/// Any Nomen `OP_RETURN`, decoded. With the `serde` feature it is tagged with `type`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OpReturn {
+    CreateV0(CreateV0),
+    CreateV1(CreateV1),
+    Commit(CommitV1),
+    Reveal(RevealV1),
+    Transfer(TransferV1),
+    Renew(RenewV1),
+    Release(ReleaseV1),
+    Signature(TransferSignature),
+}
+
+impl OpReturn {
+    pub fn serialize(&self) -> Vec<u8> {
+        match self {
+            OpReturn::CreateV0(o) => o.serialize(),
+            OpReturn::CreateV1(o) => o.serialize(),
+            OpReturn::Commit(o) => o.serialize(),
+            OpReturn::Reveal(o) => o.serialize(),
+            OpReturn::Transfer(o) => o.serialize(),
+            OpReturn::Renew(o) => o.serialize(),
+            OpReturn::Release(o) => o.serialize(),
+            OpReturn::Signature(o) => o.serialize(),
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for OpReturn {
+    type Error = super::UtilError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let op_return = if let Ok(create) = CreateV0::try_from(value) {
+            OpReturn::CreateV0(create)
+        } else if let Ok(create) = CreateV1::try_from(value) {
+            OpReturn::CreateV1(create)
+        } else if let Ok(commit) = CommitV1::try_from(value) {
+            OpReturn::Commit(commit)
+        } else if let Ok(reveal) = RevealV1::try_from(value) {
+            OpReturn::Reveal(reveal)
+        } else if let Ok(transfer) = TransferV1::try_from(value) {
+            OpReturn::Transfer(transfer)
+        } else if let Ok(renew) = RenewV1::try_from(value) {
+            OpReturn::Renew(renew)
+        } else if let Ok(release) = ReleaseV1::try_from(value) {
+            OpReturn::Release(release)
+        } else {
+            OpReturn::Signature(TransferSignature::try_from(value)?)
+        };
+        Ok(op_return)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -309,6 +660,31 @@ mod tests {
         assert_eq!(t.unwrap().serialize(), or);
     }
 
+    #[test]
+    fn test_signature_versions_do_not_overlap() {
+        let v1 = hex::decode("4e4f4d0102489e4e3ab29408da53733473156040a25e5a84cbca788c2b7143f971ead84192ae8bd8e4890cfabb08dca693875c28a1949ae0d13f5c6b08617e4fdc022bc751").unwrap();
+        let v2 =
+            SignatureV2::new(&SignatureV1::try_from(v1.as_ref()).unwrap().signature).serialize();
+        assert!(SignatureV2::try_from(v1.as_ref()).is_err());
+        assert!(SignatureV1::try_from(v2.as_ref()).is_err());
+        assert_eq!(SignatureV2::try_from(v2.as_ref()).unwrap().serialize(), v2);
+    }
+
+    #[test]
+    fn test_nomen_kind() {
+        for (kind, byte) in [
+            (NomenKind::Create, 0x00),
+            (NomenKind::Transfer, 0x01),
+            (NomenKind::Commit, 0x03),
+            (NomenKind::Reveal, 0x04),
+            (NomenKind::Renew, 0x05),
+            (NomenKind::Release, 0x06),
+        ] {
+            assert_eq!(u8::from(kind), byte);
+            assert_eq!(kind.to_string().parse::<NomenKind>().unwrap(), kind);
+        }
+    }
+
     #[test]
     fn test_invalid_version() {
         let wrong_ver = b"NOM\x01\x00";
@@ -326,4 +702,26 @@ mod tests {
         let wrong_ver = b"NOM\x00\x10";
         assert!(CreateV0::try_from(wrong_ver.as_ref()).is_err())
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let or =
+            hex::decode("4e4f4d0000e5401df4b4273968a1e7be2ef0acbcae6f61d53e73101e2983").unwrap();
+        let c = CreateV0::try_from(or.as_ref()).unwrap();
+        let json = serde_json::to_value(c).unwrap();
+        assert_eq!(json["fingerprint"], "e5401df4b4");
+        assert_eq!(json["nsid"], "273968a1e7be2ef0acbcae6f61d53e73101e2983");
+        assert_eq!(serde_json::from_value::<CreateV0>(json).unwrap(), c);
+
+        assert_eq!(
+            serde_json::to_value(NomenKind::Transfer).unwrap(),
+            "transfer"
+        );
+
+        let or = OpReturn::try_from(or.as_ref()).unwrap();
+        let json = serde_json::to_value(&or).unwrap();
+        assert_eq!(json["type"], "create_v0");
+        assert_eq!(serde_json::from_value::<OpReturn>(json).unwrap(), or);
+    }
 }
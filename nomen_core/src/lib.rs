@@ -1,21 +1,51 @@
 #![allow(unused)]
 
+mod commit;
+mod confusable;
 mod create;
+mod entity;
+#[cfg(feature = "nostr")]
 mod extractor;
 mod hash160;
 mod kind;
 mod name;
 mod nsid;
 mod nsid_builder;
+#[cfg(feature = "nostr")]
+mod proof;
+#[cfg(feature = "nostr")]
+mod record_delegate;
+mod records;
+#[cfg(feature = "nostr")]
+mod records_builder;
+mod release;
+mod renew;
+#[cfg(feature = "schema")]
+mod schema;
 mod transfer;
 
+pub use commit::*;
+pub use confusable::*;
 pub use create::*;
+pub use entity::*;
+#[cfg(feature = "nostr")]
 pub use extractor::*;
 pub use hash160::*;
 pub use kind::*;
 pub use name::*;
 pub use nsid::*;
 pub use nsid_builder::*;
+#[cfg(feature = "nostr")]
+pub use proof::*;
+#[cfg(feature = "nostr")]
+pub use record_delegate::*;
+pub use records::*;
+#[cfg(feature = "nostr")]
+pub use records_builder::*;
+pub use release::*;
+pub use renew::*;
+#[cfg(feature = "schema")]
+pub use schema::*;
 pub use transfer::*;
 
 #[derive(thiserror::Error, Debug)]
@@ -32,14 +62,17 @@ pub enum UtilError {
     NomenKind(String),
     #[error("invalid Key=Value")]
     InvalidKeyVal(String),
+    #[cfg(feature = "nostr")]
     #[error("invalid event kind")]
     InvalidEventKind(nostr_sdk::Kind),
+    #[cfg(feature = "nostr")]
     #[error("nostr event signing error")]
     UnsignedEventError(#[from] nostr_sdk::event::unsigned::Error),
     #[error("slice conversion")]
     TryFromSliceError(#[from] std::array::TryFromSliceError),
     #[error("hex conversion")]
     HexDecode(#[from] hex::FromHexError),
+    #[cfg(feature = "nostr")]
     #[error("nostr key")]
     NostrKeyError(#[from] nostr_sdk::key::Error),
     #[error("regex")]
@@ -48,26 +81,55 @@ pub enum UtilError {
     Secp256k1Error(#[from] secp256k1::Error),
     #[error("string error")]
     StringError(#[from] std::string::FromUtf8Error),
+    #[cfg(feature = "nostr")]
     #[error(transparent)]
     ExtractorError(#[from] ExtractorError),
+    #[error("invalid proof: {0}")]
+    InvalidProof(&'static str),
+    #[error("bitcoin")]
+    BitcoinError(#[from] bitcoin::Error),
+    #[error(transparent)]
+    RecordError(#[from] RecordError),
+    #[error("keys do not match the name owner")]
+    InvalidSigner,
+    #[error("invalid signature")]
+    InvalidSignature,
+    #[error("name is too long to be revealed")]
+    RevealNameTooLong,
+    #[error("name is too long to be signed")]
+    SignedNameTooLong,
+    #[error("invalid record delegation: {0}")]
+    InvalidDelegation(&'static str),
+    #[error("bech32")]
+    Bech32Error(#[from] bitcoin::bech32::Error),
+    #[error("invalid entity: {0}")]
+    InvalidEntity(&'static str),
 }
 
 pub enum NameKind {
     Name = 38300,
+    RelayIndex = 38301,
+    Children = 38302,
+    RecordDelegate = 38303,
 }
 
+#[cfg(feature = "nostr")]
 impl From<NameKind> for nostr_sdk::Kind {
     fn from(value: NameKind) -> Self {
         nostr_sdk::Kind::ParameterizedReplaceable(value as u16)
     }
 }
 
+#[cfg(feature = "nostr")]
 impl TryFrom<nostr_sdk::Kind> for NameKind {
     type Error = UtilError;
 
     fn try_from(value: nostr_sdk::Kind) -> Result<Self, Self::Error> {
         let nk = match value {
             nostr_sdk::Kind::ParameterizedReplaceable(38300) => NameKind::Name,
+            nostr_sdk::Kind::ParameterizedReplaceable(38301) => NameKind::RelayIndex,
+            nostr_sdk::Kind::ParameterizedReplaceable(38302) => NameKind::Children,
+            nostr_sdk::Kind::ParameterizedReplaceable(38303) => NameKind::RecordDelegate,
             _ => return Err(UtilError::InvalidEventKind(value)),
         };
         Ok(nk)
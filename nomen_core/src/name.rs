@@ -3,13 +3,35 @@ use std::str::FromStr;
 use derive_more::{AsRef, Display, Into};
 use regex::Regex;
 
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_with::SerializeDisplay, serde_with::DeserializeFromStr)
+)]
 #[derive(Display, AsRef, Debug, Clone, PartialEq, Eq, Default)]
 pub struct Name(String);
 
+/// Parses a top-level name from user input. Unicode names are normalised with the UTS #46 mapping (NFKC and case
+/// folding) and converted to their punycode `xn--` form, which is the canonical form used on-chain and for
+/// fingerprints and nsids.
 impl FromStr for Name {
     type Err = super::UtilError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(name) = Name::canonical(s) {
+            return Ok(name);
+        }
+        let ascii = idna::Config::default()
+            .use_std3_ascii_rules(true)
+            .check_hyphens(true)
+            .to_ascii(s)
+            .or(Err(super::UtilError::NameValidation))?;
+        Name::canonical(&ascii)
+    }
+}
+
+impl Name {
+    /// Parses a top-level name which must already be in canonical form, as names found on-chain and in events are.
+    pub fn canonical(s: &str) -> Result<Self, super::UtilError> {
         let r = Regex::new(r#"\A[0-9a-z\-]{3,43}\z"#)?;
         if r.is_match(s) {
             return Ok(Name(s.into()));
@@ -17,6 +39,38 @@ impl FromStr for Name {
 
         Err(super::UtilError::NameValidation)
     }
+
+    /// Parses a top-level name or a child name like `team.ourorg`. Every label of a child name must be a valid
+    /// canonical top-level name. Only top-level names can be created on-chain, use [`FromStr`] for those.
+    pub fn parse_hierarchical(s: &str) -> Result<Self, super::UtilError> {
+        for label in s.split('.') {
+            Name::canonical(label)?;
+        }
+        Ok(Name(s.into()))
+    }
+
+    /// The name for display, with punycode labels decoded to Unicode. Labels which don't decode are left as they are.
+    pub fn to_unicode(&self) -> String {
+        self.0
+            .split('.')
+            .map(|label| match idna::domain_to_unicode(label) {
+                (unicode, Ok(())) => unicode,
+                _ => label.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    /// The parent of a child name, or `None` for a top-level name.
+    pub fn parent(&self) -> Option<&str> {
+        self.0.split_once('.').map(|(_, parent)| parent)
+    }
+}
+
+/// The single byte length prefix of `name` in the commitment and the renewal, release and v2 transfer messages. Names
+/// over 255 bytes can't be committed to or signed.
+pub(crate) fn length_prefix(name: &str) -> Result<u8, super::UtilError> {
+    u8::try_from(name.len()).or(Err(super::UtilError::SignedNameTooLong))
 }
 
 #[cfg(test)]
@@ -48,4 +102,33 @@ mod tests {
         .all(|r| r.is_err());
         assert!(r);
     }
+
+    #[test]
+    fn test_hierarchical() {
+        let name = Name::parse_hierarchical("dev.team.ourorg").unwrap();
+        assert_eq!(name.parent(), Some("team.ourorg"));
+        assert_eq!(Name::parse_hierarchical("ourorg").unwrap().parent(), None);
+        assert!(Name::from_str("team.ourorg").is_err());
+        assert!(Name::parse_hierarchical("team..ourorg").is_err());
+        assert!(Name::parse_hierarchical("x.ourorg").is_err());
+    }
+
+    #[test]
+    fn test_unicode() {
+        let name = Name::from_str("Bücher").unwrap();
+        assert_eq!(name.as_ref(), "xn--bcher-kva");
+        assert_eq!(name.to_unicode(), "bücher");
+        assert_eq!(Name::from_str("ＢＵＣＨＥＲ").unwrap().as_ref(), "bucher");
+        assert_eq!(Name::from_str("xn--bcher-kva").unwrap(), name);
+        assert_eq!(Name::from_str("日本語").unwrap().to_unicode(), "日本語");
+        assert!(Name::canonical("Bücher").is_err());
+        assert!(Name::from_str("bü.cher").is_err());
+        assert!(Name::from_str(&"bücher".repeat(8)).is_err());
+        assert_eq!(
+            Name::parse_hierarchical("team.xn--bcher-kva")
+                .unwrap()
+                .to_unicode(),
+            "team.bücher"
+        );
+    }
 }
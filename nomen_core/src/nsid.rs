@@ -4,22 +4,47 @@ use std::{
     str::FromStr,
 };
 
-use bitcoin::secp256k1::XOnlyPublicKey;
+use bitcoin::{
+    bech32::{self, FromBase32, ToBase32, Variant},
+    secp256k1::XOnlyPublicKey,
+};
 use derive_more::{AsMut, AsRef, Deref, DerefMut, From};
+#[cfg(feature = "nostr")]
 use nostr_sdk::Event;
 
-use super::{EventExtractor, NameKind, NsidBuilder};
+#[cfg(feature = "nostr")]
+use super::EventExtractor;
+use super::{NameKind, NsidBuilder};
 
-#[derive(
-    Clone, Copy, Deref, DerefMut, AsRef, AsMut, From, Eq, PartialEq, serde_with::DeserializeFromStr,
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_with::SerializeDisplay, serde_with::DeserializeFromStr)
 )]
+#[derive(Clone, Copy, Deref, DerefMut, AsRef, AsMut, From, Eq, PartialEq)]
 pub struct Nsid([u8; 20]);
 
+/// Human readable part of the bech32 encoding of an nsid.
+pub const NSID_HRP: &str = "nsid";
+
 impl Nsid {
     #[allow(dead_code)]
     pub fn from_slice(bytes: &[u8]) -> Result<Nsid, super::UtilError> {
         Ok(Nsid(bytes.try_into()?))
     }
+
+    /// The bech32 `nsid1…` encoding, which is checksummed for copy and paste. [`Display`] and [`FromStr`] use hex,
+    /// which is the form used in events.
+    pub fn to_bech32(&self) -> String {
+        bech32::encode(NSID_HRP, self.0.to_base32(), Variant::Bech32).expect("valid hrp")
+    }
+
+    pub fn from_bech32(s: &str) -> Result<Nsid, super::UtilError> {
+        let (hrp, data, variant) = bech32::decode(s)?;
+        if hrp != NSID_HRP || variant != Variant::Bech32 {
+            return Err(super::UtilError::InvalidEntity("not an nsid"));
+        }
+        Nsid::from_slice(&Vec::<u8>::from_base32(&data)?)
+    }
 }
 
 impl TryFrom<&[u8]> for Nsid {
@@ -30,6 +55,7 @@ impl TryFrom<&[u8]> for Nsid {
     }
 }
 
+#[cfg(feature = "nostr")]
 impl TryFrom<Event> for Nsid {
     type Error = super::UtilError;
 
@@ -37,7 +63,10 @@ impl TryFrom<Event> for Nsid {
         let nk: NameKind = event.kind.try_into()?;
         let name = event.extract_name()?;
         let builder = match nk {
-            NameKind::Name => NsidBuilder::new(&name, &event.pubkey),
+            NameKind::Name | NameKind::Children | NameKind::RecordDelegate => {
+                NsidBuilder::new(&name, &event.pubkey)
+            }
+            NameKind::RelayIndex => return Err(super::UtilError::InvalidEventKind(event.kind)),
         };
         Ok(builder.finalize())
     }
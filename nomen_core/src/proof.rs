@@ -0,0 +1,462 @@
+use bitcoin::{
+    block::Header,
+    consensus::encode::{deserialize, serialize_hex},
+    hash_types::TxMerkleNode,
+    hashes::{Hash, HashEngine},
+    BlockHash, Transaction, Txid,
+};
+use nostr_sdk::Event;
+use secp256k1::XOnlyPublicKey;
+
+use crate::{
+    CreateV0, CreateV1, EventExtractor, Hash160, NsidBuilder, RecordDelegation, RevealV1,
+    TransferSignature, TransferV1,
+};
+
+/// SPV proof that a transaction was included in a block: the raw transaction, the header of the block and
+/// the merkle branch connecting the two.
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxProof {
+    #[cfg_attr(feature = "serde", serde(with = "consensus_hex"))]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub tx: Transaction,
+    #[cfg_attr(feature = "serde", serde(with = "consensus_hex"))]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub header: Header,
+    #[cfg_attr(feature = "schema", schemars(with = "Vec<String>"))]
+    pub merkle_branch: Vec<TxMerkleNode>,
+    pub index: usize,
+}
+
+impl TxProof {
+    /// Builds a proof for the transaction with `txid` in `block`.
+    pub fn from_block(block: &bitcoin::Block, txid: &Txid) -> Result<TxProof, super::UtilError> {
+        let index = block
+            .txdata
+            .iter()
+            .position(|tx| tx.txid() == *txid)
+            .ok_or(super::UtilError::InvalidProof("transaction not in block"))?;
+        let txids = block
+            .txdata
+            .iter()
+            .map(Transaction::txid)
+            .collect::<Vec<_>>();
+        Ok(TxProof {
+            tx: block.txdata[index].clone(),
+            header: block.header,
+            merkle_branch: merkle_branch(&txids, index),
+            index,
+        })
+    }
+
+    pub fn block_hash(&self) -> BlockHash {
+        self.header.block_hash()
+    }
+
+    /// Merkle root computed by hashing the transaction up through the merkle branch.
+    pub fn merkle_root(&self) -> TxMerkleNode {
+        let mut node = TxMerkleNode::from_raw_hash(self.tx.txid().to_raw_hash());
+        let mut index = self.index;
+        for sibling in &self.merkle_branch {
+            node = if index & 1 == 0 {
+                merkle_parent(&node, sibling)
+            } else {
+                merkle_parent(sibling, &node)
+            };
+            index >>= 1;
+        }
+        node
+    }
+
+    /// Checks that the transaction commits to the block header, and that the header has valid proof of work
+    /// for its own target. Whether that header is part of the best chain is up to the caller.
+    pub fn verify(&self) -> Result<(), super::UtilError> {
+        if self.merkle_root() != self.header.merkle_root {
+            return Err(super::UtilError::InvalidProof("merkle root mismatch"));
+        }
+        self.header.validate_pow(self.header.target())?;
+        Ok(())
+    }
+
+    /// `NOM` data found in the OP_RETURN outputs of the transaction.
+    pub fn op_returns(&self) -> impl Iterator<Item = &[u8]> {
+        self.tx
+            .output
+            .iter()
+            .filter(|o| o.script_pubkey.is_op_return() && o.script_pubkey.len() >= 3)
+            .map(|o| &o.script_pubkey.as_bytes()[2..])
+            .filter(|b| b.starts_with(b"NOM"))
+    }
+}
+
+/// Transfer transaction and the signature transaction that authorized it.
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransferProof {
+    pub transfer: TxProof,
+    pub signature: TxProof,
+}
+
+/// Everything a light client needs to check the ownership of a name against its own header chain.
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameProof {
+    pub create: TxProof,
+    pub upgrade: Option<TxProof>,
+    pub transfers: Vec<TransferProof>,
+    #[cfg_attr(feature = "schema", schemars(with = "Option<serde_json::Value>"))]
+    pub event: Option<Event>,
+    /// The owner's record delegation, when `event` is signed by a record delegate.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<serde_json::Value>"))]
+    pub delegation: Option<Event>,
+}
+
+impl NameProof {
+    /// Blocks which must be found in the client's best chain for the proof to hold.
+    pub fn block_hashes(&self) -> Vec<BlockHash> {
+        std::iter::once(&self.create)
+            .chain(self.upgrade.iter())
+            .chain(
+                self.transfers
+                    .iter()
+                    .flat_map(|t| [&t.transfer, &t.signature]),
+            )
+            .map(TxProof::block_hash)
+            .collect()
+    }
+
+    /// Verifies every transaction proof and replays the ownership history of `name`, returning the proven owner.
+    pub fn verify(&self, name: &str) -> Result<XOnlyPublicKey, super::UtilError> {
+        let mut owner = self.verify_create(name)?;
+
+        for tp in &self.transfers {
+            tp.transfer.verify()?;
+            tp.signature.verify()?;
+            let new_owner = tp
+                .transfer
+                .op_returns()
+                .filter_map(|b| TransferV1::try_from(b).ok())
+                .find(|t| t.name == name)
+                .ok_or(super::UtilError::InvalidProof("transfer not found"))?
+                .pubkey;
+            let transfer_txid = tp.transfer.tx.txid();
+            // A signature only completes a transfer in its own transaction or in one it spends.
+            if tp.signature.tx.txid() != transfer_txid
+                && !tp
+                    .signature
+                    .tx
+                    .input
+                    .iter()
+                    .any(|i| i.previous_output.txid == transfer_txid)
+            {
+                return Err(super::UtilError::InvalidProof(
+                    "transfer signature not bound to transfer",
+                ));
+            }
+            tp.signature
+                .op_returns()
+                .filter_map(|b| TransferSignature::try_from(b).ok())
+                .find(|s| s.verify(name, &new_owner, &owner, &transfer_txid).is_ok())
+                .ok_or(super::UtilError::InvalidProof(
+                    "transfer signature not found",
+                ))?;
+            owner = new_owner;
+        }
+
+        if let Some(event) = &self.event {
+            event.verify().or(Err(super::UtilError::InvalidProof(
+                "invalid event signature",
+            )))?;
+            if event.extract_name()? != name {
+                return Err(super::UtilError::InvalidProof("event does not match owner"));
+            }
+            if event.pubkey != owner {
+                let delegation = self
+                    .delegation
+                    .as_ref()
+                    .ok_or(super::UtilError::InvalidProof("event does not match owner"))?;
+                let delegation = RecordDelegation::try_from(delegation)?;
+                if delegation.owner != owner {
+                    return Err(super::UtilError::InvalidProof(
+                        "delegation does not match owner",
+                    ));
+                }
+                delegation.authorizes(event)?;
+            }
+        }
+
+        Ok(owner)
+    }
+
+    fn verify_create(&self, name: &str) -> Result<XOnlyPublicKey, super::UtilError> {
+        self.create.verify()?;
+        if let Some(create) = self
+            .create
+            .op_returns()
+            .filter_map(|b| CreateV1::try_from(b).ok())
+            .find(|c| c.name == name)
+        {
+            return Ok(create.pubkey);
+        }
+        if let Some(reveal) = self
+            .create
+            .op_returns()
+            .filter_map(|b| RevealV1::try_from(b).ok())
+            .find(|r| r.name == name)
+        {
+            return Ok(reveal.pubkey);
+        }
+
+        // A v0 name only commits to its owner through the nsid, so the owner comes from the upgrade or record event.
+        let fingerprint = Hash160::default()
+            .chain_update(name.as_bytes())
+            .fingerprint();
+        let create = self
+            .create
+            .op_returns()
+            .filter_map(|b| CreateV0::try_from(b).ok())
+            .find(|c| c.fingerprint == fingerprint)
+            .ok_or(super::UtilError::InvalidProof("create not found"))?;
+        let owner = match (&self.upgrade, &self.event) {
+            (Some(upgrade), _) => {
+                upgrade.verify()?;
+                upgrade
+                    .op_returns()
+                    .filter_map(|b| CreateV1::try_from(b).ok())
+                    .find(|c| c.name == name)
+                    .ok_or(super::UtilError::InvalidProof("upgrade not found"))?
+                    .pubkey
+            }
+            (None, Some(event)) => self.delegation.as_ref().map_or(event.pubkey, |d| d.pubkey),
+            (None, None) => return Err(super::UtilError::InvalidProof("v0 owner unknown")),
+        };
+        if NsidBuilder::new(name, &owner).finalize() != create.nsid {
+            return Err(super::UtilError::InvalidProof("v0 nsid mismatch"));
+        }
+        Ok(owner)
+    }
+}
+
+fn merkle_parent(left: &TxMerkleNode, right: &TxMerkleNode) -> TxMerkleNode {
+    let mut engine = TxMerkleNode::engine();
+    engine.input(left.as_byte_array());
+    engine.input(right.as_byte_array());
+    TxMerkleNode::from_engine(engine)
+}
+
+/// Sibling hashes, from the leaves up, needed to connect the transaction at `index` to the merkle root.
+pub fn merkle_branch(txids: &[Txid], mut index: usize) -> Vec<TxMerkleNode> {
+    let mut level = txids
+        .iter()
+        .map(|t| TxMerkleNode::from_raw_hash(t.to_raw_hash()))
+        .collect::<Vec<_>>();
+    let mut branch = Vec::new();
+
+    while level.len() > 1 {
+        if level.len() % 2 == 1 {
+            level.push(*level.last().expect("level is not empty"));
+        }
+        branch.push(level[index ^ 1]);
+        level = level
+            .chunks(2)
+            .map(|pair| merkle_parent(&pair[0], &pair[1]))
+            .collect();
+        index >>= 1;
+    }
+
+    branch
+}
+
+#[cfg(feature = "serde")]
+mod consensus_hex {
+    use bitcoin::consensus::{Decodable, Encodable};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T: Encodable, S: Serializer>(value: &T, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&super::serialize_hex(value))
+    }
+
+    pub fn deserialize<'de, T: Decodable, D: Deserializer<'de>>(d: D) -> Result<T, D::Error> {
+        let s = String::deserialize(d)?;
+        let bytes = hex::decode(s).map_err(serde::de::Error::custom)?;
+        super::deserialize(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        absolute::LockTime, block::Version, script::PushBytesBuf, CompactTarget, OutPoint,
+        ScriptBuf, TxIn, TxOut,
+    };
+    use nostr_sdk::Keys;
+
+    use crate::{CreateBuilder, TransferAuthorization, TransferBuilder};
+
+    use super::*;
+
+    fn op_return_tx(data: &[u8]) -> Transaction {
+        let mut pb = PushBytesBuf::new();
+        pb.extend_from_slice(data).unwrap();
+        Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: vec![TxOut {
+                value: 0,
+                script_pubkey: ScriptBuf::new_op_return(&pb),
+            }],
+        }
+    }
+
+    fn spending(mut tx: Transaction, spent: &Transaction) -> Transaction {
+        tx.input.push(TxIn {
+            previous_output: OutPoint::new(spent.txid(), 0),
+            ..Default::default()
+        });
+        tx
+    }
+
+    fn mine(txdata: Vec<Transaction>) -> bitcoin::Block {
+        let mut block = bitcoin::Block {
+            header: Header {
+                version: Version::TWO,
+                prev_blockhash: BlockHash::all_zeros(),
+                merkle_root: TxMerkleNode::all_zeros(),
+                time: 0,
+                bits: CompactTarget::from_consensus(0x207f_ffff),
+                nonce: 0,
+            },
+            txdata,
+        };
+        block.header.merkle_root = block.compute_merkle_root().unwrap();
+        while block.header.validate_pow(block.header.target()).is_err() {
+            block.header.nonce += 1;
+        }
+        block
+    }
+
+    #[test]
+    fn test_merkle_branch() {
+        for count in 1..8u8 {
+            let block = mine((0..count).map(|i| op_return_tx(&[i])).collect());
+            for tx in &block.txdata {
+                let proof = TxProof::from_block(&block, &tx.txid()).unwrap();
+                assert!(proof.verify().is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn test_tampered_branch() {
+        let block = mine((0..4u8).map(|i| op_return_tx(&[i])).collect());
+        let mut proof = TxProof::from_block(&block, &block.txdata[2].txid()).unwrap();
+        proof.index = 3;
+        assert!(proof.verify().is_err());
+    }
+
+    #[test]
+    fn test_name_proof_with_transfer() {
+        let old = Keys::generate();
+        let new = Keys::generate();
+        let create =
+            op_return_tx(&CreateBuilder::new(&old.public_key(), "hello-world").v1_op_return());
+        let tb = TransferBuilder {
+            new_pubkey: &new.public_key(),
+            name: "hello-world",
+        };
+        let transfer = op_return_tx(&tb.transfer_op_return());
+        let unbound = op_return_tx(&tb.signature_op_return(old).unwrap());
+        let signature = spending(unbound.clone(), &transfer);
+        let block = mine(vec![
+            op_return_tx(b"coinbase"),
+            create,
+            transfer,
+            signature,
+            unbound,
+        ]);
+        let proof = |i: usize| TxProof::from_block(&block, &block.txdata[i].txid()).unwrap();
+
+        let mut np = NameProof {
+            create: proof(1),
+            upgrade: None,
+            transfers: vec![TransferProof {
+                transfer: proof(2),
+                signature: proof(3),
+            }],
+            event: None,
+            delegation: None,
+        };
+        assert_eq!(np.verify("hello-world").unwrap(), new.public_key());
+        assert!(np.verify("other-name").is_err());
+
+        np.transfers[0].signature = proof(2);
+        assert!(np.verify("hello-world").is_err());
+
+        // The same signature in a transaction which doesn't spend the transfer.
+        np.transfers[0].signature = proof(4);
+        assert!(np.verify("hello-world").is_err());
+    }
+
+    #[test]
+    fn test_name_proof_with_v2_transfer() {
+        let old = Keys::generate();
+        let new = Keys::generate();
+        let create =
+            op_return_tx(&CreateBuilder::new(&old.public_key(), "hello-world").v1_op_return());
+        let tb = TransferBuilder {
+            new_pubkey: &new.public_key(),
+            name: "hello-world",
+        };
+        let transfer = op_return_tx(&tb.transfer_op_return());
+        let auth = TransferAuthorization {
+            name: "hello-world",
+            new_owner: &new.public_key(),
+            transfer_txid: &transfer.txid(),
+        };
+        let signature = spending(
+            op_return_tx(&auth.signature_op_return(&old).unwrap()),
+            &transfer,
+        );
+        // An identical transfer in another transaction, which the signature must not authorise.
+        let mut replayed = op_return_tx(&tb.transfer_op_return());
+        replayed.lock_time = LockTime::from_consensus(1);
+        let block = mine(vec![
+            op_return_tx(b"coinbase"),
+            create,
+            transfer,
+            signature,
+            replayed,
+        ]);
+        let proof = |i: usize| TxProof::from_block(&block, &block.txdata[i].txid()).unwrap();
+
+        let mut np = NameProof {
+            create: proof(1),
+            upgrade: None,
+            transfers: vec![TransferProof {
+                transfer: proof(2),
+                signature: proof(3),
+            }],
+            event: None,
+            delegation: None,
+        };
+        assert_eq!(np.verify("hello-world").unwrap(), new.public_key());
+
+        np.transfers[0].transfer = proof(4);
+        assert!(np.verify("hello-world").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip() {
+        let block = mine(vec![op_return_tx(b"NOM")]);
+        let proof = TxProof::from_block(&block, &block.txdata[0].txid()).unwrap();
+        let json = serde_json::to_string(&proof).unwrap();
+        assert_eq!(serde_json::from_str::<TxProof>(&json).unwrap(), proof);
+    }
+}
@@ -0,0 +1,180 @@
+use nostr_sdk::{Event, EventBuilder, Keys, Tag, TagKind, Timestamp, UnsignedEvent};
+use secp256k1::XOnlyPublicKey;
+
+use super::{EventExtractor, NameKind, Nsid, NsidBuilder};
+
+/// Builds the kind 38303 event with which the owner of a name authorises a delegate key to sign the name's record
+/// events, so the owner key can be kept offline. Its `d` tag is the nsid of the name and owner, its `nom` tag is the
+/// name and its `delegate` tag is the delegate's pubkey. A newer event replaces the previous one, and an event
+/// without a delegate revokes the delegation.
+pub struct RecordDelegateBuilder<'a> {
+    pub owner: &'a XOnlyPublicKey,
+    pub name: &'a str,
+    pub delegate: Option<&'a XOnlyPublicKey>,
+}
+
+impl<'a> RecordDelegateBuilder<'a> {
+    pub fn new(
+        owner: &'a XOnlyPublicKey,
+        name: &'a str,
+        delegate: &'a XOnlyPublicKey,
+    ) -> RecordDelegateBuilder<'a> {
+        RecordDelegateBuilder {
+            owner,
+            name,
+            delegate: Some(delegate),
+        }
+    }
+
+    /// A builder for the event revoking the current delegate.
+    pub fn revoke(owner: &'a XOnlyPublicKey, name: &'a str) -> RecordDelegateBuilder<'a> {
+        RecordDelegateBuilder {
+            owner,
+            name,
+            delegate: None,
+        }
+    }
+
+    fn event_builder(&self) -> EventBuilder {
+        let nsid = NsidBuilder::new(self.name, self.owner).finalize();
+        let mut tags = vec![
+            Tag::Identifier(nsid.to_string()),
+            Tag::Generic(
+                TagKind::Custom("nom".to_owned()),
+                vec![self.name.to_owned()],
+            ),
+        ];
+        if let Some(delegate) = self.delegate {
+            tags.push(Tag::Generic(
+                TagKind::Custom("delegate".to_owned()),
+                vec![delegate.to_string()],
+            ));
+        }
+        EventBuilder::new(NameKind::RecordDelegate.into(), "", &tags)
+    }
+
+    pub fn unsigned_event(&self) -> UnsignedEvent {
+        self.event_builder().to_unsigned_event(*self.owner)
+    }
+
+    /// Signs the delegation event. The keys must belong to the owner the builder was created with.
+    pub fn signed_event(&self, keys: &Keys) -> Result<Event, super::UtilError> {
+        if keys.public_key() != *self.owner {
+            return Err(super::UtilError::InvalidSigner);
+        }
+        Ok(self.unsigned_event().sign(keys)?)
+    }
+}
+
+/// A verified kind 38303 delegation event.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordDelegation {
+    pub name: String,
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub owner: XOnlyPublicKey,
+    #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+    pub delegate: Option<XOnlyPublicKey>,
+    #[cfg_attr(feature = "schema", schemars(with = "u64"))]
+    pub created_at: Timestamp,
+}
+
+impl RecordDelegation {
+    /// Checks that `event` is a record event for the delegating owner's name, signed by the delegate no earlier than
+    /// the delegation.
+    pub fn authorizes(&self, event: &Event) -> Result<(), super::UtilError> {
+        let invalid = super::UtilError::InvalidDelegation;
+        if event.kind != NameKind::Name.into() {
+            return Err(invalid("not a record event"));
+        }
+        if self.delegate != Some(event.pubkey) {
+            return Err(invalid("event is not signed by the delegate"));
+        }
+        event.verify().or(Err(invalid("invalid event signature")))?;
+        if event.extract_name()? != self.name || event.extract_owner()? != Some(self.owner) {
+            return Err(invalid("event does not match the delegation"));
+        }
+        if event.extract_nsid()? != NsidBuilder::new(&self.name, &self.owner).finalize() {
+            return Err(invalid("event nsid does not match the owner"));
+        }
+        if event.created_at < self.created_at {
+            return Err(invalid("event predates the delegation"));
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&Event> for RecordDelegation {
+    type Error = super::UtilError;
+
+    fn try_from(event: &Event) -> Result<Self, Self::Error> {
+        if event.kind != NameKind::RecordDelegate.into() {
+            return Err(super::UtilError::InvalidEventKind(event.kind));
+        }
+        event.verify().or(Err(super::UtilError::InvalidDelegation(
+            "invalid event signature",
+        )))?;
+        let calculated: Nsid = event.clone().try_into()?;
+        if event.extract_nsid()? != calculated {
+            return Err(super::UtilError::InvalidDelegation("invalid nsid"));
+        }
+        Ok(RecordDelegation {
+            name: event.extract_name()?,
+            owner: event.pubkey,
+            delegate: event.extract_delegate()?,
+            created_at: event.created_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Records, RecordsBuilder};
+
+    use super::*;
+
+    #[test]
+    fn test_delegated_records() {
+        let owner = Keys::generate();
+        let pubkey = owner.public_key();
+        let delegate = Keys::generate();
+        let event = RecordDelegateBuilder::new(&pubkey, "hello-world", &delegate.public_key())
+            .signed_event(&owner)
+            .unwrap();
+        let delegation = RecordDelegation::try_from(&event).unwrap();
+        assert_eq!(delegation.owner, pubkey);
+        assert_eq!(delegation.delegate, Some(delegate.public_key()));
+
+        let records = Records::new();
+        let builder = RecordsBuilder::new(&pubkey, "hello-world", &records);
+        let delegated = builder.delegated_event(&delegate).unwrap();
+        assert!(delegation.authorizes(&delegated).is_ok());
+        assert!(delegation
+            .authorizes(&builder.delegated_event(&Keys::generate()).unwrap())
+            .is_err());
+        assert!(delegation
+            .authorizes(&builder.signed_event(&owner).unwrap())
+            .is_err());
+
+        let other = RecordsBuilder::new(&pubkey, "other-name", &records);
+        assert!(delegation
+            .authorizes(&other.delegated_event(&delegate).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_revoke() {
+        let owner = Keys::generate();
+        let event = RecordDelegateBuilder::revoke(&owner.public_key(), "hello-world")
+            .signed_event(&owner)
+            .unwrap();
+        let delegation = RecordDelegation::try_from(&event).unwrap();
+        assert_eq!(delegation.delegate, None);
+        assert!(
+            RecordDelegateBuilder::revoke(&owner.public_key(), "hello-world")
+                .signed_event(&Keys::generate())
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,314 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    net::{Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+    sync::OnceLock,
+};
+
+use bitcoin::bech32::{self, FromBase32, Variant};
+use regex::Regex;
+use secp256k1::XOnlyPublicKey;
+
+/// Record keys with a known meaning and format. Any other key is allowed, but its value is not validated.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_with::SerializeDisplay, serde_with::DeserializeFromStr)
+)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RecordKey {
+    Ip4,
+    Ip6,
+    Npub,
+    Lud16,
+    Web,
+    Txt,
+    Cname,
+    Motd,
+    Twitter,
+    Other(String),
+}
+
+impl FromStr for RecordKey {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let key = match s.to_uppercase().as_str() {
+            "IP4" => RecordKey::Ip4,
+            "IP6" => RecordKey::Ip6,
+            "NPUB" => RecordKey::Npub,
+            "LUD16" => RecordKey::Lud16,
+            "WEB" => RecordKey::Web,
+            "TXT" => RecordKey::Txt,
+            "CNAME" => RecordKey::Cname,
+            "MOTD" => RecordKey::Motd,
+            "TWITTER" => RecordKey::Twitter,
+            other => RecordKey::Other(other.to_string()),
+        };
+        Ok(key)
+    }
+}
+
+impl Display for RecordKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            RecordKey::Ip4 => "IP4",
+            RecordKey::Ip6 => "IP6",
+            RecordKey::Npub => "NPUB",
+            RecordKey::Lud16 => "LUD16",
+            RecordKey::Web => "WEB",
+            RecordKey::Txt => "TXT",
+            RecordKey::Cname => "CNAME",
+            RecordKey::Motd => "MOTD",
+            RecordKey::Twitter => "TWITTER",
+            RecordKey::Other(key) => key,
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Whether `value` is a NIP-19 `npub`.
+fn is_npub(value: &str) -> bool {
+    match bech32::decode(value) {
+        Ok((hrp, data, Variant::Bech32)) if hrp == "npub" => Vec::<u8>::from_base32(&data)
+            .map_or(false, |key| XOnlyPublicKey::from_slice(&key).is_ok()),
+        _ => false,
+    }
+}
+
+/// Whether `value` matches `pattern`, which is compiled into `regex` the first time it is used.
+fn matches(regex: &'static OnceLock<Regex>, pattern: &str, value: &str) -> bool {
+    regex
+        .get_or_init(|| Regex::new(pattern).expect("invalid record pattern"))
+        .is_match(value)
+}
+
+impl RecordKey {
+    pub fn validate(&self, value: &str) -> Result<(), RecordError> {
+        static LUD16: OnceLock<Regex> = OnceLock::new();
+        static WEB: OnceLock<Regex> = OnceLock::new();
+        static CNAME: OnceLock<Regex> = OnceLock::new();
+        static TWITTER: OnceLock<Regex> = OnceLock::new();
+        let reason = match self {
+            RecordKey::Ip4 if Ipv4Addr::from_str(value).is_err() => "not an IPv4 address",
+            RecordKey::Ip6 if Ipv6Addr::from_str(value).is_err() => "not an IPv6 address",
+            RecordKey::Npub if !is_npub(value) => "not an npub",
+            RecordKey::Lud16
+                if !matches(
+                    &LUD16,
+                    r"(?i)\A[a-z0-9\-_.+]+@[a-z0-9\-]+(\.[a-z0-9\-]+)+\z",
+                    value,
+                ) =>
+            {
+                "not a lightning address"
+            }
+            RecordKey::Web if !matches(&WEB, r"\Ahttps?://\S+\z", value) => "not an http(s) URL",
+            RecordKey::Cname
+                if !matches(&CNAME, r"(?i)\A[a-z0-9\-]+(\.[a-z0-9\-]+)*\.?\z", value) =>
+            {
+                "not a host name"
+            }
+            RecordKey::Twitter if !matches(&TWITTER, r"\A@?[A-Za-z0-9_]{1,15}\z", value) => {
+                "not a Twitter handle"
+            }
+            RecordKey::Other(key) if key.is_empty() || key.contains(char::is_whitespace) => {
+                "invalid key"
+            }
+            _ => return Ok(()),
+        };
+        Err(RecordError::InvalidValue {
+            key: self.to_string(),
+            reason,
+        })
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum RecordError {
+    #[error("invalid {key} record: {reason}")]
+    InvalidValue { key: String, reason: &'static str },
+    #[error("invalid record line: {0}")]
+    InvalidLine(String),
+}
+
+/// The records of a name, keyed by upper case record key. Records built with [`Records::insert`] or parsed with
+/// [`FromStr`] are validated. Records deserialized from an event are not, use [`Records::validate`] to check them.
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Records(BTreeMap<String, String>);
+
+impl Records {
+    pub fn new() -> Records {
+        Records::default()
+    }
+
+    pub fn insert(&mut self, key: &str, value: &str) -> Result<(), RecordError> {
+        let key: RecordKey = key.parse().expect("infallible");
+        key.validate(value)?;
+        self.0.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    /// Builds records from untrusted key/value pairs, keeping the valid ones and returning the errors for the rest.
+    pub fn parse(pairs: impl IntoIterator<Item = (String, String)>) -> (Records, Vec<RecordError>) {
+        let mut records = Records::new();
+        let errors = pairs
+            .into_iter()
+            .filter_map(|(k, v)| records.insert(&k, &v).err())
+            .collect();
+        (records, errors)
+    }
+
+    /// All problems with these records.
+    pub fn validate(&self) -> Vec<RecordError> {
+        self.0
+            .iter()
+            .filter_map(|(k, v)| {
+                let key: RecordKey = k.parse().expect("infallible");
+                match key.validate(v) {
+                    Ok(()) if key.to_string() != *k => Some(RecordError::InvalidValue {
+                        key: k.clone(),
+                        reason: "key is not upper case",
+                    }),
+                    Ok(()) => None,
+                    Err(err) => Some(err),
+                }
+            })
+            .collect()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(&key.to_uppercase()).map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Records as `KEY=value` lines, the inverse of [`FromStr`].
+    pub fn to_lines(&self) -> String {
+        self.0
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for Records {
+    type Error = RecordError;
+
+    fn try_from(value: HashMap<String, String>) -> Result<Self, Self::Error> {
+        let mut records = Records::new();
+        for (k, v) in value {
+            records.insert(&k, &v)?;
+        }
+        Ok(records)
+    }
+}
+
+impl From<Records> for HashMap<String, String> {
+    fn from(value: Records) -> Self {
+        value.0.into_iter().collect()
+    }
+}
+
+impl IntoIterator for Records {
+    type Item = (String, String);
+    type IntoIter = std::collections::btree_map::IntoIter<String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+/// Parses `KEY=value` lines, ignoring blank lines.
+impl FromStr for Records {
+    type Err = RecordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut records = Records::new();
+        for line in s.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let (k, v) = line
+                .split_once('=')
+                .ok_or_else(|| RecordError::InvalidLine(line.to_string()))?;
+            records.insert(k.trim(), v.trim())?;
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::bech32::ToBase32;
+
+    use super::*;
+
+    #[test]
+    fn test_validate_known_keys() {
+        let mut records = Records::new();
+        assert!(records.insert("ip4", "127.0.0.1").is_ok());
+        assert!(records.insert("IP6", "::1").is_ok());
+        let pubkey =
+            hex::decode("60de6fbc4a78209942c62706d904ff9592c2e856f219793f7f73e62fc33bfc18")
+                .unwrap();
+        let npub = bech32::encode("npub", pubkey.to_base32(), Variant::Bech32).unwrap();
+        assert!(records.insert("NPUB", &npub).is_ok());
+        assert!(records.insert("LUD16", "satoshi@example.com").is_ok());
+        assert!(records.insert("WEB", "https://example.com").is_ok());
+        assert!(records.insert("CNAME", "example.com").is_ok());
+        assert!(records.insert("TWITTER", "@satoshi").is_ok());
+        assert!(records.insert("CUSTOM", "anything goes").is_ok());
+        assert_eq!(records.get("IP4"), Some("127.0.0.1"));
+        assert!(records.validate().is_empty());
+
+        assert!(records.insert("IP4", "localhost").is_err());
+        assert!(records.insert("IP6", "127.0.0.1").is_err());
+        assert!(records.insert("NPUB", "npub1234").is_err());
+        assert!(records.insert("LUD16", "satoshi").is_err());
+        assert!(records.insert("WEB", "example.com").is_err());
+        assert!(records.insert("CNAME", "not a host").is_err());
+        assert!(records.insert("TWITTER", "not a handle").is_err());
+    }
+
+    #[test]
+    fn test_parse_keeps_valid_records() {
+        let (records, errors) = Records::parse([
+            ("IP4".to_string(), "127.0.0.1".to_string()),
+            ("NPUB".to_string(), "npub1234".to_string()),
+        ]);
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            errors,
+            [RecordError::InvalidValue {
+                key: "NPUB".into(),
+                reason: "not an npub"
+            }]
+        );
+    }
+
+    #[test]
+    fn test_from_lines() {
+        let records: Records = "ip4=127.0.0.1\n\nmotd=hello=world".parse().unwrap();
+        assert_eq!(records.get("MOTD"), Some("hello=world"));
+        assert_eq!(records.to_lines(), "IP4=127.0.0.1\nMOTD=hello=world");
+        assert!("IP4".parse::<Records>().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialized_records_are_checked_by_validate() {
+        let records: Records = serde_json::from_str(r#"{"IP4":"nope","ip6":"::1"}"#).unwrap();
+        assert_eq!(records.validate().len(), 2);
+    }
+}
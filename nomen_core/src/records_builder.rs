@@ -0,0 +1,96 @@
+use nostr_sdk::{Event, EventBuilder, Keys, Tag, TagKind, UnsignedEvent};
+use secp256k1::XOnlyPublicKey;
+
+use super::{NameKind, NsidBuilder, Records};
+
+/// Builds the kind 38300 record event for a name. Its `d` tag is the nsid of the name and owner, its `nom` tag is the
+/// name and its content is the JSON records.
+pub struct RecordsBuilder<'a> {
+    pub pubkey: &'a XOnlyPublicKey,
+    pub name: &'a str,
+    pub records: &'a Records,
+}
+
+impl<'a> RecordsBuilder<'a> {
+    pub fn new(
+        pubkey: &'a XOnlyPublicKey,
+        name: &'a str,
+        records: &'a Records,
+    ) -> RecordsBuilder<'a> {
+        RecordsBuilder {
+            pubkey,
+            name,
+            records,
+        }
+    }
+
+    fn event_builder(&self, delegated: bool) -> EventBuilder {
+        let content = serde_json::to_string(self.records).expect("records serialize to JSON");
+        let nsid = NsidBuilder::new(self.name, self.pubkey).finalize();
+        let mut tags = vec![
+            Tag::Identifier(nsid.to_string()),
+            Tag::Generic(
+                TagKind::Custom("nom".to_owned()),
+                vec![self.name.to_owned()],
+            ),
+        ];
+        if delegated {
+            tags.push(Tag::Generic(
+                TagKind::Custom("owner".to_owned()),
+                vec![self.pubkey.to_string()],
+            ));
+        }
+        EventBuilder::new(NameKind::Name.into(), content, &tags)
+    }
+
+    pub fn unsigned_event(&self) -> UnsignedEvent {
+        self.event_builder(false).to_unsigned_event(*self.pubkey)
+    }
+
+    /// Signs the record event. The keys must belong to the owner the builder was created with.
+    pub fn signed_event(&self, keys: &Keys) -> Result<Event, super::UtilError> {
+        if keys.public_key() != *self.pubkey {
+            return Err(super::UtilError::InvalidSigner);
+        }
+        Ok(self.unsigned_event().sign(keys)?)
+    }
+
+    /// Signs the record event with the keys of a record delegate of the owner, see [`RecordDelegateBuilder`](crate::RecordDelegateBuilder). The
+    /// event's `owner` tag holds the owner's pubkey.
+    pub fn delegated_event(&self, keys: &Keys) -> Result<Event, super::UtilError> {
+        Ok(self
+            .event_builder(true)
+            .to_unsigned_event(keys.public_key())
+            .sign(keys)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EventExtractor, Nsid};
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let keys = Keys::generate();
+        let records: Records = "IP4=127.0.0.1\nMOTD=hello".parse().unwrap();
+        let event = RecordsBuilder::new(&keys.public_key(), "hello-world", &records)
+            .signed_event(&keys)
+            .unwrap();
+
+        assert!(event.verify().is_ok());
+        assert_eq!(event.extract_name().unwrap(), "hello-world");
+        assert_eq!(event.extract_records().unwrap(), records);
+        let nsid: Nsid = event.clone().try_into().unwrap();
+        assert_eq!(event.extract_nsid().unwrap(), nsid);
+    }
+
+    #[test]
+    fn test_wrong_signer() {
+        let records = Records::new();
+        let pubkey = Keys::generate().public_key();
+        let builder = RecordsBuilder::new(&pubkey, "hello-world", &records);
+        assert!(builder.signed_event(&Keys::generate()).is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use bitcoin::{hashes::Hash, Txid};
+#[cfg(feature = "nostr")]
+use nostr_sdk::Keys;
+use secp256k1::{schnorr::Signature, Message, Secp256k1, XOnlyPublicKey};
+use sha2::{Digest, Sha256};
+
+use super::{Hash160, ReleaseV1};
+
+/// Domain separation tag of the release message.
+pub const RELEASE_TAG: &str = "nomen/release/v1";
+
+/// Builds the release of a name by its current owner. Once the release is indexed, the name is available to be
+/// created again.
+///
+/// The signed message is the BIP340 style tagged hash `sha256(sha256(tag) || sha256(tag) || len(name) || name || owner
+/// || claim_txid)` with [`RELEASE_TAG`] as tag, the name length as a single byte and the txid in its internal byte
+/// order. `claim_txid` is the transaction which created the name, so a release can't be replayed against a later claim
+/// of the same name.
+pub struct ReleaseBuilder<'a> {
+    pub name: &'a str,
+    pub owner: &'a XOnlyPublicKey,
+    pub claim_txid: &'a Txid,
+}
+
+impl<'a> ReleaseBuilder<'a> {
+    pub fn new(
+        name: &'a str,
+        owner: &'a XOnlyPublicKey,
+        claim_txid: &'a Txid,
+    ) -> ReleaseBuilder<'a> {
+        ReleaseBuilder {
+            name,
+            owner,
+            claim_txid,
+        }
+    }
+
+    pub fn message(&self) -> Result<Message, super::UtilError> {
+        let tag = Sha256::digest(RELEASE_TAG.as_bytes());
+        let hash = Sha256::new()
+            .chain_update(tag)
+            .chain_update(tag)
+            .chain_update([super::length_prefix(self.name)?])
+            .chain_update(self.name.as_bytes())
+            .chain_update(self.owner.serialize())
+            .chain_update(self.claim_txid.as_byte_array())
+            .finalize();
+        Ok(Message::from_slice(&hash).expect("sha256 digest is 32 bytes"))
+    }
+
+    /// Signs the release. The keys must belong to the owner the builder was created with.
+    #[cfg(feature = "nostr")]
+    pub fn sign(&self, keys: &Keys) -> Result<Signature, super::UtilError> {
+        if keys.public_key() != *self.owner {
+            return Err(super::UtilError::InvalidSigner);
+        }
+        Ok(keys.sign_schnorr(&self.message()?)?)
+    }
+
+    #[cfg(feature = "nostr")]
+    pub fn op_return(&self, keys: &Keys) -> Result<Vec<u8>, super::UtilError> {
+        Ok(self.signature_provided_op_return(self.sign(keys)?))
+    }
+
+    pub fn signature_provided_op_return(&self, signature: Signature) -> Vec<u8> {
+        ReleaseV1 {
+            fingerprint: Hash160::default()
+                .chain_update(self.name.as_bytes())
+                .fingerprint(),
+            signature,
+        }
+        .serialize()
+    }
+
+    pub fn verify(&self, signature: &Signature) -> Result<(), super::UtilError> {
+        Secp256k1::verification_only()
+            .verify_schnorr(signature, &self.message()?, self.owner)
+            .or(Err(super::UtilError::InvalidSignature))
+    }
+}
+
+#[cfg(all(test, feature = "nostr"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release() {
+        let keys = Keys::generate();
+        let owner = keys.public_key();
+        let claim = Txid::from_byte_array([1; 32]);
+        let rb = ReleaseBuilder::new("hello-world", &owner, &claim);
+        let release = ReleaseV1::try_from(rb.op_return(&keys).unwrap().as_ref()).unwrap();
+
+        assert_eq!(hex::encode(release.fingerprint), "e5401df4b4");
+        assert!(rb.verify(&release.signature).is_ok());
+        let other_claim = Txid::from_byte_array([2; 32]);
+        assert!(ReleaseBuilder::new("hello-world", &owner, &other_claim)
+            .verify(&release.signature)
+            .is_err());
+        assert!(rb.sign(&Keys::generate()).is_err());
+        assert!(crate::RenewV1::try_from(rb.op_return(&keys).unwrap().as_ref()).is_err());
+    }
+}
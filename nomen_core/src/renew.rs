@@ -0,0 +1,97 @@
+#[cfg(feature = "nostr")]
+use nostr_sdk::Keys;
+use secp256k1::{schnorr::Signature, Message, Secp256k1, XOnlyPublicKey};
+use sha2::{Digest, Sha256};
+
+use super::{Hash160, RenewV1};
+
+/// Domain separation tag of the renewal message.
+pub const RENEW_TAG: &str = "nomen/renew/v1";
+
+/// Builds a renewal of a name by its current owner.
+///
+/// The signed message is the BIP340 style tagged hash `sha256(sha256(tag) || sha256(tag) || len(name) || name || owner
+/// || renewed_from)` with [`RENEW_TAG`] as tag, the name length as a single byte and `renewed_from` as a big endian
+/// `u64`. `renewed_from` is the block height the name was created or last renewed at, so a renewal can't be replayed
+/// once it has been used, and a renewal by a previous owner is never valid.
+pub struct RenewBuilder<'a> {
+    pub name: &'a str,
+    pub owner: &'a XOnlyPublicKey,
+    pub renewed_from: u64,
+}
+
+impl<'a> RenewBuilder<'a> {
+    pub fn new(name: &'a str, owner: &'a XOnlyPublicKey, renewed_from: u64) -> RenewBuilder<'a> {
+        RenewBuilder {
+            name,
+            owner,
+            renewed_from,
+        }
+    }
+
+    pub fn message(&self) -> Result<Message, super::UtilError> {
+        let tag = Sha256::digest(RENEW_TAG.as_bytes());
+        let hash = Sha256::new()
+            .chain_update(tag)
+            .chain_update(tag)
+            .chain_update([super::length_prefix(self.name)?])
+            .chain_update(self.name.as_bytes())
+            .chain_update(self.owner.serialize())
+            .chain_update(self.renewed_from.to_be_bytes())
+            .finalize();
+        Ok(Message::from_slice(&hash).expect("sha256 digest is 32 bytes"))
+    }
+
+    /// Signs the renewal. The keys must belong to the owner the builder was created with.
+    #[cfg(feature = "nostr")]
+    pub fn sign(&self, keys: &Keys) -> Result<Signature, super::UtilError> {
+        if keys.public_key() != *self.owner {
+            return Err(super::UtilError::InvalidSigner);
+        }
+        Ok(keys.sign_schnorr(&self.message()?)?)
+    }
+
+    #[cfg(feature = "nostr")]
+    pub fn op_return(&self, keys: &Keys) -> Result<Vec<u8>, super::UtilError> {
+        Ok(self.signature_provided_op_return(self.sign(keys)?))
+    }
+
+    pub fn signature_provided_op_return(&self, signature: Signature) -> Vec<u8> {
+        RenewV1 {
+            fingerprint: Hash160::default()
+                .chain_update(self.name.as_bytes())
+                .fingerprint(),
+            signature,
+        }
+        .serialize()
+    }
+
+    pub fn verify(&self, signature: &Signature) -> Result<(), super::UtilError> {
+        Secp256k1::verification_only()
+            .verify_schnorr(signature, &self.message()?, self.owner)
+            .or(Err(super::UtilError::InvalidSignature))
+    }
+}
+
+#[cfg(all(test, feature = "nostr"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renew() {
+        let keys = Keys::generate();
+        let owner = keys.public_key();
+        let rb = RenewBuilder::new("hello-world", &owner, 800_000);
+        let renew = RenewV1::try_from(rb.op_return(&keys).unwrap().as_ref()).unwrap();
+
+        assert_eq!(hex::encode(renew.fingerprint), "e5401df4b4");
+        assert!(rb.verify(&renew.signature).is_ok());
+        assert!(RenewBuilder::new("hello-world", &owner, 800_001)
+            .verify(&renew.signature)
+            .is_err());
+        assert!(RenewBuilder::new("other-name", &owner, 800_000)
+            .verify(&renew.signature)
+            .is_err());
+        assert!(rb.sign(&Keys::generate()).is_err());
+    }
+}
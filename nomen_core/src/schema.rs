@@ -0,0 +1,127 @@
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Metadata, RootSchema, Schema, SchemaObject, StringValidation},
+    JsonSchema,
+};
+
+use super::{
+    CommitV1, CreateV0, CreateV1, Name, NameEntity, NameProof, NomenKind, Nsid, OpReturn,
+    RecordDelegation, RecordKey, ReleaseV1, RenewV1, RevealV1, SignatureV1, SignatureV2,
+    TransferSignature, TransferV1,
+};
+
+fn string_schema(description: &str, pattern: Option<&str>, values: Option<&[&str]>) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        metadata: Some(Box::new(Metadata {
+            description: Some(description.into()),
+            ..Default::default()
+        })),
+        string: pattern.map(|p| {
+            Box::new(StringValidation {
+                pattern: Some(p.into()),
+                ..Default::default()
+            })
+        }),
+        enum_values: values.map(|v| v.iter().map(|s| (*s).into()).collect()),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// Types which serialize through [`Display`](std::fmt::Display) and [`FromStr`](std::str::FromStr) are strings.
+macro_rules! string_schema {
+    ($ty:ty, $description:literal, $pattern:expr, $values:expr) => {
+        impl JsonSchema for $ty {
+            fn schema_name() -> String {
+                stringify!($ty).into()
+            }
+
+            fn json_schema(_: &mut SchemaGenerator) -> Schema {
+                string_schema($description, $pattern, $values)
+            }
+        }
+    };
+}
+
+string_schema!(Nsid, "Hex encoded nsid.", Some("^[0-9a-f]{40}$"), None);
+string_schema!(
+    Name,
+    "Top-level name in canonical (punycode) form.",
+    Some("^[0-9a-z-]{3,43}$"),
+    None
+);
+string_schema!(
+    NameEntity,
+    "Bech32 name entity bundling an nsid, name and relays.",
+    Some("^nname1[02-9ac-hj-np-z]+$"),
+    None
+);
+string_schema!(
+    RecordKey,
+    "Record key, upper case for known keys.",
+    None,
+    None
+);
+string_schema!(
+    NomenKind,
+    "Kind of a Nomen OP_RETURN.",
+    None,
+    Some(&["create", "transfer", "commit", "reveal", "renew", "release"])
+);
+
+/// JSON Schema with a definition for every serializable protocol type, as published in `docs/protocol.schema.json`.
+pub fn protocol_schema() -> RootSchema {
+    let mut gen = SchemaGenerator::default();
+    gen.subschema_for::<Nsid>();
+    gen.subschema_for::<Name>();
+    gen.subschema_for::<NameEntity>();
+    gen.subschema_for::<NomenKind>();
+    gen.subschema_for::<CreateV0>();
+    gen.subschema_for::<CreateV1>();
+    gen.subschema_for::<TransferV1>();
+    gen.subschema_for::<CommitV1>();
+    gen.subschema_for::<RevealV1>();
+    gen.subschema_for::<RenewV1>();
+    gen.subschema_for::<ReleaseV1>();
+    gen.subschema_for::<SignatureV1>();
+    gen.subschema_for::<SignatureV2>();
+    gen.subschema_for::<TransferSignature>();
+    gen.subschema_for::<OpReturn>();
+    gen.subschema_for::<RecordKey>();
+    gen.subschema_for::<RecordDelegation>();
+    gen.subschema_for::<NameProof>();
+
+    RootSchema {
+        meta_schema: gen.settings().meta_schema.clone(),
+        schema: SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                title: Some("Nomen protocol types".into()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        },
+        definitions: gen.take_definitions(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../docs/protocol.schema.json");
+
+    /// Run with `NOMEN_UPDATE_SCHEMA=1` to regenerate the published schema.
+    #[test]
+    fn test_schema_up_to_date() {
+        let schema = serde_json::to_string_pretty(&protocol_schema()).unwrap() + "\n";
+        if std::env::var_os("NOMEN_UPDATE_SCHEMA").is_some() {
+            std::fs::write(SCHEMA_PATH, &schema).unwrap();
+        }
+        let published = std::fs::read_to_string(SCHEMA_PATH).unwrap_or_default();
+        assert_eq!(
+            published, schema,
+            "docs/protocol.schema.json is out of date, rerun with NOMEN_UPDATE_SCHEMA=1"
+        );
+    }
+}
@@ -1,8 +1,11 @@
 use crate::NomenKind;
-use nostr_sdk::{EventId, UnsignedEvent};
-use secp256k1::{schnorr::Signature, XOnlyPublicKey};
+use bitcoin::{hashes::Hash, Txid};
+#[cfg(feature = "nostr")]
+use nostr_sdk::{EventId, Keys, UnsignedEvent};
+use secp256k1::{schnorr::Signature, Message, Secp256k1, XOnlyPublicKey};
+use sha2::{Digest, Sha256};
 
-use super::{SignatureV1, TransferV1};
+use super::{SignatureV1, SignatureV2, TransferV1};
 
 pub struct TransferBuilder<'a> {
     pub new_pubkey: &'a XOnlyPublicKey,
@@ -18,6 +21,15 @@ impl<'a> TransferBuilder<'a> {
         .serialize()
     }
 
+    /// The v1 message, the NIP-01 id of [`TransferBuilder::unsigned_event`]: the sha256 of the JSON
+    /// `[0,"<prev_owner>",1,1,[],"<prev_owner><name>"]`.
+    pub fn message(&self, prev_owner: &XOnlyPublicKey) -> Message {
+        let content = format!("{}{}", hex::encode(prev_owner.serialize()), self.name);
+        let json = format!("[0,\"{prev_owner}\",1,1,[],{}]", json_string(&content));
+        Message::from_slice(&Sha256::digest(json.as_bytes())).expect("sha256 digest is 32 bytes")
+    }
+
+    #[cfg(feature = "nostr")]
     pub fn unsigned_event(&self, prev_owner: &XOnlyPublicKey) -> nostr_sdk::UnsignedEvent {
         let created_at = 1u64.into();
         let kind: nostr_sdk::Kind = 1u64.into();
@@ -34,6 +46,7 @@ impl<'a> TransferBuilder<'a> {
         }
     }
 
+    #[cfg(feature = "nostr")]
     pub fn signature_op_return(&self, keys: nostr_sdk::Keys) -> Result<Vec<u8>, super::UtilError> {
         let unsigned_event = self.unsigned_event(&keys.public_key());
         let event = unsigned_event.sign(&keys)?;
@@ -46,6 +59,146 @@ impl<'a> TransferBuilder<'a> {
     pub fn signature_provided_op_return(&self, signature: Signature) -> Vec<u8> {
         SignatureV1 { signature }.serialize()
     }
+
+    /// Checks a v1 transfer signature by the previous owner.
+    pub fn verify(
+        &self,
+        signature: &Signature,
+        old_owner: &XOnlyPublicKey,
+    ) -> Result<(), super::UtilError> {
+        Secp256k1::verification_only()
+            .verify_schnorr(signature, &self.message(old_owner), old_owner)
+            .or(Err(super::UtilError::InvalidSignature))
+    }
+}
+
+/// `s` as a JSON string literal, escaped the way NIP-01 serializes event content.
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            '\u{8}' => json.push_str("\\b"),
+            '\u{c}' => json.push_str("\\f"),
+            c if c < ' ' => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// A transfer must be signed within this many blocks. A transfer mined at height `h` expires once the chain reaches
+/// `h + TRANSFER_EXPIRY_BLOCKS + 1` without a signature. Indexers may use a shorter window on test networks.
+pub const TRANSFER_EXPIRY_BLOCKS: usize = 100;
+
+/// Domain separation tag of the v2 transfer authorisation message.
+pub const TRANSFER_V2_TAG: &str = "nomen/transfer/v2";
+
+/// The v2 transfer authorisation signed by the previous owner. Unlike v1, the message commits to the new owner and to
+/// the txid of the transfer transaction, so a signature can only ever authorise that one transfer.
+///
+/// The message is the BIP340 style tagged hash `sha256(sha256(tag) || sha256(tag) || len(name) || name || new_owner ||
+/// old_owner || txid)`, with [`TRANSFER_V2_TAG`] as tag, the name length as a single byte and the txid in its internal
+/// byte order.
+pub struct TransferAuthorization<'a> {
+    pub name: &'a str,
+    pub new_owner: &'a XOnlyPublicKey,
+    pub transfer_txid: &'a Txid,
+}
+
+impl<'a> TransferAuthorization<'a> {
+    pub fn message(&self, old_owner: &XOnlyPublicKey) -> Result<Message, super::UtilError> {
+        let tag = Sha256::digest(TRANSFER_V2_TAG.as_bytes());
+        let hash = Sha256::new()
+            .chain_update(tag)
+            .chain_update(tag)
+            .chain_update([super::length_prefix(self.name)?])
+            .chain_update(self.name.as_bytes())
+            .chain_update(self.new_owner.serialize())
+            .chain_update(old_owner.serialize())
+            .chain_update(self.transfer_txid.as_byte_array())
+            .finalize();
+        Ok(Message::from_slice(&hash).expect("sha256 digest is 32 bytes"))
+    }
+
+    #[cfg(feature = "nostr")]
+    pub fn sign(&self, keys: &Keys) -> Result<Signature, super::UtilError> {
+        Ok(keys.sign_schnorr(&self.message(&keys.public_key())?)?)
+    }
+
+    #[cfg(feature = "nostr")]
+    pub fn signature_op_return(&self, keys: &Keys) -> Result<Vec<u8>, super::UtilError> {
+        Ok(SignatureV2::new(&self.sign(keys)?).serialize())
+    }
+
+    pub fn verify(
+        &self,
+        signature: &Signature,
+        old_owner: &XOnlyPublicKey,
+    ) -> Result<(), super::UtilError> {
+        Secp256k1::verification_only()
+            .verify_schnorr(signature, &self.message(old_owner)?, old_owner)
+            .or(Err(super::UtilError::InvalidSignature))
+    }
+}
+
+/// A transfer signature found on chain, in either version.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(tag = "version", rename_all = "lowercase"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransferSignature {
+    V1(SignatureV1),
+    V2(SignatureV2),
+}
+
+impl TransferSignature {
+    pub fn serialize(&self) -> Vec<u8> {
+        match self {
+            TransferSignature::V1(s) => s.serialize(),
+            TransferSignature::V2(s) => s.serialize(),
+        }
+    }
+
+    /// Checks that the signature authorises the transfer of `name` from `old_owner` to `new_owner` made in
+    /// `transfer_txid`. v1 signatures don't commit to the transaction, so `transfer_txid` is ignored for them.
+    pub fn verify(
+        &self,
+        name: &str,
+        new_owner: &XOnlyPublicKey,
+        old_owner: &XOnlyPublicKey,
+        transfer_txid: &Txid,
+    ) -> Result<(), super::UtilError> {
+        match self {
+            TransferSignature::V1(s) => TransferBuilder {
+                new_pubkey: new_owner,
+                name,
+            }
+            .verify(&s.signature, old_owner),
+            TransferSignature::V2(s) => TransferAuthorization {
+                name,
+                new_owner,
+                transfer_txid,
+            }
+            .verify(&s.signature, old_owner),
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for TransferSignature {
+    type Error = super::UtilError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        SignatureV1::try_from(value)
+            .map(TransferSignature::V1)
+            .or_else(|_| SignatureV2::try_from(value).map(TransferSignature::V2))
+    }
 }
 
 #[cfg(test)]
@@ -69,4 +222,91 @@ mod tests {
 
         // Signatures are not consistent, so they can't really be tested here.
     }
+
+    #[cfg(feature = "nostr")]
+    #[test]
+    fn test_v1_message_is_event_id() {
+        let old = Keys::generate().public_key();
+        let new = Keys::generate().public_key();
+        for name in ["hello-world", "quote\"back\\slash\n\u{1}"] {
+            let tb = TransferBuilder {
+                new_pubkey: &new,
+                name,
+            };
+            assert_eq!(
+                tb.message(&old).as_ref(),
+                tb.unsigned_event(&old).id.as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn test_v2_message_rejects_long_names() {
+        let pubkey = XOnlyPublicKey::from_str(
+            "74301b9c5d30b764bca8d3eb4febb06862f558d292fde93b4a290d90850bac91",
+        )
+        .unwrap();
+        let txid = Txid::from_byte_array([1; 32]);
+        let message = |name: &str| {
+            TransferAuthorization {
+                name,
+                new_owner: &pubkey,
+                transfer_txid: &txid,
+            }
+            .message(&pubkey)
+        };
+        assert!(message(&"a".repeat(255)).is_ok());
+        assert!(message(&"a".repeat(256)).is_err());
+    }
+
+    #[cfg(feature = "nostr")]
+    #[test]
+    fn test_v2_signature_is_bound_to_transfer() {
+        let old = Keys::generate();
+        let new = Keys::generate().public_key();
+        let txid = Txid::from_byte_array([1; 32]);
+        let auth = TransferAuthorization {
+            name: "hello-world",
+            new_owner: &new,
+            transfer_txid: &txid,
+        };
+        let sig = auth.sign(&old).unwrap();
+        assert!(auth.verify(&sig, &old.public_key()).is_ok());
+
+        let other = Keys::generate().public_key();
+        let other_txid = Txid::from_byte_array([2; 32]);
+        let ts =
+            TransferSignature::try_from(auth.signature_op_return(&old).unwrap().as_ref()).unwrap();
+        assert!(ts
+            .verify("hello-world", &new, &old.public_key(), &txid)
+            .is_ok());
+        assert!(ts
+            .verify("hello-world", &new, &old.public_key(), &other_txid)
+            .is_err());
+        assert!(ts
+            .verify("hello-world", &other, &old.public_key(), &txid)
+            .is_err());
+        assert!(ts.verify("hello-world", &new, &other, &txid).is_err());
+        assert!(ts
+            .verify("other-name", &new, &old.public_key(), &txid)
+            .is_err());
+    }
+
+    #[cfg(feature = "nostr")]
+    #[test]
+    fn test_v1_signature_still_verifies() {
+        let old = Keys::generate();
+        let new = Keys::generate().public_key();
+        let tb = TransferBuilder {
+            new_pubkey: &new,
+            name: "hello-world",
+        };
+        let op_return = tb.signature_op_return(old).unwrap();
+        let ts = TransferSignature::try_from(op_return.as_ref()).unwrap();
+        let txid = Txid::all_zeros();
+        assert!(ts
+            .verify("hello-world", &new, &old.public_key(), &txid)
+            .is_ok());
+        assert!(ts.verify("hello-world", &new, &new, &txid).is_err());
+    }
 }
@@ -0,0 +1,217 @@
+//! Runs the conformance vectors in `docs/vectors`, which other implementations of the protocol test against too.
+
+use std::{path::PathBuf, str::FromStr};
+
+use bitcoin::Txid;
+use nomen_core::{
+    Hash160, Name, NsidBuilder, OpReturn, ReleaseBuilder, ReleaseV1, RenewBuilder, RenewV1,
+    SignatureV1, SignatureV2, TransferAuthorization, TransferBuilder, TransferSignature,
+};
+use secp256k1::{schnorr::Signature, KeyPair, Message, Secp256k1, SecretKey, XOnlyPublicKey};
+use serde::{de::DeserializeOwned, Deserialize};
+
+fn load<T: DeserializeOwned>(file: &str) -> T {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../docs/vectors")
+        .join(file);
+    let json = std::fs::read_to_string(path).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+#[derive(Deserialize)]
+struct OpReturnVectors {
+    valid: Vec<ValidOpReturn>,
+    invalid: Vec<InvalidOpReturn>,
+}
+
+#[derive(Deserialize)]
+struct ValidOpReturn {
+    description: String,
+    op_return: String,
+    decoded: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct InvalidOpReturn {
+    description: String,
+    op_return: String,
+}
+
+#[test]
+fn test_op_returns() {
+    let vectors: OpReturnVectors = load("op_returns.json");
+    for v in vectors.valid {
+        let bytes = hex::decode(&v.op_return).unwrap();
+        let decoded = OpReturn::try_from(bytes.as_slice())
+            .unwrap_or_else(|e| panic!("{}: {e}", v.description));
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            v.decoded,
+            "{}",
+            v.description
+        );
+
+        let encoded: OpReturn = serde_json::from_value(v.decoded).unwrap();
+        assert_eq!(
+            hex::encode(encoded.serialize()),
+            v.op_return,
+            "{}",
+            v.description
+        );
+    }
+    for v in vectors.invalid {
+        let bytes = hex::decode(&v.op_return).unwrap();
+        assert!(
+            OpReturn::try_from(bytes.as_slice()).is_err(),
+            "{}",
+            v.description
+        );
+    }
+}
+
+#[derive(Deserialize)]
+struct NameVectors {
+    valid: Vec<NameVector>,
+    invalid: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct NameVector {
+    input: String,
+    name: String,
+    fingerprint: String,
+    pubkey: XOnlyPublicKey,
+    nsid: String,
+    nsid_bech32: String,
+}
+
+#[test]
+fn test_names() {
+    let vectors: NameVectors = load("names.json");
+    for v in vectors.valid {
+        let name = Name::from_str(&v.input).unwrap();
+        assert_eq!(name.to_string(), v.name, "{}", v.input);
+
+        let fingerprint = Hash160::default()
+            .chain_update(name.as_ref().as_bytes())
+            .fingerprint();
+        assert_eq!(hex::encode(fingerprint), v.fingerprint, "{}", v.input);
+
+        let nsid = NsidBuilder::new(name.as_ref(), &v.pubkey).finalize();
+        assert_eq!(nsid.to_string(), v.nsid, "{}", v.input);
+        assert_eq!(nsid.to_bech32(), v.nsid_bech32, "{}", v.input);
+    }
+    for input in vectors.invalid {
+        assert!(Name::from_str(&input).is_err(), "{input}");
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SignatureKind {
+    TransferV1,
+    TransferV2,
+    Renew,
+    Release,
+}
+
+#[derive(Deserialize)]
+struct SignatureVector {
+    description: String,
+    #[serde(rename = "type")]
+    kind: SignatureKind,
+    name: String,
+    secret_key: SecretKey,
+    owner: XOnlyPublicKey,
+    new_owner: Option<XOnlyPublicKey>,
+    transfer_txid: Option<Txid>,
+    renewed_from: Option<u64>,
+    claim_txid: Option<Txid>,
+    message: String,
+    signature: Signature,
+    op_return: String,
+    valid: bool,
+}
+
+/// The message, the `OP_RETURN` carrying `signature`, and whether the decoded `OP_RETURN` verifies.
+fn check(v: &SignatureVector) -> (Message, Vec<u8>, bool) {
+    let op_return = hex::decode(&v.op_return).unwrap();
+    match v.kind {
+        SignatureKind::TransferV1 | SignatureKind::TransferV2 => {
+            let new_owner = v.new_owner.as_ref().unwrap();
+            let (message, encoded) = if let SignatureKind::TransferV1 = v.kind {
+                let tb = TransferBuilder {
+                    new_pubkey: new_owner,
+                    name: &v.name,
+                };
+                (
+                    tb.message(&v.owner),
+                    SignatureV1::new(&v.signature).serialize(),
+                )
+            } else {
+                let auth = TransferAuthorization {
+                    name: &v.name,
+                    new_owner,
+                    transfer_txid: v.transfer_txid.as_ref().unwrap(),
+                };
+                (
+                    auth.message(&v.owner).unwrap(),
+                    SignatureV2::new(&v.signature).serialize(),
+                )
+            };
+            let decoded = TransferSignature::try_from(op_return.as_slice()).unwrap();
+            // v1 signatures ignore the txid, so any txid will do for them.
+            let txid = v
+                .transfer_txid
+                .unwrap_or_else(|| Txid::from_str(&"0".repeat(64)).unwrap());
+            let verified = decoded.verify(&v.name, new_owner, &v.owner, &txid).is_ok();
+            (message, encoded, verified)
+        }
+        SignatureKind::Renew => {
+            let rb = RenewBuilder::new(&v.name, &v.owner, v.renewed_from.unwrap());
+            let decoded = RenewV1::try_from(op_return.as_slice()).unwrap();
+            (
+                rb.message().unwrap(),
+                rb.signature_provided_op_return(v.signature),
+                rb.verify(&decoded.signature).is_ok(),
+            )
+        }
+        SignatureKind::Release => {
+            let claim_txid = v.claim_txid.unwrap();
+            let rb = ReleaseBuilder::new(&v.name, &v.owner, &claim_txid);
+            let decoded = ReleaseV1::try_from(op_return.as_slice()).unwrap();
+            (
+                rb.message().unwrap(),
+                rb.signature_provided_op_return(v.signature),
+                rb.verify(&decoded.signature).is_ok(),
+            )
+        }
+    }
+}
+
+#[test]
+fn test_signatures() {
+    let secp = Secp256k1::new();
+    let vectors: Vec<SignatureVector> = load("signatures.json");
+    for v in vectors {
+        let keys = KeyPair::from_secret_key(&secp, &v.secret_key);
+        assert_eq!(keys.x_only_public_key().0, v.owner, "{}", v.description);
+
+        let (message, encoded, verified) = check(&v);
+        assert_eq!(
+            hex::encode(message.as_ref()),
+            v.message,
+            "{}",
+            v.description
+        );
+        assert_eq!(hex::encode(encoded), v.op_return, "{}", v.description);
+        assert_eq!(verified, v.valid, "{}", v.description);
+        assert_eq!(
+            secp.verify_schnorr(&v.signature, &message, &v.owner)
+                .is_ok(),
+            v.valid,
+            "{}",
+            v.description
+        );
+    }
+}
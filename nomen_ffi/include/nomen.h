@@ -0,0 +1,50 @@
+/*
+ * C ABI to nomen_core, see nomen_ffi/src/lib.rs for the documentation of each function.
+ *
+ * Arguments are NUL terminated UTF-8 strings. Public keys, signatures, txids and OP_RETURNs are hex. Every returned
+ * string belongs to the caller and must be freed with nomen_string_free. A function which fails returns NULL (or
+ * false), and nomen_last_error returns the reason.
+ */
+
+#ifndef NOMEN_H
+#define NOMEN_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+void nomen_string_free(char *s);
+char *nomen_last_error(void);
+
+char *nomen_normalize_name(const char *name);
+char *nomen_fingerprint(const char *name);
+char *nomen_nsid(const char *name, const char *pubkey);
+
+char *nomen_create_op_return(const char *name, const char *pubkey);
+char *nomen_transfer_op_return(const char *name, const char *new_owner);
+char *nomen_transfer_message(const char *name, const char *new_owner, const char *old_owner,
+                             const char *transfer_txid);
+char *nomen_transfer_v1_message(const char *name, const char *new_owner, const char *old_owner);
+char *nomen_transfer_signature_op_return(const char *signature);
+char *nomen_renew_message(const char *name, const char *owner, uint64_t renewed_from);
+char *nomen_renew_op_return(const char *name, const char *owner, uint64_t renewed_from,
+                            const char *signature);
+char *nomen_release_message(const char *name, const char *owner, const char *claim_txid);
+char *nomen_release_op_return(const char *name, const char *owner, const char *claim_txid,
+                              const char *signature);
+
+char *nomen_decode_op_return(const char *op_return);
+
+bool nomen_validate_name(const char *name);
+bool nomen_validate_record(const char *key, const char *value);
+bool nomen_verify_transfer_signature(const char *signature_op_return, const char *name, const char *new_owner,
+                                     const char *old_owner, const char *transfer_txid);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
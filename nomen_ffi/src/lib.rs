@@ -0,0 +1,350 @@
+//! A stable C ABI to the nomen_core builders, decoders and validators, so mobile apps build `OP_RETURN`s and
+//! signature messages with the same code as the indexer. The declarations are in `include/nomen.h`.
+//!
+//! Arguments are NUL terminated UTF-8 strings. Public keys, signatures, txids and `OP_RETURN`s are hex. Every returned
+//! string belongs to the caller and must be freed with [`nomen_string_free`]. A function which fails returns `NULL`
+//! (or `false`), and [`nomen_last_error`] returns the reason.
+//!
+//! Keys never cross the ABI: the `*_message` functions return the 32 byte message for the app to sign with BIP340
+//! Schnorr, and the matching `*_op_return` functions take the signature.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::{
+    cell::RefCell,
+    error::Error,
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, UnwindSafe},
+    ptr,
+    str::FromStr,
+};
+
+use bitcoin::Txid;
+use nomen_core::{
+    CreateBuilder, Hash160, Name, NsidBuilder, OpReturn, RecordKey, ReleaseBuilder, RenewBuilder,
+    SignatureV2, TransferAuthorization, TransferBuilder, TransferSignature,
+};
+use secp256k1::{schnorr::Signature, XOnlyPublicKey};
+
+type FfiResult<T> = Result<T, Box<dyn Error>>;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<String>> = RefCell::new(None);
+}
+
+fn set_error(error: String) {
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(error));
+}
+
+/// Runs `f`, turning errors and panics into the last error, so neither crosses the ABI.
+fn call<T>(f: impl FnOnce() -> FfiResult<T> + UnwindSafe) -> Option<T> {
+    LAST_ERROR.with(|e| e.borrow_mut().take());
+    match catch_unwind(f) {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(err)) => {
+            set_error(err.to_string());
+            None
+        }
+        Err(_) => {
+            set_error("internal error".into());
+            None
+        }
+    }
+}
+
+fn string_call(f: impl FnOnce() -> FfiResult<String> + UnwindSafe) -> *mut c_char {
+    call(|| Ok(CString::new(f()?)?)).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+unsafe fn arg<'a>(s: *const c_char) -> FfiResult<&'a str> {
+    if s.is_null() {
+        return Err("unexpected NULL argument".into());
+    }
+    Ok(CStr::from_ptr(s).to_str()?)
+}
+
+unsafe fn name(s: *const c_char) -> FfiResult<Name> {
+    Name::from_str(arg(s)?).or(Err("invalid name".into()))
+}
+
+unsafe fn pubkey(s: *const c_char) -> FfiResult<XOnlyPublicKey> {
+    XOnlyPublicKey::from_str(arg(s)?).or(Err("invalid public key".into()))
+}
+
+unsafe fn signature(s: *const c_char) -> FfiResult<Signature> {
+    Signature::from_str(arg(s)?).or(Err("invalid signature".into()))
+}
+
+unsafe fn txid(s: *const c_char) -> FfiResult<Txid> {
+    Txid::from_str(arg(s)?).or(Err("invalid txid".into()))
+}
+
+/// Frees a string returned by this library. `NULL` is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn nomen_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// The error of the last failed call on this thread, or `NULL`.
+#[no_mangle]
+pub extern "C" fn nomen_last_error() -> *mut c_char {
+    LAST_ERROR
+        .with(|e| e.borrow().clone())
+        .and_then(|e| CString::new(e).ok())
+        .map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// The canonical form of a name. Unicode names become punycode.
+#[no_mangle]
+pub unsafe extern "C" fn nomen_normalize_name(name: *const c_char) -> *mut c_char {
+    string_call(|| Ok(self::name(name)?.to_string()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nomen_fingerprint(name: *const c_char) -> *mut c_char {
+    string_call(|| {
+        let name = self::name(name)?;
+        Ok(hex::encode(
+            Hash160::default()
+                .chain_update(name.as_ref().as_bytes())
+                .fingerprint(),
+        ))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nomen_nsid(name: *const c_char, pubkey: *const c_char) -> *mut c_char {
+    string_call(|| {
+        let name = self::name(name)?;
+        Ok(NsidBuilder::new(name.as_ref(), &self::pubkey(pubkey)?)
+            .finalize()
+            .to_string())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nomen_create_op_return(
+    name: *const c_char,
+    pubkey: *const c_char,
+) -> *mut c_char {
+    string_call(|| {
+        let name = self::name(name)?;
+        Ok(hex::encode(
+            CreateBuilder::new(&self::pubkey(pubkey)?, name.as_ref()).v1_op_return(),
+        ))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nomen_transfer_op_return(
+    name: *const c_char,
+    new_owner: *const c_char,
+) -> *mut c_char {
+    string_call(|| {
+        let name = self::name(name)?;
+        Ok(hex::encode(
+            TransferBuilder {
+                new_pubkey: &pubkey(new_owner)?,
+                name: name.as_ref(),
+            }
+            .transfer_op_return(),
+        ))
+    })
+}
+
+/// The v2 transfer authorisation message, signed by the current owner once the transfer is broadcast.
+#[no_mangle]
+pub unsafe extern "C" fn nomen_transfer_message(
+    name: *const c_char,
+    new_owner: *const c_char,
+    old_owner: *const c_char,
+    transfer_txid: *const c_char,
+) -> *mut c_char {
+    string_call(|| {
+        let name = self::name(name)?;
+        let message = TransferAuthorization {
+            name: name.as_ref(),
+            new_owner: &pubkey(new_owner)?,
+            transfer_txid: &txid(transfer_txid)?,
+        }
+        .message(&pubkey(old_owner)?)?;
+        Ok(hex::encode(message.as_ref()))
+    })
+}
+
+/// The legacy v1 transfer message. It doesn't commit to the transfer, prefer [`nomen_transfer_message`].
+#[no_mangle]
+pub unsafe extern "C" fn nomen_transfer_v1_message(
+    name: *const c_char,
+    new_owner: *const c_char,
+    old_owner: *const c_char,
+) -> *mut c_char {
+    string_call(|| {
+        let name = self::name(name)?;
+        let message = TransferBuilder {
+            new_pubkey: &pubkey(new_owner)?,
+            name: name.as_ref(),
+        }
+        .message(&pubkey(old_owner)?);
+        Ok(hex::encode(message.as_ref()))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nomen_transfer_signature_op_return(
+    signature: *const c_char,
+) -> *mut c_char {
+    string_call(|| {
+        Ok(hex::encode(
+            SignatureV2::new(&self::signature(signature)?).serialize(),
+        ))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nomen_renew_message(
+    name: *const c_char,
+    owner: *const c_char,
+    renewed_from: u64,
+) -> *mut c_char {
+    string_call(|| {
+        let (name, owner) = (self::name(name)?, pubkey(owner)?);
+        let message = RenewBuilder::new(name.as_ref(), &owner, renewed_from).message()?;
+        Ok(hex::encode(message.as_ref()))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nomen_renew_op_return(
+    name: *const c_char,
+    owner: *const c_char,
+    renewed_from: u64,
+    signature: *const c_char,
+) -> *mut c_char {
+    string_call(|| {
+        let (name, owner) = (self::name(name)?, pubkey(owner)?);
+        Ok(hex::encode(
+            RenewBuilder::new(name.as_ref(), &owner, renewed_from)
+                .signature_provided_op_return(self::signature(signature)?),
+        ))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nomen_release_message(
+    name: *const c_char,
+    owner: *const c_char,
+    claim_txid: *const c_char,
+) -> *mut c_char {
+    string_call(|| {
+        let (name, owner, claim_txid) = (self::name(name)?, pubkey(owner)?, txid(claim_txid)?);
+        let message = ReleaseBuilder::new(name.as_ref(), &owner, &claim_txid).message()?;
+        Ok(hex::encode(message.as_ref()))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nomen_release_op_return(
+    name: *const c_char,
+    owner: *const c_char,
+    claim_txid: *const c_char,
+    signature: *const c_char,
+) -> *mut c_char {
+    string_call(|| {
+        let (name, owner, claim_txid) = (self::name(name)?, pubkey(owner)?, txid(claim_txid)?);
+        Ok(hex::encode(
+            ReleaseBuilder::new(name.as_ref(), &owner, &claim_txid)
+                .signature_provided_op_return(self::signature(signature)?),
+        ))
+    })
+}
+
+/// Decodes any Nomen `OP_RETURN` to the JSON of [`OpReturn`].
+#[no_mangle]
+pub unsafe extern "C" fn nomen_decode_op_return(op_return: *const c_char) -> *mut c_char {
+    string_call(|| {
+        let op_return = OpReturn::try_from(hex::decode(arg(op_return)?)?.as_slice())?;
+        Ok(serde_json::to_string(&op_return)?)
+    })
+}
+
+/// Whether `name` is a valid top-level name once normalised.
+#[no_mangle]
+pub unsafe extern "C" fn nomen_validate_name(name: *const c_char) -> bool {
+    call(|| self::name(name).map(|_| ())).is_some()
+}
+
+/// Whether `value` is valid for the record `key`. Unknown keys accept any value.
+#[no_mangle]
+pub unsafe extern "C" fn nomen_validate_record(key: *const c_char, value: *const c_char) -> bool {
+    call(|| Ok(RecordKey::from_str(arg(key)?)?.validate(arg(value)?)?)).is_some()
+}
+
+/// Whether the signature `OP_RETURN` authorises the transfer of `name` to `new_owner` made in `transfer_txid`.
+#[no_mangle]
+pub unsafe extern "C" fn nomen_verify_transfer_signature(
+    signature_op_return: *const c_char,
+    name: *const c_char,
+    new_owner: *const c_char,
+    old_owner: *const c_char,
+    transfer_txid: *const c_char,
+) -> bool {
+    call(|| {
+        let signature =
+            TransferSignature::try_from(hex::decode(arg(signature_op_return)?)?.as_slice())?;
+        Ok(signature.verify(
+            self::name(name)?.as_ref(),
+            &pubkey(new_owner)?,
+            &pubkey(old_owner)?,
+            &txid(transfer_txid)?,
+        )?)
+    })
+    .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBKEY: &str = "60de6fbc4a78209942c62706d904ff9592c2e856f219793f7f73e62fc33bfc18";
+
+    fn c(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    unsafe fn take(s: *mut c_char) -> Option<String> {
+        if s.is_null() {
+            return None;
+        }
+        let owned = CStr::from_ptr(s).to_str().unwrap().to_string();
+        nomen_string_free(s);
+        Some(owned)
+    }
+
+    #[test]
+    fn test_errors() {
+        unsafe {
+            assert_eq!(
+                take(nomen_nsid(c("hello-world").as_ptr(), c("nope").as_ptr())),
+                None
+            );
+            assert_eq!(
+                take(nomen_last_error()).as_deref(),
+                Some("invalid public key")
+            );
+            assert_eq!(take(nomen_nsid(ptr::null(), c(PUBKEY).as_ptr())), None);
+            assert!(nomen_validate_name(c("hello-world").as_ptr()));
+            assert_eq!(take(nomen_last_error()), None);
+            assert!(!nomen_validate_record(
+                c("IP4").as_ptr(),
+                c("nope").as_ptr()
+            ));
+            assert_eq!(
+                take(nomen_last_error()).as_deref(),
+                Some("invalid IP4 record: not an IPv4 address")
+            );
+        }
+    }
+}
@@ -0,0 +1,60 @@
+/* Smoke test of the C ABI, built and run by tests/smoke.rs. Exits non-zero on the first failed check. */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "nomen.h"
+
+#define PUBKEY "60de6fbc4a78209942c62706d904ff9592c2e856f219793f7f73e62fc33bfc18"
+
+static int failures = 0;
+
+static void expect_str(const char *what, char *actual, const char *expected) {
+    if (actual == NULL || strcmp(actual, expected) != 0) {
+        fprintf(stderr, "%s: expected %s, got %s\n", what, expected, actual ? actual : "NULL");
+        failures++;
+    }
+    nomen_string_free(actual);
+}
+
+static void expect_contains(const char *what, char *actual, const char *expected) {
+    if (actual == NULL || strstr(actual, expected) == NULL) {
+        fprintf(stderr, "%s: expected %s in %s\n", what, expected, actual ? actual : "NULL");
+        failures++;
+    }
+    nomen_string_free(actual);
+}
+
+static void expect(const char *what, bool ok) {
+    if (!ok) {
+        fprintf(stderr, "%s failed\n", what);
+        failures++;
+    }
+}
+
+int main(void) {
+    expect_str("create", nomen_create_op_return("hello-world", PUBKEY),
+               "4e4f4d010060de6fbc4a78209942c62706d904ff9592c2e856f219793f7f73e62fc33bfc1868656c6c6f2d776f726c64");
+    expect_str("nsid", nomen_nsid("hello-world", PUBKEY), "273968a1e7be2ef0acbcae6f61d53e73101e2983");
+    expect_str("fingerprint", nomen_fingerprint("hello-world"), "e5401df4b4");
+    expect_str("normalize", nomen_normalize_name("B\xc3\xbc" "cher"), "xn--bcher-kva");
+    expect_contains("decode", nomen_decode_op_return("4e4f4d0000e5401df4b4273968a1e7be2ef0acbcae6f61d53e73101e2983"),
+                    "\"type\":\"create_v0\"");
+
+    char *message = nomen_transfer_message("hello-world", PUBKEY, PUBKEY,
+                                           "0000000000000000000000000000000000000000000000000000000000000001");
+    expect("transfer message", message != NULL && strlen(message) == 64);
+    nomen_string_free(message);
+
+    expect("valid name", nomen_validate_name("hello-world"));
+    expect("invalid name", !nomen_validate_name("no"));
+    expect("valid record", nomen_validate_record("IP4", "127.0.0.1"));
+
+    expect("invalid pubkey", nomen_nsid("hello-world", "nope") == NULL);
+    expect_str("last error", nomen_last_error(), "invalid public key");
+
+    if (failures == 0) {
+        printf("ok\n");
+    }
+    return failures;
+}
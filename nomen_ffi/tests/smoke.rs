@@ -0,0 +1,60 @@
+use std::{path::PathBuf, process::Command};
+
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Builds `smoke.c` against `include/nomen.h` and the static library cargo built next to this test, and runs it.
+#[test]
+fn test_c_smoke() {
+    // This test runs from target/<profile>/deps.
+    let profile_dir = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .and_then(|deps| deps.parent())
+        .unwrap()
+        .to_path_buf();
+    let binary = profile_dir.join("nomen_ffi_smoke");
+
+    let status = Command::new(std::env::var("CC").unwrap_or_else(|_| "cc".into()))
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(manifest_dir().join("include"))
+        .arg(manifest_dir().join("tests/smoke.c"))
+        .arg(profile_dir.join("libnomen_ffi.a"))
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&binary)
+        .status()
+        .expect("a C compiler is installed");
+    assert!(status.success());
+
+    let output = Command::new(&binary).output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Every exported function is declared in the header.
+#[test]
+fn test_header_declares_exports() {
+    let source = std::fs::read_to_string(manifest_dir().join("src/lib.rs")).unwrap();
+    let header = std::fs::read_to_string(manifest_dir().join("include/nomen.h")).unwrap();
+    let exports: Vec<&str> = source
+        .lines()
+        .filter_map(|line| {
+            line.strip_prefix("pub unsafe extern \"C\" fn ")
+                .or_else(|| line.strip_prefix("pub extern \"C\" fn "))
+        })
+        .filter_map(|rest| rest.split('(').next())
+        .collect();
+    assert!(!exports.is_empty());
+    for export in exports {
+        assert!(
+            header.contains(&format!(" *{export}(")) || header.contains(&format!(" {export}(")),
+            "{export} is missing from nomen.h"
+        );
+    }
+}
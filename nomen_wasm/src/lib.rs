@@ -0,0 +1,208 @@
+//! wasm-bindgen bindings to nomen_core for browser wallets, so they build `OP_RETURN`s with the same code as the
+//! indexer.
+//!
+//! Public keys, signatures, txids, salts and `OP_RETURN`s are hex strings. Keys never enter the bindings: the
+//! `*_message` functions return the 32 byte message for the wallet to sign with BIP340 Schnorr, and the matching
+//! `*_op_return` functions take the signature.
+
+use std::str::FromStr;
+
+use bitcoin::Txid;
+use nomen_core::{
+    CommitBuilder, CreateBuilder, Hash160, Name, NsidBuilder, OpReturn, ReleaseBuilder,
+    RenewBuilder, SignatureV2, TransferAuthorization, TransferBuilder,
+};
+use secp256k1::{schnorr::Signature, XOnlyPublicKey};
+use wasm_bindgen::prelude::*;
+
+fn pubkey(pubkey: &str) -> Result<XOnlyPublicKey, JsError> {
+    XOnlyPublicKey::from_str(pubkey).map_err(|_| JsError::new("invalid public key"))
+}
+
+fn signature(signature: &str) -> Result<Signature, JsError> {
+    Signature::from_str(signature).map_err(|_| JsError::new("invalid signature"))
+}
+
+fn txid(txid: &str) -> Result<Txid, JsError> {
+    Txid::from_str(txid).map_err(|_| JsError::new("invalid txid"))
+}
+
+fn name(name: &str) -> Result<Name, JsError> {
+    Name::from_str(name).map_err(|_| JsError::new("invalid name"))
+}
+
+/// The canonical form of a name, which is the form used on-chain. Unicode names become punycode.
+#[wasm_bindgen(js_name = normalizeName)]
+pub fn normalize_name(name: &str) -> Result<String, JsError> {
+    Ok(self::name(name)?.to_string())
+}
+
+#[wasm_bindgen]
+pub fn fingerprint(name: &str) -> Result<String, JsError> {
+    let name = self::name(name)?;
+    Ok(hex::encode(
+        Hash160::default()
+            .chain_update(name.as_ref().as_bytes())
+            .fingerprint(),
+    ))
+}
+
+#[wasm_bindgen]
+pub fn nsid(name: &str, pubkey: &str) -> Result<String, JsError> {
+    let name = self::name(name)?;
+    Ok(NsidBuilder::new(name.as_ref(), &self::pubkey(pubkey)?)
+        .finalize()
+        .to_string())
+}
+
+#[wasm_bindgen(js_name = nsidBech32)]
+pub fn nsid_bech32(name: &str, pubkey: &str) -> Result<String, JsError> {
+    let name = self::name(name)?;
+    Ok(NsidBuilder::new(name.as_ref(), &self::pubkey(pubkey)?)
+        .finalize()
+        .to_bech32())
+}
+
+#[wasm_bindgen(js_name = createOpReturn)]
+pub fn create_op_return(name: &str, pubkey: &str) -> Result<String, JsError> {
+    let name = self::name(name)?;
+    Ok(hex::encode(
+        CreateBuilder::new(&self::pubkey(pubkey)?, name.as_ref()).v1_op_return(),
+    ))
+}
+
+#[wasm_bindgen(js_name = commitOpReturn)]
+pub fn commit_op_return(name: &str, pubkey: &str, salt: &str) -> Result<String, JsError> {
+    let name = self::name(name)?;
+    let mut bytes = [0; 8];
+    hex::decode_to_slice(salt, &mut bytes)?;
+    Ok(hex::encode(
+        CommitBuilder::with_salt(&self::pubkey(pubkey)?, name.as_ref(), bytes)
+            .commit_op_return()?,
+    ))
+}
+
+#[wasm_bindgen(js_name = revealOpReturn)]
+pub fn reveal_op_return(name: &str, pubkey: &str, salt: &str) -> Result<String, JsError> {
+    let name = self::name(name)?;
+    let mut bytes = [0; 8];
+    hex::decode_to_slice(salt, &mut bytes)?;
+    Ok(hex::encode(
+        CommitBuilder::with_salt(&self::pubkey(pubkey)?, name.as_ref(), bytes)
+            .reveal_op_return()?,
+    ))
+}
+
+#[wasm_bindgen(js_name = transferOpReturn)]
+pub fn transfer_op_return(name: &str, new_owner: &str) -> Result<String, JsError> {
+    let name = self::name(name)?;
+    Ok(hex::encode(
+        TransferBuilder {
+            new_pubkey: &pubkey(new_owner)?,
+            name: name.as_ref(),
+        }
+        .transfer_op_return(),
+    ))
+}
+
+/// The v2 transfer authorisation message, to be signed by the current owner once the transfer is broadcast.
+#[wasm_bindgen(js_name = transferMessage)]
+pub fn transfer_message(
+    name: &str,
+    new_owner: &str,
+    old_owner: &str,
+    transfer_txid: &str,
+) -> Result<String, JsError> {
+    let name = self::name(name)?;
+    let message = TransferAuthorization {
+        name: name.as_ref(),
+        new_owner: &pubkey(new_owner)?,
+        transfer_txid: &txid(transfer_txid)?,
+    }
+    .message(&pubkey(old_owner)?)?;
+    Ok(hex::encode(message.as_ref()))
+}
+
+#[wasm_bindgen(js_name = transferSignatureOpReturn)]
+pub fn transfer_signature_op_return(signature: &str) -> Result<String, JsError> {
+    Ok(hex::encode(
+        SignatureV2::new(&self::signature(signature)?).serialize(),
+    ))
+}
+
+#[wasm_bindgen(js_name = renewMessage)]
+pub fn renew_message(name: &str, owner: &str, renewed_from: u32) -> Result<String, JsError> {
+    let name = self::name(name)?;
+    let owner = pubkey(owner)?;
+    let message = RenewBuilder::new(name.as_ref(), &owner, renewed_from.into()).message()?;
+    Ok(hex::encode(message.as_ref()))
+}
+
+#[wasm_bindgen(js_name = renewOpReturn)]
+pub fn renew_op_return(
+    name: &str,
+    owner: &str,
+    renewed_from: u32,
+    signature: &str,
+) -> Result<String, JsError> {
+    let name = self::name(name)?;
+    let owner = pubkey(owner)?;
+    Ok(hex::encode(
+        RenewBuilder::new(name.as_ref(), &owner, renewed_from.into())
+            .signature_provided_op_return(self::signature(signature)?),
+    ))
+}
+
+#[wasm_bindgen(js_name = releaseMessage)]
+pub fn release_message(name: &str, owner: &str, claim_txid: &str) -> Result<String, JsError> {
+    let name = self::name(name)?;
+    let (owner, claim_txid) = (pubkey(owner)?, txid(claim_txid)?);
+    let message = ReleaseBuilder::new(name.as_ref(), &owner, &claim_txid).message()?;
+    Ok(hex::encode(message.as_ref()))
+}
+
+#[wasm_bindgen(js_name = releaseOpReturn)]
+pub fn release_op_return(
+    name: &str,
+    owner: &str,
+    claim_txid: &str,
+    signature: &str,
+) -> Result<String, JsError> {
+    let name = self::name(name)?;
+    let (owner, claim_txid) = (pubkey(owner)?, txid(claim_txid)?);
+    Ok(hex::encode(
+        ReleaseBuilder::new(name.as_ref(), &owner, &claim_txid)
+            .signature_provided_op_return(self::signature(signature)?),
+    ))
+}
+
+/// Decodes any Nomen `OP_RETURN` to the JSON of [`OpReturn`], as served by the indexer's `/api/op_return/decode`.
+#[wasm_bindgen(js_name = decodeOpReturn)]
+pub fn decode_op_return(op_return: &str) -> Result<String, JsError> {
+    let op_return = OpReturn::try_from(hex::decode(op_return)?.as_slice())?;
+    Ok(serde_json::to_string(&op_return)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBKEY: &str = "60de6fbc4a78209942c62706d904ff9592c2e856f219793f7f73e62fc33bfc18";
+
+    /// `JsError` isn't `Debug`, and can only be built on wasm32.
+    fn ok(result: Result<String, JsError>) -> String {
+        result.ok().expect("binding failed")
+    }
+
+    #[test]
+    fn test_create() {
+        let op_return = ok(create_op_return("hello-world", PUBKEY));
+        assert_eq!(op_return, "4e4f4d010060de6fbc4a78209942c62706d904ff9592c2e856f219793f7f73e62fc33bfc1868656c6c6f2d776f726c64");
+        assert!(ok(decode_op_return(&op_return)).contains(r#""type":"create_v1""#));
+        assert_eq!(
+            ok(nsid("hello-world", PUBKEY)),
+            "273968a1e7be2ef0acbcae6f61d53e73101e2983"
+        );
+        assert_eq!(ok(fingerprint("hello-world")), "e5401df4b4");
+    }
+}
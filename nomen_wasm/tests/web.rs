@@ -0,0 +1,35 @@
+//! The bindings as JavaScript calls them, run with `wasm-bindgen-test-runner`. See docs/DEVELOPMENT.md.
+#![cfg(target_arch = "wasm32")]
+
+use nomen_wasm::*;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const PUBKEY: &str = "60de6fbc4a78209942c62706d904ff9592c2e856f219793f7f73e62fc33bfc18";
+
+#[wasm_bindgen_test]
+fn test_create() {
+    let op_return = create_op_return("hello-world", PUBKEY).unwrap();
+    assert_eq!(op_return, "4e4f4d010060de6fbc4a78209942c62706d904ff9592c2e856f219793f7f73e62fc33bfc1868656c6c6f2d776f726c64");
+    assert!(decode_op_return(&op_return)
+        .unwrap()
+        .contains(r#""type":"create_v1""#));
+    assert_eq!(
+        nsid("hello-world", PUBKEY).unwrap(),
+        "273968a1e7be2ef0acbcae6f61d53e73101e2983"
+    );
+    assert_eq!(fingerprint("hello-world").unwrap(), "e5401df4b4");
+}
+
+#[wasm_bindgen_test]
+fn test_normalize_name() {
+    assert_eq!(normalize_name("Hello-World").unwrap(), "hello-world");
+    assert!(normalize_name("hello world").is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_invalid_input() {
+    assert!(create_op_return("hello-world", "nope").is_err());
+    assert!(commit_op_return("hello-world", PUBKEY, "00").is_err());
+    assert!(transfer_signature_op_return("nope").is_err());
+    assert!(decode_op_return("4e4f4d").is_err());
+}